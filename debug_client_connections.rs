@@ -347,8 +347,10 @@ impl ClientConnectionDebugger {
                 Ok(CallToolResult {
                     content: vec![Content::Text {
                         text: "Test tool executed successfully".to_string(),
+                        annotations: None,
                     }],
                     is_error: false,
+                    ..Default::default()
                 })
             },
         );
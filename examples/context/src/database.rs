@@ -3,7 +3,6 @@
 //! This module handles the database connection and provides
 //! basic database operations.
 
-use crate::models::*;
 use rusqlite::{Connection, Result as SqlResult};
 use std::path::Path;
 
@@ -91,7 +90,7 @@ impl Database {
     }
 
     /// Begin a transaction
-    pub fn begin_transaction(&mut self) -> SqlResult<rusqlite::Transaction> {
+    pub fn begin_transaction(&mut self) -> SqlResult<rusqlite::Transaction<'_>> {
         self.conn.transaction()
     }
 
@@ -101,7 +100,7 @@ impl Database {
             "SELECT sql FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
         )?;
 
-        let schema_iter = stmt.query_map([], |row| Ok(row.get::<_, String>(0)?))?;
+        let schema_iter = stmt.query_map([], |row| row.get::<_, String>(0))?;
 
         let mut schemas = Vec::new();
         for schema in schema_iter {
@@ -117,7 +116,7 @@ impl Database {
             "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
         )?;
 
-        let table_iter = stmt.query_map([], |row| Ok(row.get::<_, String>(0)?))?;
+        let table_iter = stmt.query_map([], |row| row.get::<_, String>(0))?;
 
         let mut tables = Vec::new();
         for table in table_iter {
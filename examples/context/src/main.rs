@@ -3,8 +3,13 @@
 //! This demonstrates how to use the database mapping and services
 //! with your actual database structure.
 
+// The data layer exposes a small library-style API; not every helper is
+// exercised by this demo binary.
+#[allow(dead_code)]
 mod database;
+#[allow(dead_code)]
 mod models;
+#[allow(dead_code)]
 mod services;
 
 use database::Database;
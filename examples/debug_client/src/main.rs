@@ -322,8 +322,10 @@ async fn start_test_server() -> Result<(String, tokio::task::JoinHandle<()>)> {
             Ok(CallToolResult {
                 content: vec![Content::Text {
                     text: "Test tool executed successfully".to_string(),
+                    annotations: None,
                 }],
                 is_error: false,
+                ..Default::default()
             })
         },
     );
@@ -1,6 +1,6 @@
 //! An example MCP client that demonstrates prompt handling.
 
-use mcp_sdk::{Client, Content, NdjsonAdapter, Result};
+use mcp_sdk::{Client, NdjsonAdapter, Result};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -32,10 +32,11 @@ async fn main() -> Result<()> {
 
     println!("[Client] Received prompt result: {:#?}", get_result);
 
-    if let Some(first_message) = get_result.messages.first() {
-        if let Content::Text { text } = &first_message.content {
-            println!("\n✅ Success! Got prompt content: '{}'", text);
-        }
+    if !get_result.messages.is_empty() {
+        println!(
+            "\n✅ Success! Got prompt content:\n{}",
+            get_result.render_text()
+        );
     } else {
         println!("\n✅ Success! Got prompt with no messages.");
     }
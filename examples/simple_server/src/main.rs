@@ -9,9 +9,8 @@ use clap::Parser;
 use mcp_sdk::{
     error::{Error, Result},
     network_adapter::NdjsonAdapter,
-    CallToolResult, ConnectionHandle, Content, GetPromptResult, ListPromptsResult,
-    ListToolsChangedParams, Notification, Prompt, ReadResourceResult, Resource, ResourceContents,
-    Server, TextResourceContents, Tool,
+    CallToolResult, ConnectionHandle, Content, GetPromptResult, ListPromptsResult, Prompt,
+    ReadResourceResult, Resource, ResourceContents, Server, TextResourceContents, Tool,
 };
 use serde_json::json;
 use serde_json::Value;
@@ -43,6 +42,7 @@ async fn list_resources_handler(
         name: format!("hello{}.txt", suffix),
         description: Some("An example resource file.".to_string()),
         mime_type: Some("text/plain".to_string()),
+        annotations: None,
     }])
 }
 
@@ -65,6 +65,7 @@ async fn read_resource_handler(
             mime_type: Some("text/plain".to_string()),
             text: format!("Hello from resource {}!", suffix),
         })],
+        meta: None,
     })
 }
 
@@ -76,9 +77,11 @@ async fn list_prompts_handler(
     Ok(ListPromptsResult {
         prompts: vec![Prompt {
             name: format!("example-prompt{}", suffix),
+            title: None,
             description: Some("An example prompt.".to_string()),
             arguments: None,
         }],
+        meta: None,
     })
 }
 
@@ -93,7 +96,7 @@ async fn get_prompt_handler(
         suffix, name
     );
     Ok(GetPromptResult {
-        description: Some(format!("This is the example prompt{}.", suffix)).into(),
+        description: Some(format!("This is the example prompt{}.", suffix)),
         messages: vec![],
     })
 }
@@ -108,6 +111,7 @@ async fn main() -> Result<()> {
         .register_tool(
             Tool {
                 name: format!("fetch{}", args.suffix),
+                title: None,
                 description: Some("Fetches a website and returns its content".to_string()),
                 input_schema: json!({ "type": "object", "properties": { "url": { "type": "string" } } }),
                 annotations: None,
@@ -123,8 +127,10 @@ async fn main() -> Result<()> {
                         Ok(CallToolResult {
                             content: vec![Content::Text {
                                 text: format!("Mock content of {}", url),
+                                annotations: None,
                             }],
                             is_error: false,
+                            ..Default::default()
                         })
                     }
                 }
@@ -133,24 +139,21 @@ async fn main() -> Result<()> {
         .register_tool(
             Tool {
                 name: "trigger_notification".to_string(),
+                title: None,
                 description: Some("Asks the server to send a 'tools/listChanged' notification.".to_string()),
                 input_schema: json!({ "type": "object" }),
                 annotations: None,
             },
             |handle: ConnectionHandle, _args: Value| async move {
                 info!("Sending 'tools/listChanged' notification...");
-                handle
-                    .send_notification(Notification {
-                        jsonrpc: "2.0".to_string(),
-                        method: "notifications/tools/list_changed".to_string(),
-                        params: Some(ListToolsChangedParams {}),
-                    })
-                    .await?;
+                handle.notify_tools_list_changed().await?;
                 Ok(CallToolResult {
                     content: vec![Content::Text {
                         text: "Notification sent!".to_string(),
+                        annotations: None,
                     }],
                     is_error: false,
+                    ..Default::default()
                 })
             },
         )
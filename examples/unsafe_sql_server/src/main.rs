@@ -67,8 +67,12 @@ async fn get_schema_handler(
     .await.map_err(to_sdk_error)??; // The outer `??` handles JoinError and the inner Result
 
     Ok(CallToolResult {
-        content: vec![Content::Text { text: result_text }],
+        content: vec![Content::Text {
+            text: result_text,
+            annotations: None,
+        }],
         is_error: false,
+        ..Default::default()
     })
 }
 
@@ -138,8 +142,12 @@ async fn execute_sql_handler(
     .map_err(to_sdk_error)??;
 
     Ok(CallToolResult {
-        content: vec![Content::Text { text: result_text }],
+        content: vec![Content::Text {
+            text: result_text,
+            annotations: None,
+        }],
         is_error: false,
+        ..Default::default()
     })
 }
 
@@ -207,6 +207,7 @@ pub fn tool_arguments_derive(input: TokenStream) -> TokenStream {
 
     let mut properties_map_inserts = Vec::new();
     let mut required_field_names = Vec::new();
+    let mut ordered_field_names = Vec::new();
     let mut compile_errors = TokenStream2::new();
 
     for field in fields {
@@ -240,6 +241,7 @@ pub fn tool_arguments_derive(input: TokenStream) -> TokenStream {
                     base_schema_ts
                 };
 
+                ordered_field_names.push(actual_field_name_str.clone());
                 properties_map_inserts.push(quote! {
                     map.insert(#actual_field_name_str.to_string(), #property_schema_ts);
                 });
@@ -279,12 +281,20 @@ pub fn tool_arguments_derive(input: TokenStream) -> TokenStream {
                 });
                 SCHEMA.clone()
             }
+
+            pub fn mcp_arg_order() -> &'static [&'static str] {
+                &[ #(#ordered_field_names),* ]
+            }
         }
 
         impl ::mcp_sdk::ToolArgumentsDescriptor for #name {
             fn mcp_input_schema() -> ::serde_json::Value {
                 Self::mcp_input_schema()
             }
+
+            fn mcp_arg_order() -> &'static [&'static str] {
+                Self::mcp_arg_order()
+            }
         }
     };
 
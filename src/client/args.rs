@@ -0,0 +1,72 @@
+//! A small builder for tool-call arguments.
+
+use serde_json::{Map, Value};
+
+/// Accumulates named arguments into the JSON object sent with a `tools/call`.
+///
+/// Used through [`Client::call_tool_args`](super::Client::call_tool_args), so that
+/// callers don't need to hand-write `serde_json::json!` blobs:
+///
+/// ```
+/// use mcp_sdk::client::ArgsBuilder;
+/// use serde_json::json;
+///
+/// let args = ArgsBuilder::new().arg("url", "https://example.com").arg("n", 3).build();
+/// assert_eq!(args, json!({ "url": "https://example.com", "n": 3 }));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ArgsBuilder {
+    args: Map<String, Value>,
+}
+
+impl ArgsBuilder {
+    /// Creates an empty set of arguments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an argument, replacing any earlier argument with the same name.
+    pub fn arg(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.args.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the accumulated arguments as a JSON object.
+    pub fn build(self) -> Value {
+        Value::Object(self.args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CallToolParams;
+    use serde_json::json;
+
+    #[test]
+    fn test_builder_produces_call_tool_params() {
+        let params = CallToolParams {
+            name: "fetch".to_string(),
+            arguments: ArgsBuilder::new()
+                .arg("url", "https://example.com")
+                .arg("n", 3)
+                .arg("verbose", true)
+                .arg("n", 5)
+                .build(),
+            meta: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            json!({
+                "name": "fetch",
+                "arguments": { "url": "https://example.com", "n": 5, "verbose": true }
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty_builder_is_an_empty_object() {
+        assert_eq!(ArgsBuilder::new().build(), json!({}));
+    }
+}
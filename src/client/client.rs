@@ -1,28 +1,215 @@
 //! Defines the public-facing `Client` struct and its API methods.
 
-use super::session::{ClientSession, NotificationHandler, NotificationHandlerMap, ResponseResult};
+use super::args::ArgsBuilder;
+use super::session::{
+    ClientSession, NotificationHandler, NotificationHandlerMap, RequestHandlerMap, ResponseResult,
+    RootsCache,
+};
 use crate::{
-    error::Result,
-    network_adapter::NetworkAdapter,
+    error::{Error, Result},
+    network_adapter::{NetworkAdapter, StdioAdapter},
     protocol::ProtocolConnection,
     types::{
-        CallToolParams, CallToolResult, ClientCapabilities, GetPromptParams, GetPromptResult,
-        Implementation, InitializeRequestParams, InitializeResult, ListPromptsParams,
-        ListPromptsResult, ListResourcesParams, ListToolsParams, ReadResourceParams,
-        ReadResourceResult, Request, RequestId, Resource, Tool, LATEST_PROTOCOL_VERSION,
+        CallToolParams, CallToolResult, ClientCapabilities, CloseParams, ElicitParams,
+        ElicitResult, ErrorData, GetPromptParams, GetPromptResult, Implementation,
+        InitializeRequestParams, InitializeResult, ListPromptsParams, ListPromptsResult,
+        ListResourceTemplatesParams, ListResourceTemplatesResult, ListResourcesParams,
+        ListToolsParams, Notification, PartialResultParams, ReadResourceParams, ReadResourceResult,
+        Request, RequestId, Resource, ResourceTemplate, Root, RootsCapability, ServerCapabilities,
+        Tool, INVALID_PARAMS, LATEST_PROTOCOL_VERSION, METHOD_NOT_FOUND,
     },
 };
 use dashmap::DashMap;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Stdio;
 use std::sync::{
-    atomic::{AtomicI64, Ordering},
-    Arc,
+    atomic::{AtomicI64, AtomicUsize, Ordering},
+    Arc, RwLock,
 };
+use std::time::Duration;
+use tokio::process::{Child, Command};
 use tokio::sync::{mpsc, oneshot, Mutex};
 use tokio::task::JoinHandle;
-use tracing::{error, info};
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+/// How long [`Client::close`] waits for the connection to drain.
+pub const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything a server offers through resources, as returned by
+/// [`Client::discover_resources`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResourceCatalog {
+    /// Concrete resources from `resources/list`.
+    pub resources: Vec<Resource>,
+    /// Parameterized resources from `resources/templates/list`.
+    pub templates: Vec<ResourceTemplate>,
+}
+
+/// Keeps a request counted in [`Client`]'s pending requests while it is alive.
+struct PendingSlot<'a>(&'a AtomicUsize);
+
+impl Drop for PendingSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+type RequestSender = mpsc::Sender<(Request<Value>, oneshot::Sender<ResponseResult>)>;
+
+// Opens a new connection for a client set up with `Client::with_reconnect`.
+type Reconnector =
+    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = Result<Link>> + Send>> + Send + Sync>;
+
+/// The parts of a [`Client`] bound to a single connection.
+struct Link {
+    request_sender: RequestSender,
+    /// Pre-serialized messages (e.g. notifications) for the server.
+    outgoing_sender: mpsc::Sender<String>,
+    session_handle: JoinHandle<()>,
+    /// Tells the session task to wind down, see [`Client::close`].
+    shutdown_sender: Option<oneshot::Sender<()>>,
+}
+
+impl Link {
+    /// Spawns the background task that manages the connection over `adapter`.
+    fn spawn<A>(
+        adapter: A,
+        notification_handlers: &NotificationHandlerMap,
+        roots: &RootsCache,
+        request_handlers: &RequestHandlerMap,
+    ) -> Self
+    where
+        A: NetworkAdapter + 'static,
+    {
+        let connection = ProtocolConnection::new(adapter);
+        let (request_sender, request_receiver) = mpsc::channel(32);
+        let (outgoing_sender, outgoing_receiver) = mpsc::channel(32);
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+        let session = ClientSession::new(
+            connection,
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::clone(notification_handlers),
+            request_receiver,
+            outgoing_receiver,
+            Arc::clone(roots),
+            Arc::clone(request_handlers),
+        )
+        .with_shutdown(shutdown_receiver);
+
+        Self {
+            request_sender,
+            outgoing_sender,
+            session_handle: tokio::spawn(session.run()),
+            shutdown_sender: Some(shutdown_sender),
+        }
+    }
+}
+
+/// Performs the `initialize` handshake over a freshly spawned connection,
+/// advertising `capabilities`.
+async fn handshake(
+    request_sender: &RequestSender,
+    outgoing_sender: &mpsc::Sender<String>,
+    id: RequestId,
+    capabilities: ClientCapabilities,
+) -> Result<InitializeResult> {
+    let init_params = InitializeRequestParams {
+        protocol_version: LATEST_PROTOCOL_VERSION.to_string(),
+        client_info: Implementation {
+            name: "mcp-rust-sdk-client".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        capabilities,
+    };
+    let span = info_span!("mcp_client_request", method = "initialize", id = ?id);
+    let request = Request {
+        jsonrpc: "2.0".to_string(),
+        id,
+        method: "initialize".to_string(),
+        params: Some(serde_json::to_value(init_params)?),
+    };
+    let init_value = async {
+        let (tx, rx) = oneshot::channel();
+        request_sender.send((request, tx)).await?;
+        rx.await?
+    }
+    .instrument(span)
+    .await?;
+    let init_response: InitializeResult = serde_json::from_value(init_value)
+        .map_err(|e| Error::Handshake(format!("invalid initialize response: {}", e)))?;
+
+    outgoing_sender
+        .send(notification_json("notifications/initialized")?)
+        .await?;
+
+    info!(
+        "[Client] Handshake successful. Server: {:?}",
+        init_response.server_info
+    );
+    Ok(init_response)
+}
+
+/// Starts pinging the server over `link` every `interval`, aborting its
+/// session once a ping fails or goes unanswered for a whole `interval`.
+fn spawn_keepalive(link: &Link, interval: Duration) -> JoinHandle<()> {
+    let request_sender = link.request_sender.clone();
+    let session = link.session_handle.abort_handle();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await; // The first tick completes immediately.
+        for n in 1.. {
+            ticker.tick().await;
+            let request = Request {
+                jsonrpc: "2.0".to_string(),
+                // Distinct from the client's own ids, whatever their style.
+                id: RequestId::Str(format!("keepalive-{}", n)),
+                method: "ping".to_string(),
+                params: None,
+            };
+            let (tx, rx) = oneshot::channel();
+            if request_sender.send((request, tx)).await.is_err() {
+                return; // The connection is already closed.
+            }
+            match tokio::time::timeout(interval, rx).await {
+                Ok(Ok(Ok(_))) => debug!("[Client] Keepalive ping {} answered", n),
+                Ok(Ok(Err(e))) => {
+                    warn!("[Client] Keepalive ping failed, closing connection: {}", e);
+                    break;
+                }
+                Ok(Err(_)) => return,
+                Err(_) => {
+                    warn!(
+                        "[Client] Keepalive ping unanswered after {:?}, closing connection",
+                        interval
+                    );
+                    break;
+                }
+            }
+        }
+        session.abort();
+    })
+}
+
+/// Whether repeating a request for `method` is harmless, making it safe to
+/// retry after the connection drops.
+fn is_idempotent(method: &str) -> bool {
+    method == "ping" || method == "resources/read" || method.ends_with("/list")
+}
+
+/// Serializes a parameterless notification.
+fn notification_json(method: &str) -> Result<String> {
+    let notification: Notification<Value> = Notification {
+        jsonrpc: "2.0".to_string(),
+        method: method.to_string(),
+        params: None,
+    };
+    Ok(serde_json::to_string(&notification)?)
+}
 
 /// A high-level, asynchronous client for interacting with an MCP server.
 ///
@@ -60,9 +247,35 @@ use tracing::{error, info};
 /// ```
 pub struct Client {
     next_request_id: AtomicI64,
-    request_sender: mpsc::Sender<(Request<Value>, oneshot::Sender<ResponseResult>)>,
+    /// Set by [`Client::with_string_ids`] to send string ids instead of numbers.
+    id_prefix: Option<String>,
     notification_handlers: NotificationHandlerMap,
-    session_handle: JoinHandle<()>,
+    roots: RootsCache,
+    request_handlers: RequestHandlerMap,
+    /// The current connection; replaced when the client reconnects.
+    link: RwLock<Link>,
+    /// What the client advertises in `initialize`, kept to redo the handshake.
+    capabilities: ClientCapabilities,
+    /// Set by [`Client::with_reconnect`].
+    reconnector: Option<Reconnector>,
+    /// Held while reconnecting, so concurrent retries reconnect only once.
+    reconnecting: Mutex<()>,
+    /// Set by [`Client::with_keepalive`].
+    keepalive_interval: Option<Duration>,
+    /// The ping task for the current connection; restarted when the client
+    /// reconnects.
+    keepalive_handle: std::sync::Mutex<Option<JoinHandle<()>>>,
+    /// The server's answer to the `initialize` handshake, or a placeholder for
+    /// [`Client::new_without_handshake`]. Always set once a `Client` is returned,
+    /// and replaced when the client reconnects.
+    initialize_result: RwLock<Option<Arc<InitializeResult>>>,
+    /// Requests sent and still awaiting a response.
+    in_flight: AtomicUsize,
+    /// Set by [`Client::max_pending`] to cap `in_flight`.
+    max_pending: Option<usize>,
+    /// The server subprocess, when connected through [`Client::connect_stdio`].
+    /// It is spawned with `kill_on_drop`, so dropping the client terminates it.
+    child: Option<Child>,
 }
 
 impl Client {
@@ -75,61 +288,223 @@ impl Client {
     /// # Arguments
     ///
     /// * `addr` - The network address of the MCP server (e.g., "127.0.0.1:8080").
+    ///
+    /// The `roots` capability is not advertised; use [`Client::new_with_roots`] to
+    /// expose filesystem roots to the server.
     pub async fn new<A>(adapter: A) -> Result<Self>
     where
         // The adapter must be able to connect and be used in an async task.
         A: NetworkAdapter + 'static,
     {
-        let connection = ProtocolConnection::new(adapter);
+        let capabilities = ClientCapabilities {
+            tools: Some(crate::types::ToolsCapability {
+                list_changed: Some(true),
+            }),
+            roots: None,
+        };
+        Self::with_capabilities(adapter, capabilities, Vec::new()).await
+    }
 
-        let pending_requests = Arc::new(Mutex::new(HashMap::new()));
-        let notification_handlers = Arc::new(DashMap::new());
-        let (request_sender, request_receiver) = mpsc::channel(32);
+    /// Like [`Client::new`], but also advertises the `roots` capability and
+    /// exposes `roots` to the server from the start. Change them later with
+    /// [`Self::set_roots`].
+    pub async fn new_with_roots<A>(adapter: A, roots: Vec<Root>) -> Result<Self>
+    where
+        A: NetworkAdapter + 'static,
+    {
+        let capabilities = ClientCapabilities {
+            tools: Some(crate::types::ToolsCapability {
+                list_changed: Some(true),
+            }),
+            roots: Some(RootsCapability {
+                list_changed: Some(true),
+            }),
+        };
+        Self::with_capabilities(adapter, capabilities, roots).await
+    }
 
-        let session = ClientSession::new(
-            connection,
-            pending_requests,
-            Arc::clone(&notification_handlers),
-            request_receiver,
-        );
+    /// Like [`Client::new`], but advertises no optional capabilities during the
+    /// handshake.
+    ///
+    /// Some servers change behavior based on what the client claims to support;
+    /// this gives them the most conservative picture, which is mainly useful for
+    /// conformance testing.
+    pub async fn new_minimal<A>(adapter: A) -> Result<Self>
+    where
+        A: NetworkAdapter + 'static,
+    {
+        Self::with_capabilities(adapter, ClientCapabilities::default(), Vec::new()).await
+    }
 
-        let session_handle = tokio::spawn(session.run());
+    /// Spawns the connection task without sending `initialize`, for connections
+    /// whose handshake has already been done, e.g. by a proxy in front of the
+    /// server, or for servers configured to skip it.
+    ///
+    /// **Use with care:** MCP servers reject every request until they have been
+    /// initialized, so against an ordinary server each call made through this
+    /// client fails. Nothing is known about the server either:
+    /// [`Self::initialize_result`] is an empty placeholder advertising no
+    /// capabilities, so [`Self::server_capabilities`] and
+    /// [`Self::supported_methods`] say nothing useful.
+    pub fn new_without_handshake<A>(adapter: A) -> Self
+    where
+        A: NetworkAdapter + 'static,
+    {
+        let mut client = Self::spawn(adapter, ClientCapabilities::default());
+        *client.initialize_result.get_mut().unwrap() = Some(Arc::new(InitializeResult {
+            protocol_version: LATEST_PROTOCOL_VERSION.to_string(),
+            capabilities: ServerCapabilities::default(),
+            server_info: Implementation {
+                name: String::new(),
+                version: String::new(),
+            },
+            instructions: None,
+        }));
+        client
+    }
 
-        let client = Self {
-            next_request_id: AtomicI64::new(1), // Start subsequent requests from 1
-            request_sender,
+    /// Spawns the connection task and performs the handshake, advertising
+    /// `capabilities` and exposing `roots`.
+    async fn with_capabilities<A>(
+        adapter: A,
+        capabilities: ClientCapabilities,
+        roots: Vec<Root>,
+    ) -> Result<Self>
+    where
+        A: NetworkAdapter + 'static,
+    {
+        let mut client = Self::spawn(adapter, capabilities.clone());
+        *client.roots.write().unwrap() = roots;
+        let init_response = handshake(
+            &client.request_sender(),
+            &client.outgoing_sender(),
+            client.new_request_id(),
+            capabilities,
+        )
+        .await?;
+        *client.initialize_result.get_mut().unwrap() = Some(Arc::new(init_response));
+
+        Ok(client)
+    }
+
+    /// Spawns the background task that manages the connection over `adapter`.
+    fn spawn<A>(adapter: A, capabilities: ClientCapabilities) -> Self
+    where
+        A: NetworkAdapter + 'static,
+    {
+        let notification_handlers = Arc::new(DashMap::new());
+        let roots: RootsCache = Arc::new(RwLock::new(Vec::new()));
+        let request_handlers: RequestHandlerMap = Arc::new(DashMap::new());
+        let link = Link::spawn(adapter, &notification_handlers, &roots, &request_handlers);
+
+        Self {
+            next_request_id: AtomicI64::new(0), // The handshake takes id 0
+            id_prefix: None,
             notification_handlers,
-            session_handle,
-        };
+            roots,
+            request_handlers,
+            link: RwLock::new(link),
+            capabilities,
+            reconnector: None,
+            reconnecting: Mutex::new(()),
+            keepalive_interval: None,
+            keepalive_handle: std::sync::Mutex::new(None),
+            initialize_result: RwLock::new(None),
+            in_flight: AtomicUsize::new(0),
+            max_pending: None,
+            child: None,
+        }
+    }
 
-        // Perform the MCP initialize handshake.
-        let init_params = InitializeRequestParams {
-            protocol_version: LATEST_PROTOCOL_VERSION.to_string(),
-            client_info: Implementation {
-                name: "mcp-rust-sdk-client".to_string(),
-                version: env!("CARGO_PKG_VERSION").to_string(),
-            },
-            capabilities: ClientCapabilities {
-                tools: Some(crate::types::ToolsCapability {
-                    list_changed: Some(true),
-                }),
-                ..Default::default()
-            },
-        };
+    /// Sender for requests over the current connection.
+    fn request_sender(&self) -> RequestSender {
+        self.link.read().unwrap().request_sender.clone()
+    }
 
-        // The first request must have ID 0.
-        let init_response: InitializeResult = client
-            .send_request_with_id(RequestId::Num(0), "initialize", init_params)
-            .await?;
+    /// Sender for pre-serialized messages over the current connection.
+    fn outgoing_sender(&self) -> mpsc::Sender<String> {
+        self.link.read().unwrap().outgoing_sender.clone()
+    }
 
-        info!(
-            "[Client] Handshake successful. Server: {:?}",
-            init_response.server_info
-        );
+    /// Spawns an MCP server as a subprocess and connects to it over its stdio.
+    ///
+    /// The child's stdin and stdout are piped through a [`StdioAdapter`] while its
+    /// stderr is inherited, so server logs still reach the terminal. The handshake
+    /// is performed before returning. The child process is killed when the returned
+    /// `Client` is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The program to execute (e.g. a path to a server binary).
+    /// * `args` - The arguments passed to the program.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process cannot be spawned or the handshake fails.
+    pub async fn connect_stdio<I, S>(command: impl AsRef<OsStr>, args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()?;
 
+        let child_stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| Error::Other("Failed to capture child process stdin".into()))?;
+        let child_stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| Error::Other("Failed to capture child process stdout".into()))?;
+
+        let mut client = Self::new(StdioAdapter::from_child(child_stdin, child_stdout)).await?;
+        client.child = Some(child);
         Ok(client)
     }
 
+    /// Returns the server's full response to the `initialize` handshake.
+    ///
+    /// For a client created with [`Self::new_without_handshake`] this is an empty
+    /// placeholder. After a reconnect this is the new server's response.
+    ///
+    /// The result is shared, not copied, so this is cheap to call; a reconnect
+    /// replaces it rather than changing the one returned here.
+    pub fn initialize_result(&self) -> Arc<InitializeResult> {
+        let result = self.initialize_result.read().unwrap();
+        Arc::clone(
+            result
+                .as_ref()
+                .expect("initialize_result is set before a Client is returned"),
+        )
+    }
+
+    /// Returns a copy of the name and version the server reported during the
+    /// handshake. Read it through [`Self::initialize_result`] to avoid the copy.
+    pub fn server_info(&self) -> Implementation {
+        self.initialize_result().server_info.clone()
+    }
+
+    /// Returns a copy of the capabilities the server advertised during the
+    /// handshake. Read them through [`Self::initialize_result`] to avoid the copy.
+    ///
+    /// Use this to avoid calling methods the server doesn't support, e.g. only
+    /// calling [`Self::list_prompts`] when `prompts` is present.
+    pub fn server_capabilities(&self) -> ServerCapabilities {
+        self.initialize_result().capabilities.clone()
+    }
+
+    /// Lists the request methods the server's advertised capabilities imply it
+    /// supports, e.g. `tools/list` and `tools/call` for a server offering tools.
+    pub fn supported_methods(&self) -> Vec<String> {
+        methods_for_capabilities(&self.initialize_result().capabilities)
+    }
+
     /// Registers a handler for the `tools/listChanged` notification.
     ///
     /// The provided closure will be executed whenever the server sends a notification
@@ -160,10 +535,233 @@ impl Client {
             wrapped_handler,
         );
     }
-    /// Sends a generic request to the server and awaits the response.
+
+    /// Registers a handler for the chunks a tool streams with
+    /// `notifications/tools/partial_result` before its final result.
+    ///
+    /// Chunks arrive in order and before the matching [`Self::call_tool`] call
+    /// returns; `request_id` tells concurrent calls apart. The handler runs on
+    /// the connection's task, so it should hand chunks off rather than block.
+    pub fn on_partial_result<F>(&self, handler: F)
+    where
+        F: Fn(PartialResultParams) + Send + Sync + 'static,
+    {
+        let wrapped_handler: NotificationHandler = Arc::new(move |params: Value| {
+            match serde_json::from_value::<PartialResultParams>(params) {
+                Ok(params) => handler(params),
+                Err(e) => error!("[Client] Failed to deserialize partial result: {}", e),
+            }
+        });
+        self.notification_handlers.insert(
+            "notifications/tools/partial_result".to_string(),
+            wrapped_handler,
+        );
+    }
+
+    /// Registers a handler for the `notifications/close` a server sends when it
+    /// closes the connection on purpose, carrying a code and a reason.
+    ///
+    /// The handler runs on the connection's task as soon as the notification is
+    /// read, so it has run by the time the connection is closed and requests
+    /// start failing.
+    pub fn on_close<F>(&self, handler: F)
+    where
+        F: Fn(CloseParams) + Send + Sync + 'static,
+    {
+        let wrapped_handler: NotificationHandler =
+            Arc::new(
+                move |params: Value| match serde_json::from_value::<CloseParams>(params) {
+                    Ok(params) => handler(params),
+                    Err(e) => error!("[Client] Failed to deserialize close reason: {}", e),
+                },
+            );
+        self.notification_handlers
+            .insert("notifications/close".to_string(), wrapped_handler);
+    }
+
+    /// Registers a handler for requests the server sends with the given `method`,
+    /// e.g. `sampling/createMessage`.
+    ///
+    /// The handler receives the request's `params` (`Value::Null` when absent) and
+    /// its result is sent back as the response. An [`Error::JsonRpc`] is returned to
+    /// the server as-is; any other error becomes an internal error. Handlers run on
+    /// their own task, so they may call back into the server. A handler registered
+    /// for `ping` or `roots/list` takes over from the built-in answer.
+    pub fn on_request<F, Fut>(&self, method: &str, handler: F)
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        self.request_handlers.insert(
+            method.to_string(),
+            Arc::new(move |params| Box::pin(handler(params))),
+        );
+    }
+
+    /// Registers the handler that answers the server's `elicitation/create` requests,
+    /// typically by prompting the user for input matching `requested_schema`.
+    pub fn on_elicit<F, Fut>(&self, handler: F)
+    where
+        F: Fn(ElicitParams) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ElicitResult>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.on_request("elicitation/create", move |params| {
+            let handler = Arc::clone(&handler);
+            async move {
+                let params: ElicitParams = serde_json::from_value(params).map_err(|e| {
+                    Error::JsonRpc(ErrorData {
+                        code: INVALID_PARAMS,
+                        message: format!("Invalid elicitation params: {}", e),
+                        data: None,
+                    })
+                })?;
+                Ok(serde_json::to_value(handler(params).await?)?)
+            }
+        });
+    }
+
+    /// Replaces the roots exposed to the server and notifies it of the change.
+    ///
+    /// Servers only know to ask for roots when the client advertised them, see
+    /// [`Client::new_with_roots`].
+    ///
+    /// Subsequent `roots/list` requests from the server are answered from this
+    /// list directly, so it is safe for servers to ask for it as often as they like.
+    pub async fn set_roots(&self, roots: Vec<Root>) -> Result<()> {
+        *self.roots.write().unwrap() = roots;
+        self.send_notification("notifications/roots/list_changed")
+            .await
+    }
+
+    /// Queues a parameterless notification for the server.
+    async fn send_notification(&self, method: &str) -> Result<()> {
+        self.outgoing_sender()
+            .send(notification_json(method)?)
+            .await?;
+        Ok(())
+    }
+
+    /// Switches to string request ids of the form `"{prefix}-{counter}"`, for
+    /// servers or proxies that don't accept numeric ids.
+    ///
+    /// Applies to every request sent after this call, including the `initialize`
+    /// request of a reconnect. The one already sent by [`Client::new`] keeps its
+    /// numeric id `0`.
+    pub fn with_string_ids(mut self, prefix: impl Into<String>) -> Self {
+        self.id_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Caps how many requests may await a response at once. A request made while
+    /// `max` are pending fails right away with [`Error::TooManyPendingRequests`]
+    /// instead of being sent, so a server that stops answering can't make the
+    /// client queue requests without bound.
+    pub fn max_pending(mut self, max: usize) -> Self {
+        self.max_pending = Some(max);
+        self
+    }
+
+    /// Lets the client reconnect when its connection is lost: `connect` is called
+    /// to open a new transport, over which the `initialize` handshake is redone.
+    ///
+    /// Reconnecting only happens on behalf of [`Client::send_request_retrying`]
+    /// and [`Client::call_tool_retrying`]; other requests fail as before when
+    /// the connection is gone.
+    pub fn with_reconnect<A, F, Fut>(mut self, connect: F) -> Self
+    where
+        A: NetworkAdapter + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<A>> + Send + 'static,
+    {
+        let notification_handlers = Arc::clone(&self.notification_handlers);
+        let roots = Arc::clone(&self.roots);
+        let request_handlers = Arc::clone(&self.request_handlers);
+        self.reconnector = Some(Arc::new(move || {
+            let adapter = connect();
+            let notification_handlers = Arc::clone(&notification_handlers);
+            let roots = Arc::clone(&roots);
+            let request_handlers = Arc::clone(&request_handlers);
+            Box::pin(async move {
+                let adapter = adapter.await?;
+                Ok(Link::spawn(
+                    adapter,
+                    &notification_handlers,
+                    &roots,
+                    &request_handlers,
+                ))
+            })
+        }));
+        self
+    }
+
+    /// Sends a `ping` to the server every `interval` so that idle connections are
+    /// not silently dropped by NATs or load balancers.
+    ///
+    /// A ping that fails or goes unanswered for a whole `interval` means the server
+    /// is gone: the connection is closed, and requests made afterwards fail with
+    /// [`Error::ChannelClosed`]. A client set up with [`Client::with_reconnect`]
+    /// keeps pinging over each new connection.
+    pub fn with_keepalive(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = Some(interval);
+        let keepalive = spawn_keepalive(&self.link.read().unwrap(), interval);
+        if let Some(previous) = self.keepalive_handle.get_mut().unwrap().replace(keepalive) {
+            previous.abort();
+        }
+        self
+    }
+
+    /// Closes the connection gracefully.
+    ///
+    /// Queued notifications are written, requests still awaiting a response get
+    /// it, and server requests being handled are answered before the connection
+    /// is closed. If that takes longer than [`CLOSE_TIMEOUT`], the connection is
+    /// cut and [`Error::Timeout`] is returned. MCP has no shutdown message; the
+    /// server learns of the close when the transport ends.
+    ///
+    /// Dropping a `Client` remains a fallback that closes the connection right
+    /// away, without any of this.
+    pub async fn close(mut self) -> Result<()> {
+        if let Some(keepalive) = self.keepalive_handle.get_mut().unwrap().take() {
+            keepalive.abort();
+        }
+        let link = self.link.get_mut().unwrap();
+        if let Some(shutdown) = link.shutdown_sender.take() {
+            // The session may already be gone, in which case there is nothing to drain.
+            let _ = shutdown.send(());
+        }
+        match tokio::time::timeout(CLOSE_TIMEOUT, &mut link.session_handle).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(Error::Other(format!("Client session task failed: {}", e))),
+            Err(_) => {
+                warn!(
+                    "[Client] Session not drained after {:?}, closing connection",
+                    CLOSE_TIMEOUT
+                );
+                link.session_handle.abort();
+                Err(Error::Timeout)
+            }
+        }
+    }
+
+    /// Generates the id for the next outgoing request.
     fn new_request_id(&self) -> RequestId {
         let id = self.next_request_id.fetch_add(1, Ordering::SeqCst);
-        RequestId::Num(id)
+        match &self.id_prefix {
+            Some(prefix) => RequestId::Str(format!("{}-{}", prefix, id)),
+            None => RequestId::Num(id),
+        }
+    }
+
+    /// Counts a request as pending until the returned guard is dropped, failing
+    /// if [`Self::max_pending`] requests are pending already.
+    fn reserve_pending_slot(&self) -> Result<PendingSlot<'_>> {
+        let pending = self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let slot = PendingSlot(&self.in_flight);
+        match self.max_pending {
+            Some(max) if pending >= max => Err(Error::TooManyPendingRequests(max)),
+            _ => Ok(slot),
+        }
     }
 
     /// Sends a generic request to the server and awaits the response.
@@ -182,6 +780,8 @@ impl Client {
         P: serde::Serialize,
         R: DeserializeOwned,
     {
+        // Pairs up with the server's `mcp_request` span through the shared id.
+        let span = info_span!("mcp_client_request", method = %method, id = ?id);
         let request_payload = Request {
             jsonrpc: "2.0".to_string(),
             id,
@@ -189,42 +789,248 @@ impl Client {
             params: Some(serde_json::to_value(params)?),
         };
 
-        let (tx, rx) = oneshot::channel();
-        self.request_sender.send((request_payload, tx)).await?;
-        let response_val = rx.await??;
-        Ok(serde_json::from_value(response_val)?)
+        async {
+            let _slot = self.reserve_pending_slot()?;
+            let (tx, rx) = oneshot::channel();
+            self.request_sender().send((request_payload, tx)).await?;
+            let response_val = rx.await??;
+            Ok(serde_json::from_value(response_val)?)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Replaces a closed connection with a new one from the reconnector.
+    async fn reconnect(&self, reconnector: &Reconnector) -> Result<()> {
+        let _guard = self.reconnecting.lock().await;
+        if !self.request_sender().is_closed() {
+            // Another request reconnected while we waited for the lock.
+            return Ok(());
+        }
+        info!("[Client] Connection lost, reconnecting");
+        let link = reconnector().await?;
+        let init_response = handshake(
+            &link.request_sender,
+            &link.outgoing_sender,
+            self.new_request_id(),
+            self.capabilities.clone(),
+        )
+        .await?;
+        *self.initialize_result.write().unwrap() = Some(Arc::new(init_response));
+        if let Some(interval) = self.keepalive_interval {
+            let keepalive = spawn_keepalive(&link, interval);
+            if let Some(previous) = self.keepalive_handle.lock().unwrap().replace(keepalive) {
+                previous.abort();
+            }
+        }
+        let old = std::mem::replace(&mut *self.link.write().unwrap(), link);
+        old.session_handle.abort();
+        Ok(())
+    }
+
+    /// Runs `attempt`, reconnecting and running it again up to `retries` times
+    /// while it fails because the connection was lost.
+    async fn retrying<R, F, Fut>(&self, retries: usize, attempt: F) -> Result<R>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        let mut remaining = retries;
+        loop {
+            match attempt().await {
+                Err(e @ (Error::ConnectionClosed | Error::ChannelClosed)) => {
+                    let Some(reconnector) = self.reconnector.as_ref().filter(|_| remaining > 0)
+                    else {
+                        return Err(e);
+                    };
+                    remaining -= 1;
+                    warn!("[Client] Request failed ({}), retrying after reconnect", e);
+                    self.reconnect(reconnector).await?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends a request, and if the connection is lost before the response
+    /// arrives, reconnects (see [`Client::with_reconnect`]) and sends it again,
+    /// up to `retries` times.
+    ///
+    /// Only methods that are safe to repeat are retried: `ping`, the `*/list`
+    /// methods and `resources/read`. Any other method is sent once; use
+    /// [`Client::call_tool_retrying`] to opt a tool call in explicitly.
+    pub async fn send_request_retrying<R>(
+        &self,
+        method: &str,
+        params: Value,
+        retries: usize,
+    ) -> Result<R>
+    where
+        R: DeserializeOwned,
+    {
+        let retries = if is_idempotent(method) { retries } else { 0 };
+        self.retrying(retries, || self.send_request(method, params.clone()))
+            .await
     }
 
     // --- Public API Methods ---
 
     /// Sends a `tools/list` request to the server to get a list of available tools.
     pub async fn list_tools(&self) -> Result<Vec<Tool>> {
-        let list_tools_result: crate::types::ListToolsResult =
-            self.send_request("tools/list", ListToolsParams {}).await?;
+        let list_tools_result: crate::types::ListToolsResult = self
+            .send_request("tools/list", ListToolsParams::default())
+            .await?;
         Ok(list_tools_result.tools)
     }
 
+    /// Lists the server's tools as a map from tool name to description, e.g. for
+    /// showing a menu of what the server offers.
+    pub async fn tool_map(&self) -> Result<HashMap<String, Option<String>>> {
+        Ok(self
+            .list_tools()
+            .await?
+            .into_iter()
+            .map(|tool| (tool.name, tool.description))
+            .collect())
+    }
+
+    /// Looks up a single tool by name, returning `None` when the server doesn't
+    /// offer it.
+    pub async fn find_tool(&self, name: &str) -> Result<Option<Tool>> {
+        Ok(self
+            .list_tools()
+            .await?
+            .into_iter()
+            .find(|tool| tool.name == name))
+    }
+
     /// Sends a `tools/call` request to execute a specific tool on the server.
     pub async fn call_tool(&self, name: String, arguments: Value) -> Result<CallToolResult> {
-        self.send_request("tools/call", CallToolParams { name, arguments })
+        self.send_request(
+            "tools/call",
+            CallToolParams {
+                name,
+                arguments,
+                meta: None,
+            },
+        )
+        .await
+    }
+
+    /// Like [`Client::call_tool`], also sending `meta` as the request's `_meta`,
+    /// e.g. `{"progressToken": 1}` to ask for progress notifications.
+    pub async fn call_tool_with_meta(
+        &self,
+        name: String,
+        arguments: Value,
+        meta: Value,
+    ) -> Result<CallToolResult> {
+        self.send_request(
+            "tools/call",
+            CallToolParams {
+                name,
+                arguments,
+                meta: Some(meta),
+            },
+        )
+        .await
+    }
+
+    /// Like [`Client::call_tool`], but reconnects and calls the tool again, up to
+    /// `retries` times, if the connection is lost before the result arrives.
+    ///
+    /// The server may have run the tool before the connection dropped, so only
+    /// use this for tools that are safe to run more than once.
+    pub async fn call_tool_retrying(
+        &self,
+        name: String,
+        arguments: Value,
+        retries: usize,
+    ) -> Result<CallToolResult> {
+        self.retrying(retries, || self.call_tool(name.clone(), arguments.clone()))
             .await
     }
 
+    /// Calls a tool with arguments assembled through an [`ArgsBuilder`].
+    ///
+    /// ```no_run
+    /// # async fn example(client: &mcp_sdk::Client) -> mcp_sdk::Result<()> {
+    /// let result = client
+    ///     .call_tool_args("fetch", |b| b.arg("url", "https://example.com").arg("n", 3))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_tool_args<F>(
+        &self,
+        name: impl Into<String>,
+        build: F,
+    ) -> Result<CallToolResult>
+    where
+        F: FnOnce(ArgsBuilder) -> ArgsBuilder,
+    {
+        let arguments = build(ArgsBuilder::new()).build();
+        self.call_tool(name.into(), arguments).await
+    }
+
     /// Sends a `resources/list` request to get a list of available resources.
     pub async fn list_resources(&self) -> Result<Vec<Resource>> {
-        self.send_request("resources/list", ListResourcesParams {})
+        self.send_request("resources/list", ListResourcesParams::default())
             .await
     }
 
+    /// Lists the server's resources, keeping only those whose `mime_type` starts
+    /// with `mime_prefix` (e.g. `"image/"`).
+    ///
+    /// Resources without a declared MIME type are never included.
+    pub async fn list_resources_by_mime(&self, mime_prefix: &str) -> Result<Vec<Resource>> {
+        let resources = self.list_resources().await?;
+        Ok(resources
+            .into_iter()
+            .filter(|resource| {
+                resource
+                    .mime_type
+                    .as_deref()
+                    .is_some_and(|mime| mime.starts_with(mime_prefix))
+            })
+            .collect())
+    }
+
+    /// Sends a `resources/templates/list` request to get the server's resource templates.
+    pub async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>> {
+        let result: ListResourceTemplatesResult = self
+            .send_request(
+                "resources/templates/list",
+                ListResourceTemplatesParams::default(),
+            )
+            .await?;
+        Ok(result.resource_templates)
+    }
+
+    /// Fetches both the concrete resources and the resource templates of the server.
+    ///
+    /// The two lists are requested concurrently. A server that implements only one
+    /// of `resources/list` and `resources/templates/list` answers the other with
+    /// "method not found", which is treated as an empty list; any other error is
+    /// returned.
+    pub async fn discover_resources(&self) -> Result<ResourceCatalog> {
+        let (resources, templates) =
+            tokio::join!(self.list_resources(), self.list_resource_templates());
+        Ok(ResourceCatalog {
+            resources: empty_if_unsupported(resources)?,
+            templates: empty_if_unsupported(templates)?,
+        })
+    }
+
     /// Sends a `resources/read` request to get the content of a specific resource.
     pub async fn read_resource(&self, uri: String) -> Result<ReadResourceResult> {
-        self.send_request("resources/read", ReadResourceParams { uri })
+        self.send_request("resources/read", ReadResourceParams { uri, meta: None })
             .await
     }
 
     /// Sends a `prompts/list` request to get a list of available prompt templates.
     pub async fn list_prompts(&self) -> Result<ListPromptsResult> {
-        self.send_request("prompts/list", ListPromptsParams {})
+        self.send_request("prompts/list", ListPromptsParams::default())
             .await
     }
 
@@ -239,9 +1045,48 @@ impl Client {
     }
 }
 
+/// Maps a "method not found" error to an empty list.
+fn empty_if_unsupported<T>(result: Result<Vec<T>>) -> Result<Vec<T>> {
+    match result {
+        Err(Error::JsonRpc(e)) if e.code == METHOD_NOT_FOUND => Ok(Vec::new()),
+        other => other,
+    }
+}
+
 impl Drop for Client {
     /// Ensures the background connection task is terminated when the `Client` is dropped.
+    /// A server subprocess owned by the client is killed when `child` is dropped.
     fn drop(&mut self) {
-        self.session_handle.abort();
+        if let Ok(link) = self.link.get_mut() {
+            link.session_handle.abort();
+        }
+        if let Some(keepalive) = self.keepalive_handle.get_mut().ok().and_then(Option::take) {
+            keepalive.abort();
+        }
+    }
+}
+
+/// Maps each advertised capability to the request methods it makes available.
+fn methods_for_capabilities(capabilities: &ServerCapabilities) -> Vec<String> {
+    let mut methods = Vec::new();
+    if capabilities.tools.is_some() {
+        methods.extend(["tools/list", "tools/call"]);
+    }
+    if let Some(resources) = &capabilities.resources {
+        methods.extend([
+            "resources/list",
+            "resources/read",
+            "resources/templates/list",
+        ]);
+        if resources.subscribe == Some(true) {
+            methods.extend(["resources/subscribe", "resources/unsubscribe"]);
+        }
+    }
+    if capabilities.prompts.is_some() {
+        methods.extend(["prompts/list", "prompts/get"]);
+    }
+    if capabilities.logging.is_some() {
+        methods.push("logging/setLevel");
     }
+    methods.into_iter().map(String::from).collect()
 }
@@ -0,0 +1,181 @@
+//! Defines the `McpClient` trait, an abstraction over the request methods of `Client`.
+
+use super::client::Client;
+use crate::{
+    error::Result,
+    types::{
+        CallToolResult, GetPromptResult, ListPromptsResult, ReadResourceResult, Resource,
+        ResourceTemplate, Root, Tool,
+    },
+};
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// The operations an application can perform against an MCP server.
+///
+/// [`Client`] implements this trait. Code that depends on `McpClient` instead of
+/// `Client` directly can be tested by injecting a hand-written fake, without
+/// running a server.
+///
+/// # Example
+///
+/// ```
+/// use mcp_sdk::client::McpClient;
+///
+/// async fn tool_names(client: &impl McpClient) -> mcp_sdk::Result<Vec<String>> {
+///     Ok(client.list_tools().await?.into_iter().map(|t| t.name).collect())
+/// }
+/// ```
+#[async_trait]
+pub trait McpClient: Send + Sync {
+    /// Lists the tools offered by the server.
+    async fn list_tools(&self) -> Result<Vec<Tool>>;
+
+    /// Calls a tool by name with JSON arguments.
+    async fn call_tool(&self, name: String, arguments: Value) -> Result<CallToolResult>;
+
+    /// Lists the resources offered by the server.
+    async fn list_resources(&self) -> Result<Vec<Resource>>;
+
+    /// Lists the resource templates offered by the server.
+    async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>>;
+
+    /// Reads the contents of a resource.
+    async fn read_resource(&self, uri: String) -> Result<ReadResourceResult>;
+
+    /// Lists the prompts offered by the server.
+    async fn list_prompts(&self) -> Result<ListPromptsResult>;
+
+    /// Retrieves a prompt, rendered with the given arguments.
+    async fn get_prompt(&self, name: String, arguments: Option<Value>) -> Result<GetPromptResult>;
+
+    /// Replaces the roots exposed to the server.
+    async fn set_roots(&self, roots: Vec<Root>) -> Result<()>;
+}
+
+#[async_trait]
+impl McpClient for Client {
+    async fn list_tools(&self) -> Result<Vec<Tool>> {
+        Client::list_tools(self).await
+    }
+
+    async fn call_tool(&self, name: String, arguments: Value) -> Result<CallToolResult> {
+        Client::call_tool(self, name, arguments).await
+    }
+
+    async fn list_resources(&self) -> Result<Vec<Resource>> {
+        Client::list_resources(self).await
+    }
+
+    async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>> {
+        Client::list_resource_templates(self).await
+    }
+
+    async fn read_resource(&self, uri: String) -> Result<ReadResourceResult> {
+        Client::read_resource(self, uri).await
+    }
+
+    async fn list_prompts(&self) -> Result<ListPromptsResult> {
+        Client::list_prompts(self).await
+    }
+
+    async fn get_prompt(&self, name: String, arguments: Option<Value>) -> Result<GetPromptResult> {
+        Client::get_prompt(self, name, arguments).await
+    }
+
+    async fn set_roots(&self, roots: Vec<Root>) -> Result<()> {
+        Client::set_roots(self, roots).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Content;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// A fake that records tool calls and serves a fixed tool list.
+    #[derive(Default)]
+    struct FakeClient {
+        calls: Mutex<Vec<(String, Value)>>,
+    }
+
+    #[async_trait]
+    impl McpClient for FakeClient {
+        async fn list_tools(&self) -> Result<Vec<Tool>> {
+            Ok(vec![Tool {
+                name: "echo".to_string(),
+                ..Default::default()
+            }])
+        }
+
+        async fn call_tool(&self, name: String, arguments: Value) -> Result<CallToolResult> {
+            let text = arguments["message"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            self.calls.lock().unwrap().push((name, arguments));
+            Ok(CallToolResult {
+                content: vec![Content::Text {
+                    text,
+                    annotations: None,
+                }],
+                ..Default::default()
+            })
+        }
+
+        async fn list_resources(&self) -> Result<Vec<Resource>> {
+            Ok(vec![])
+        }
+
+        async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>> {
+            Ok(vec![])
+        }
+
+        async fn read_resource(&self, uri: String) -> Result<ReadResourceResult> {
+            Err(format!("no resource {}", uri).into())
+        }
+
+        async fn list_prompts(&self) -> Result<ListPromptsResult> {
+            Ok(ListPromptsResult {
+                prompts: vec![],
+                meta: None,
+            })
+        }
+
+        async fn get_prompt(&self, name: String, _: Option<Value>) -> Result<GetPromptResult> {
+            Err(format!("no prompt {}", name).into())
+        }
+
+        async fn set_roots(&self, _roots: Vec<Root>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Application code written against the trait rather than `Client`.
+    async fn echo_everywhere(client: &dyn McpClient, message: &str) -> Result<Vec<String>> {
+        let mut replies = Vec::new();
+        for tool in client.list_tools().await? {
+            let result = client
+                .call_tool(tool.name, json!({ "message": message }))
+                .await?;
+            if let Some(Content::Text { text, .. }) = result.content.first() {
+                replies.push(text.clone());
+            }
+        }
+        Ok(replies)
+    }
+
+    #[tokio::test]
+    async fn test_fake_client_can_stand_in_for_client() {
+        let fake = FakeClient::default();
+        let replies = echo_everywhere(&fake, "hi").await.unwrap();
+
+        assert_eq!(replies, vec!["hi".to_string()]);
+        assert_eq!(
+            *fake.calls.lock().unwrap(),
+            vec![("echo".to_string(), json!({ "message": "hi" }))]
+        );
+    }
+}
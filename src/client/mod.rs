@@ -1,8 +1,13 @@
 //! Defines the public API for the MCP client.
 
+mod args;
+#[allow(clippy::module_inception)]
 mod client;
+mod mcp_client;
 mod session;
 mod session_group;
 
-pub use client::Client;
+pub use args::ArgsBuilder;
+pub use client::{Client, ResourceCatalog, CLOSE_TIMEOUT};
+pub use mcp_client::McpClient;
 pub use session_group::ClientSessionGroup;
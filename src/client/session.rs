@@ -4,13 +4,21 @@ use crate::{
     error::{Error, Result},
     network_adapter::NetworkAdapter,
     protocol::ProtocolConnection,
-    types::{JSONRPCResponse, Request, RequestId},
+    types::{
+        ErrorData, ErrorResponse, JSONRPCResponse, ListRootsResult, Request, RequestId, Response,
+        Root, INTERNAL_ERROR, METHOD_NOT_FOUND,
+    },
 };
 use dashmap::DashMap;
 use serde_json::Value;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, RwLock},
+};
 use tokio::sync::{mpsc, oneshot, Mutex};
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 // --- Type Aliases ---
 pub(crate) type ResponseResult = Result<Value>;
@@ -18,12 +26,25 @@ pub(crate) type ResponseSender = oneshot::Sender<ResponseResult>;
 pub(crate) type PendingRequestMap = Arc<Mutex<HashMap<RequestId, ResponseSender>>>;
 pub(crate) type NotificationHandler = Arc<dyn Fn(Value) + Send + Sync>;
 pub(crate) type NotificationHandlerMap = Arc<DashMap<String, NotificationHandler>>;
+pub(crate) type RequestHandler =
+    Arc<dyn Fn(Value) -> Pin<Box<dyn Future<Output = Result<Value>> + Send>> + Send + Sync>;
+pub(crate) type RequestHandlerMap = Arc<DashMap<String, RequestHandler>>;
+pub(crate) type RootsCache = Arc<RwLock<Vec<Root>>>;
 
 pub(crate) struct ClientSession<A: NetworkAdapter> {
     connection: ProtocolConnection<A>,
     pending_requests: PendingRequestMap,
     notification_handlers: NotificationHandlerMap,
     request_receiver: mpsc::Receiver<(Request<Value>, ResponseSender)>,
+    /// Pre-serialized messages (e.g. notifications) queued by the `Client`.
+    outgoing_receiver: mpsc::Receiver<String>,
+    roots: RootsCache,
+    request_handlers: RequestHandlerMap,
+    /// Responses from request handlers, which run on their own tasks.
+    handler_response_sender: mpsc::Sender<String>,
+    handler_response_receiver: mpsc::Receiver<String>,
+    /// Fires when the `Client` asks the session to wind down, see [`Self::with_shutdown`].
+    shutdown: Option<oneshot::Receiver<()>>,
 }
 
 impl<A: NetworkAdapter + Send + 'static> ClientSession<A> {
@@ -32,20 +53,75 @@ impl<A: NetworkAdapter + Send + 'static> ClientSession<A> {
         pending_requests: PendingRequestMap,
         notification_handlers: NotificationHandlerMap,
         request_receiver: mpsc::Receiver<(Request<Value>, ResponseSender)>,
+        outgoing_receiver: mpsc::Receiver<String>,
+        roots: RootsCache,
+        request_handlers: RequestHandlerMap,
     ) -> Self {
+        let (handler_response_sender, handler_response_receiver) = mpsc::channel(32);
         Self {
             connection,
             pending_requests,
             notification_handlers,
             request_receiver,
+            outgoing_receiver,
+            roots,
+            request_handlers,
+            handler_response_sender,
+            handler_response_receiver,
+            shutdown: None,
         }
     }
 
+    /// Lets the session be closed gracefully: once `shutdown` fires, no further
+    /// requests are accepted, and `run` returns as soon as queued messages are
+    /// written, every pending request has its response and every server request
+    /// being handled has been answered.
+    pub(crate) fn with_shutdown(mut self, shutdown: oneshot::Receiver<()>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Whether a draining session has nothing left to send or wait for.
+    async fn is_drained(&self) -> bool {
+        self.outgoing_receiver.is_empty()
+            && self.request_receiver.is_empty()
+            && self.handler_response_receiver.is_empty()
+            // Each handler still running holds a clone of the sender.
+            && self.handler_response_sender.strong_count() == 1
+            && self.pending_requests.lock().await.is_empty()
+    }
+
     pub(crate) async fn run(mut self) {
+        let mut shutdown = self.shutdown.take();
+        let mut draining = false;
         loop {
+            if draining && self.is_drained().await {
+                info!("[Client] Session drained, closing connection.");
+                break;
+            }
             tokio::select! {
                 biased;
 
+                _ = async { shutdown.as_mut().unwrap().await }, if shutdown.is_some() => {
+                    shutdown = None;
+                    draining = true;
+                    self.request_receiver.close();
+                },
+
+                // Polled before requests so that `notifications/initialized` always
+                // reaches the server ahead of the first request after the handshake.
+                Some(raw_message) = self.outgoing_receiver.recv() => {
+                    if let Err(e) = self.connection.send_raw(&raw_message).await {
+                        error!("[Client] Error writing message to server: {}", e);
+                        break;
+                    }
+                },
+                Some(raw_response) = self.handler_response_receiver.recv() => {
+                    if let Err(e) = self.connection.send_raw(&raw_response).await {
+                        error!("[Client] Error answering server request: {}", e);
+                        break;
+                    }
+                },
                 Some((request, responder)) = self.request_receiver.recv() => {
                     // req_id_clone_for_log removed
                     self.pending_requests.lock().await.insert(request.id.clone(), responder);
@@ -58,11 +134,17 @@ impl<A: NetworkAdapter + Send + 'static> ClientSession<A> {
                 },
                 read_result = self.connection.recv_message::<Value>() => {
                     match read_result {
+                        Ok(Some(Value::Array(batch))) => {
+                            // A batch response: each element is routed on its own.
+                            if let Err(e) = self.handle_batch(batch).await {
+                                error!("[Client] Error answering server request: {}", e);
+                                break;
+                            }
+                        },
                         Ok(Some(raw_message)) => {
-                            if raw_message.get("id").is_some() {
-                                Self::handle_response(raw_message, &self.pending_requests).await;
-                            } else if raw_message.get("method").is_some() {
-                                Self::handle_notification(raw_message, self.notification_handlers.clone());
+                            if let Err(e) = self.handle_message(raw_message).await {
+                                error!("[Client] Error answering server request: {}", e);
+                                break;
                             }
                         },
                         Ok(None) => {
@@ -77,6 +159,40 @@ impl<A: NetworkAdapter + Send + 'static> ClientSession<A> {
                 },
             }
         }
+        self.fail_pending_requests().await;
+    }
+
+    /// Answers every request still waiting for a response, including those queued
+    /// but never sent, with [`Error::ConnectionClosed`] so their callers don't hang.
+    async fn fail_pending_requests(&mut self) {
+        self.request_receiver.close();
+        while let Ok((_, responder)) = self.request_receiver.try_recv() {
+            let _ = responder.send(Err(Error::ConnectionClosed));
+        }
+        for (_, responder) in self.pending_requests.lock().await.drain() {
+            let _ = responder.send(Err(Error::ConnectionClosed));
+        }
+    }
+
+    /// Routes a single incoming message by its shape: request, response or notification.
+    async fn handle_message(&mut self, raw_message: Value) -> Result<()> {
+        // A null id counts as no id at all, making the message a notification.
+        let has_id = raw_message.get("id").is_some_and(|id| !id.is_null());
+        if has_id && raw_message.get("method").is_some() {
+            self.handle_request(raw_message).await?;
+        } else if has_id {
+            Self::handle_response(raw_message, &self.pending_requests).await;
+        } else if raw_message.get("method").is_some() {
+            Self::handle_notification(raw_message, &self.notification_handlers);
+        }
+        Ok(())
+    }
+
+    async fn handle_batch(&mut self, batch: Vec<Value>) -> Result<()> {
+        for raw_message in batch {
+            self.handle_message(raw_message).await?;
+        }
+        Ok(())
     }
 
     async fn handle_response(raw_message: Value, pending_requests: &PendingRequestMap) {
@@ -84,30 +200,113 @@ impl<A: NetworkAdapter + Send + 'static> ClientSession<A> {
             if let Some(sender) = pending_requests.lock().await.remove(&id) {
                 let response: core::result::Result<JSONRPCResponse<Value>, _> =
                     serde_json::from_value(raw_message);
-                match response {
-                    Ok(JSONRPCResponse::Success(success)) => {
-                        let _ = sender.send(Ok(success.result));
-                    }
-                    Ok(JSONRPCResponse::Error(err)) => {
-                        let _ = sender.send(Err(Error::JsonRpc(err.error)));
-                    }
+                let result = match response {
+                    Ok(JSONRPCResponse::Success(success)) => Ok(success.result),
+                    Ok(JSONRPCResponse::Error(err)) => Err(Error::JsonRpc(err.error)),
+                    Err(e) => Err(Error::Serialization(e)),
+                };
+                // The caller may have given up on the request (e.g. its future was
+                // dropped along with the `Client`); the late response is discarded.
+                if sender.send(result).is_err() {
+                    debug!("[Client] Discarding response {:?}: requester is gone", id);
+                }
+            }
+        }
+    }
+
+    /// Answers a request initiated by the server.
+    async fn handle_request(&mut self, raw_message: Value) -> Result<()> {
+        let request: Request<Value> = match serde_json::from_value(raw_message) {
+            Ok(request) => request,
+            Err(e) => {
+                error!("[Client] Received malformed request from server: {}", e);
+                return Ok(());
+            }
+        };
+        if let Some(handler) = self
+            .request_handlers
+            .get(&request.method)
+            .map(|entry| Arc::clone(entry.value()))
+        {
+            // Run on its own task so a handler can itself talk to the server.
+            let responses = self.handler_response_sender.clone();
+            let id = request.id;
+            let params = request.params.unwrap_or(Value::Null);
+            tokio::spawn(async move {
+                let response = match handler(params).await {
+                    Ok(result) => serde_json::to_string(&Response {
+                        jsonrpc: "2.0".to_string(),
+                        id,
+                        result,
+                    }),
                     Err(e) => {
-                        let _ = sender.send(Err(Error::Serialization(e)));
+                        let error = match e {
+                            Error::JsonRpc(data) => data,
+                            other => ErrorData {
+                                code: INTERNAL_ERROR,
+                                message: other.to_string(),
+                                data: None,
+                            },
+                        };
+                        serde_json::to_string(&ErrorResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id,
+                            error,
+                        })
                     }
+                };
+                match response {
+                    Ok(raw) => {
+                        let _ = responses.send(raw).await;
+                    }
+                    Err(e) => error!("[Client] Failed to serialize request response: {}", e),
                 }
+            });
+            return Ok(());
+        }
+        match request.method.as_str() {
+            "ping" => {
+                let response = Response {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: serde_json::Map::new(),
+                };
+                self.connection.send_serializable(response).await
+            }
+            "roots/list" => {
+                // Served from the cache kept up to date by `Client::set_roots`.
+                let roots = self.roots.read().unwrap().clone();
+                let response = Response {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: ListRootsResult { roots },
+                };
+                self.connection.send_serializable(response).await
+            }
+            method => {
+                info!("[Client] Received unhandled request: {}", method);
+                let response = ErrorResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    error: ErrorData {
+                        code: METHOD_NOT_FOUND,
+                        message: format!("Method not found: {}", method),
+                        data: None,
+                    },
+                };
+                self.connection.send_serializable(response).await
             }
         }
     }
 
-    fn handle_notification(raw_message: Value, handlers: NotificationHandlerMap) {
+    /// Runs the handler for a notification right here in the session loop, so
+    /// handlers see notifications in the order they arrived, and before any
+    /// response read after them is handed back to its caller.
+    fn handle_notification(raw_message: Value, handlers: &NotificationHandlerMap) {
         if let Some(method) = raw_message.get("method").and_then(Value::as_str) {
-            if let Some(handler) = handlers.get(method) {
-                let handler = handler.clone();
+            if let Some(handler) = handlers.get(method).map(|entry| Arc::clone(entry.value())) {
                 let params = raw_message.get("params").cloned().unwrap_or(Value::Null);
-
-                tokio::spawn(async move {
-                    (handler)(params);
-                });
+                (handler)(params);
             } else {
                 info!("[Client] Received unhandled notification: {}", method);
             }
@@ -119,7 +318,8 @@ impl<A: NetworkAdapter + Send + 'static> ClientSession<A> {
 mod tests {
     use super::*;
     use crate::{
-        network_adapter::NetworkAdapter, protocol::ProtocolConnection,
+        network_adapter::{duplex_pair, DuplexAdapter, NetworkAdapter},
+        protocol::ProtocolConnection,
         types::ListToolsChangedParams,
     };
     use async_trait::async_trait;
@@ -133,75 +333,83 @@ mod tests {
     use serde_json::json;
     use std::sync::atomic::{AtomicBool, Ordering}; // CORRECTED: Added missing import
     use std::time::Duration;
-    use tokio::sync::{mpsc as async_mpsc, Mutex as TokioMutex};
+    use tokio::sync::mpsc as async_mpsc;
     use tokio::task::JoinHandle;
 
-    // --- Mock Adapter for Client Tests ---
-    #[derive(Clone)]
-    struct MockAdapter {
-        incoming_tx: async_mpsc::Sender<String>,
-        incoming_rx: Arc<TokioMutex<async_mpsc::Receiver<String>>>,
-        outgoing: Arc<TokioMutex<Vec<String>>>,
-    }
-
-    impl MockAdapter {
-        fn new() -> Self {
-            let (incoming_tx, incoming_rx) = async_mpsc::channel(32);
-            Self {
-                incoming_tx,
-                incoming_rx: Arc::new(TokioMutex::new(incoming_rx)),
-                outgoing: Arc::new(TokioMutex::new(Vec::new())),
-            }
-        }
-        async fn push_incoming(&self, msg: String) {
-            self.incoming_tx.send(msg).await.unwrap();
-        }
-        async fn pop_outgoing(&self) -> Option<String> {
-            self.outgoing.lock().await.pop()
-        }
+    /// The next message the session sent to `server`, failing the test if none
+    /// arrives. With schema validation on, the first one waits for the schema.
+    async fn next_sent(server: &mut DuplexAdapter) -> String {
+        tokio::time::timeout(Duration::from_secs(5), server.recv())
+            .await
+            .expect("Nothing was sent in time")
+            .unwrap()
+            .expect("The session closed the connection")
     }
 
-    #[async_trait]
-    impl NetworkAdapter for MockAdapter {
-        async fn send(&mut self, msg: &str) -> Result<()> {
-            self.outgoing.lock().await.push(msg.to_string());
-            Ok(())
-        }
-        async fn recv(&mut self) -> Result<Option<String>> {
-            Ok(self.incoming_rx.lock().await.recv().await)
-        }
+    /// Spawns a session over `adapter` with fresh shared state, returning the
+    /// sender for its requests and its task.
+    fn spawn_session<A: NetworkAdapter + 'static>(
+        adapter: A,
+        pending_requests: PendingRequestMap,
+    ) -> (
+        mpsc::Sender<(Request<Value>, ResponseSender)>,
+        JoinHandle<()>,
+    ) {
+        let (request_sender, request_receiver) = mpsc::channel(32);
+        let (_outgoing_sender, outgoing_receiver) = mpsc::channel(32);
+        let session = ClientSession::new(
+            ProtocolConnection::new(adapter),
+            pending_requests,
+            Arc::new(DashMap::new()),
+            request_receiver,
+            outgoing_receiver,
+            Arc::new(RwLock::new(Vec::new())),
+            Arc::new(DashMap::new()),
+        );
+        (request_sender, tokio::spawn(session.run()))
     }
 
     // --- Test Harness ---
     struct TestHarness {
-        adapter: MockAdapter,
+        /// The server's end of the connection.
+        server: DuplexAdapter,
         pending_requests: PendingRequestMap,
         notification_handlers: NotificationHandlerMap,
         request_sender: mpsc::Sender<(Request<Value>, ResponseSender)>,
+        roots: RootsCache,
+        request_handlers: RequestHandlerMap,
         _connection_handle: JoinHandle<()>,
     }
 
     fn setup_session_test() -> TestHarness {
-        let adapter = MockAdapter::new();
-        let connection = ProtocolConnection::new(adapter.clone());
+        let (client, server) = duplex_pair();
+        let connection = ProtocolConnection::new(client);
         let pending_requests = Arc::new(Mutex::new(HashMap::new()));
         let notification_handlers = Arc::new(DashMap::new());
         let (request_sender, request_receiver) = mpsc::channel(32);
+        let (_outgoing_sender, outgoing_receiver) = mpsc::channel(32);
+        let roots: RootsCache = Arc::new(RwLock::new(Vec::new()));
+        let request_handlers: RequestHandlerMap = Arc::new(DashMap::new());
 
-        let session = ClientSession {
+        let session = ClientSession::new(
             connection,
-            pending_requests: Arc::clone(&pending_requests),
-            notification_handlers: Arc::clone(&notification_handlers),
+            Arc::clone(&pending_requests),
+            Arc::clone(&notification_handlers),
             request_receiver,
-        };
+            outgoing_receiver,
+            Arc::clone(&roots),
+            Arc::clone(&request_handlers),
+        );
 
         let connection_handle = tokio::spawn(session.run());
 
         TestHarness {
-            adapter,
+            server,
             pending_requests,
             notification_handlers,
             request_sender,
+            roots,
+            request_handlers,
             _connection_handle: connection_handle,
         }
     }
@@ -209,7 +417,7 @@ mod tests {
     #[tokio::test]
     async fn test_session_handles_response() {
         init_test_tracing(); // Initialize tracing
-        let harness = setup_session_test();
+        let mut harness = setup_session_test();
         let (tx, rx) = oneshot::channel::<ResponseResult>();
 
         let request_id = RequestId::Num(1);
@@ -221,7 +429,7 @@ mod tests {
             "result": { "status": "ok" }
         })
         .to_string();
-        harness.adapter.push_incoming(response_json).await;
+        harness.server.send(&response_json).await.unwrap();
 
         let result = tokio::time::timeout(Duration::from_secs(1), rx)
             .await
@@ -234,13 +442,12 @@ mod tests {
     #[tokio::test]
     async fn test_session_handles_notification() {
         init_test_tracing(); // Initialize tracing
-        let harness = setup_session_test();
-        let handler_was_called = Arc::new(AtomicBool::new(false));
-        let handler_was_called_clone = Arc::clone(&handler_was_called);
+        let mut harness = setup_session_test();
+        let (called_tx, mut called_rx) = async_mpsc::unbounded_channel();
 
         let handler: NotificationHandler = Arc::new(move |params: Value| {
             let _params: ListToolsChangedParams = serde_json::from_value(params).unwrap();
-            handler_was_called_clone.store(true, Ordering::SeqCst);
+            called_tx.send(()).unwrap();
         });
         harness
             .notification_handlers
@@ -252,20 +459,45 @@ mod tests {
             "params": {}
         })
         .to_string();
-        harness.adapter.push_incoming(notification_json).await;
+        harness.server.send(&notification_json).await.unwrap();
 
-        tokio::time::sleep(Duration::from_millis(50)).await;
+        tokio::time::timeout(Duration::from_secs(5), called_rx.recv())
+            .await
+            .expect("Notification handler was not called");
+    }
 
-        assert!(
-            handler_was_called.load(Ordering::SeqCst),
-            "Notification handler was not called"
-        );
+    #[tokio::test]
+    async fn test_session_treats_null_id_as_notification() {
+        init_test_tracing();
+        let mut harness = setup_session_test();
+        let (called_tx, mut called_rx) = async_mpsc::unbounded_channel();
+        let handler: NotificationHandler = Arc::new(move |_params: Value| {
+            called_tx.send(()).unwrap();
+        });
+        harness
+            .notification_handlers
+            .insert("notifications/tools/list_changed".to_string(), handler);
+
+        let notification_json = json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "method": "notifications/tools/list_changed"
+        })
+        .to_string();
+        harness.server.send(&notification_json).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), called_rx.recv())
+            .await
+            .expect("Notification handler was not called");
+
+        // Handled as a request, it would have been answered with an error.
+        let reply = tokio::time::timeout(Duration::from_millis(50), harness.server.recv()).await;
+        assert!(reply.is_err(), "Unexpected reply: {:?}", reply);
     }
 
     #[tokio::test]
     async fn test_session_sends_requests() {
         init_test_tracing(); // Initialize tracing for this specific test
-        let harness = setup_session_test();
+        let mut harness = setup_session_test();
 
         let request_payload = Request {
             jsonrpc: "2.0".to_string(),
@@ -281,9 +513,221 @@ mod tests {
             .await
             .unwrap();
 
-        tokio::time::sleep(Duration::from_secs(5)).await; // Increased to 5 seconds
-
-        let sent_message = harness.adapter.pop_outgoing().await.unwrap();
+        let sent_message = next_sent(&mut harness.server).await;
         assert!(sent_message.contains("\"method\":\"test\""));
     }
+
+    #[tokio::test]
+    async fn test_session_answers_roots_list_from_cache() {
+        init_test_tracing();
+        let mut harness = setup_session_test();
+        *harness.roots.write().unwrap() = vec![Root {
+            uri: "file:///workspace".to_string(),
+            name: Some("workspace".to_string()),
+        }];
+
+        for id in 1..=2 {
+            let request = json!({ "jsonrpc": "2.0", "id": id, "method": "roots/list" });
+            harness.server.send(&request.to_string()).await.unwrap();
+        }
+
+        for id in 1..=2 {
+            let raw = next_sent(&mut harness.server).await;
+            let response: Response<ListRootsResult> = serde_json::from_str(&raw).unwrap();
+            assert_eq!(response.id, RequestId::Num(id));
+            assert_eq!(response.result.roots[0].uri, "file:///workspace");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_answers_server_ping() {
+        init_test_tracing();
+        let mut harness = setup_session_test();
+        let ping = json!({ "jsonrpc": "2.0", "id": 5, "method": "ping" });
+        harness.server.send(&ping.to_string()).await.unwrap();
+
+        let raw = next_sent(&mut harness.server).await;
+        let response: Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(response, json!({ "jsonrpc": "2.0", "id": 5, "result": {} }));
+    }
+
+    #[tokio::test]
+    async fn test_session_runs_registered_request_handlers() {
+        init_test_tracing();
+        let mut harness = setup_session_test();
+        let echo: RequestHandler =
+            Arc::new(|params: Value| Box::pin(async move { Ok(json!({ "echo": params })) }));
+        let refuse: RequestHandler = Arc::new(|_params: Value| {
+            Box::pin(async {
+                Err(Error::JsonRpc(ErrorData {
+                    code: -32042,
+                    message: "not today".to_string(),
+                    data: None,
+                }))
+            })
+        });
+        harness
+            .request_handlers
+            .insert("custom/echo".to_string(), echo);
+        harness
+            .request_handlers
+            .insert("custom/refuse".to_string(), refuse);
+
+        let requests = [
+            json!({ "jsonrpc": "2.0", "id": 11, "method": "custom/echo", "params": { "n": 1 } }),
+            json!({ "jsonrpc": "2.0", "id": "r-12", "method": "custom/refuse" }),
+        ];
+        for request in requests {
+            harness.server.send(&request.to_string()).await.unwrap();
+        }
+
+        let mut outgoing = Vec::new();
+        for _ in 0..2 {
+            let raw = next_sent(&mut harness.server).await;
+            outgoing.push(serde_json::from_str::<Value>(&raw).unwrap());
+        }
+        assert!(outgoing.contains(&json!({
+            "jsonrpc": "2.0", "id": 11, "result": { "echo": { "n": 1 } }
+        })));
+        assert!(outgoing.contains(&json!({
+            "jsonrpc": "2.0", "id": "r-12",
+            "error": { "code": -32042, "message": "not today" }
+        })));
+    }
+
+    #[tokio::test]
+    async fn test_session_rejects_unknown_server_request() {
+        init_test_tracing();
+        let mut harness = setup_session_test();
+        let request = json!({ "jsonrpc": "2.0", "id": 7, "method": "unknown/method" });
+        harness.server.send(&request.to_string()).await.unwrap();
+
+        let raw = next_sent(&mut harness.server).await;
+        let response: ErrorResponse = serde_json::from_str(&raw).unwrap();
+        assert_eq!(response.id, RequestId::Num(7));
+        assert_eq!(response.error.code, METHOD_NOT_FOUND);
+    }
+
+    /// Fails its first write with a transient error, then passes everything on to
+    /// the wrapped adapter. A healthy in-memory transport never fails like this.
+    struct FlakyAdapter {
+        inner: DuplexAdapter,
+        failed_once: AtomicBool,
+    }
+
+    #[async_trait]
+    impl NetworkAdapter for FlakyAdapter {
+        async fn send(&mut self, msg: &str) -> Result<()> {
+            if !self.failed_once.swap(true, Ordering::SeqCst) {
+                return Err(std::io::Error::from(std::io::ErrorKind::Interrupted).into());
+            }
+            self.inner.send(msg).await
+        }
+        async fn recv(&mut self) -> Result<Option<String>> {
+            self.inner.recv().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_retries_transient_send_failure() {
+        init_test_tracing();
+        let (client, mut server) = duplex_pair();
+        let flaky = FlakyAdapter {
+            inner: client,
+            failed_once: AtomicBool::new(false),
+        };
+        let (request_sender, session_handle) =
+            spawn_session(flaky, Arc::new(Mutex::new(HashMap::new())));
+
+        let request = Request {
+            jsonrpc: "2.0".to_string(),
+            id: RequestId::Num(1),
+            method: "tools/list".to_string(),
+            params: None,
+        };
+        let (tx, rx) = oneshot::channel();
+        request_sender.send((request, tx)).await.unwrap();
+
+        // The write went through on retry and the session is still running.
+        let sent = next_sent(&mut server).await;
+        assert!(sent.contains("\"method\":\"tools/list\""));
+        assert!(!session_handle.is_finished());
+
+        server
+            .send(&json!({ "jsonrpc": "2.0", "id": 1, "result": {} }).to_string())
+            .await
+            .unwrap();
+        let response = tokio::time::timeout(Duration::from_secs(1), rx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.unwrap(), json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_session_routes_batch_response_by_id() {
+        init_test_tracing();
+        let mut harness = setup_session_test();
+        let mut receivers = Vec::new();
+        for (id, method) in [(1, "tools/list"), (2, "prompts/list")] {
+            let request = Request {
+                jsonrpc: "2.0".to_string(),
+                id: RequestId::Num(id),
+                method: method.to_string(),
+                params: None,
+            };
+            let (tx, rx) = oneshot::channel();
+            harness.request_sender.send((request, tx)).await.unwrap();
+            receivers.push(rx);
+        }
+        for _ in 0..2 {
+            next_sent(&mut harness.server).await;
+        }
+
+        // The elements come back in a different order than the requests went out.
+        let batch = json!([
+            { "jsonrpc": "2.0", "id": 2, "result": { "prompts": [] } },
+            { "jsonrpc": "2.0", "id": 1, "error": { "code": -32603, "message": "boom" } }
+        ]);
+        harness.server.send(&batch.to_string()).await.unwrap();
+
+        let mut results = Vec::new();
+        for rx in receivers {
+            let result = tokio::time::timeout(Duration::from_secs(1), rx)
+                .await
+                .expect("Test timed out")
+                .expect("Oneshot channel failed");
+            results.push(result);
+        }
+        assert!(matches!(&results[0], Err(Error::JsonRpc(e)) if e.message == "boom"));
+        assert_eq!(results[1].as_ref().unwrap(), &json!({ "prompts": [] }));
+    }
+
+    #[tokio::test]
+    async fn test_pending_request_fails_when_server_disconnects() {
+        init_test_tracing();
+        let (client, mut server) = duplex_pair();
+        let pending_requests: PendingRequestMap = Arc::new(Mutex::new(HashMap::new()));
+        let (request_sender, session_handle) = spawn_session(client, Arc::clone(&pending_requests));
+
+        let request = Request {
+            jsonrpc: "2.0".to_string(),
+            id: RequestId::Num(1),
+            method: "tools/list".to_string(),
+            params: None,
+        };
+        let (tx, rx) = oneshot::channel();
+        request_sender.send((request, tx)).await.unwrap();
+        next_sent(&mut server).await;
+
+        // The server goes away without answering.
+        drop(server);
+        let result = tokio::time::timeout(Duration::from_secs(1), rx)
+            .await
+            .expect("Pending request was never resolved")
+            .expect("Oneshot channel failed");
+        assert!(matches!(result, Err(Error::ConnectionClosed)));
+        assert!(pending_requests.lock().await.is_empty());
+        session_handle.await.unwrap();
+    }
 }
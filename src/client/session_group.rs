@@ -2,11 +2,37 @@
 
 use crate::client::Client;
 use crate::error::Result;
+use crate::network_adapter::NdjsonAdapter;
 use crate::types::Tool;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tracing::error;
+use tracing::{error, info};
+
+// Re-establishes a member's connection after it was evicted for being idle.
+type Connector =
+    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = Result<Client>> + Send>> + Send + Sync>;
+
+/// A client in the group, along with what is needed to manage its lifetime.
+struct Member {
+    // `None` while evicted; reconnected on next use.
+    client: Option<Arc<Client>>,
+    last_used: Instant,
+    connector: Option<Connector>,
+}
+
+impl Member {
+    fn new(client: Client, connector: Option<Connector>) -> Self {
+        Self {
+            client: Some(Arc::new(client)),
+            last_used: Instant::now(),
+            connector,
+        }
+    }
+}
 
 /// Manages connections to multiple MCP servers simultaneously.
 ///
@@ -26,14 +52,10 @@ use tracing::error;
 /// #[tokio::main]
 /// async fn main() -> Result<()> {
 ///     let group = ClientSessionGroup::new();
-///     // Connect to two different servers
-///     let adapter1 = NdjsonAdapter::connect("127.0.0.1:8081").await.unwrap();
-///     let client1 = Client::new(adapter1).await.unwrap();
-///     group
-///       .add_client("127.0.0.1:8081".to_string(), client1)
-///       .await
-///       .unwrap();
+///     // Connect to two different servers: one by address...
+///     group.connect_and_add("127.0.0.1:8081").await?;
 ///
+///     // ...and one through a client set up by hand.
 ///     let adapter2 = NdjsonAdapter::connect("127.0.0.1:8082").await.unwrap();
 ///     let client2 = Client::new(adapter2).await.unwrap();
 ///     group
@@ -51,7 +73,8 @@ use tracing::error;
 /// ```
 #[derive(Default)]
 pub struct ClientSessionGroup {
-    sessions: Arc<RwLock<HashMap<String, Arc<Client>>>>,
+    sessions: Arc<RwLock<HashMap<String, Member>>>,
+    idle_ttl: Option<Duration>,
 }
 
 impl ClientSessionGroup {
@@ -60,25 +83,134 @@ impl ClientSessionGroup {
         Self::default()
     }
 
-    /// Adds a new client to the group by connecting to the given server address.
+    /// Closes connections that have not been used for `ttl`, bounding the resources
+    /// held by groups spanning many servers.
     ///
-    /// This method will establish a new connection and perform the MCP handshake.
-    /// If the connection is successful, the new `Client` session is added to the group,
-    /// keyed by its server address.
+    /// Clients added with [`Self::add_reconnecting`] are transparently reconnected
+    /// on their next use; clients added with [`Self::add_client`] cannot be
+    /// re-established and are removed from the group instead.
+    pub fn with_idle_ttl(mut self, ttl: Duration) -> Self {
+        self.idle_ttl = Some(ttl);
+        self
+    }
+
+    /// Adds an already connected client to the group under `id`, replacing any
+    /// client previously registered with that id.
     ///
-    /// # Arguments
+    /// Use this when the client needs a transport other than TCP, e.g. a stdio
+    /// subprocess, or custom setup before joining the group. For a plain TCP server,
+    /// [`Self::connect_and_add`] does the connecting for you.
+    pub async fn add_client(&self, id: String, client: Client) -> Result<()> {
+        let mut sessions = self.sessions.write().await;
+        sessions.insert(id, Member::new(client, None));
+        Ok(())
+    }
+
+    /// Connects to the MCP server at `addr` over TCP (NDJSON framing), performs the
+    /// handshake, and adds the client to the group keyed by `addr`.
     ///
-    /// * `addr` - The network address of the MCP server (e.g., "127.0.0.1:8080").
+    /// The group remembers the address, so a connection closed for being idle is
+    /// re-established on its next use.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the connection or handshake fails.
-    pub async fn add_client(&self, id: String, client: Client) -> Result<()> {
+    /// Returns an error if the connection or handshake fails, in which case nothing
+    /// is added.
+    pub async fn connect_and_add(&self, addr: &str) -> Result<()> {
+        let target = addr.to_string();
+        self.add_reconnecting(addr.to_string(), move || {
+            let target = target.clone();
+            async move { Client::new(NdjsonAdapter::connect(&target).await?).await }
+        })
+        .await
+    }
+
+    /// Connects with `connect` and adds the resulting client to the group under `id`.
+    ///
+    /// `connect` is kept so that the connection can be re-established after it was
+    /// closed for being idle (see [`Self::with_idle_ttl`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the initial connection attempt, in which case nothing
+    /// is added.
+    pub async fn add_reconnecting<F, Fut>(&self, id: String, connect: F) -> Result<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Client>> + Send + 'static,
+    {
+        let connector: Connector = Arc::new(move || Box::pin(connect()));
+        let client = connector().await?;
         let mut sessions = self.sessions.write().await;
-        sessions.insert(id, Arc::new(client));
+        sessions.insert(id, Member::new(client, Some(connector)));
         Ok(())
     }
 
+    /// Returns the client registered under `id`, reconnecting it first if it was
+    /// closed for being idle. Returns `Ok(None)` for an unknown `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reconnecting fails.
+    pub async fn get_client(&self, id: &str) -> Result<Option<Arc<Client>>> {
+        self.evict_idle().await;
+        self.checkout(id).await
+    }
+
+    /// Closes every connection that has been idle for longer than the configured
+    /// TTL, returning how many were closed. Does nothing without a TTL.
+    ///
+    /// This also runs at the start of [`Self::get_client`] and
+    /// [`Self::list_tools_all`], so calling it directly is only needed to release
+    /// connections while the group is otherwise unused.
+    pub async fn evict_idle(&self) -> usize {
+        let Some(ttl) = self.idle_ttl else {
+            return 0;
+        };
+        let mut sessions = self.sessions.write().await;
+        let mut evicted = 0;
+        sessions.retain(|id, member| {
+            if member.client.is_none() || member.last_used.elapsed() < ttl {
+                return true;
+            }
+            info!("[Group] Closing idle connection '{}'", id);
+            // Dropping the last `Arc<Client>` aborts its connection task.
+            member.client = None;
+            evicted += 1;
+            member.connector.is_some()
+        });
+        evicted
+    }
+
+    /// Marks a member as used and hands out its client, reconnecting if needed.
+    async fn checkout(&self, id: &str) -> Result<Option<Arc<Client>>> {
+        let connector = {
+            let mut sessions = self.sessions.write().await;
+            let Some(member) = sessions.get_mut(id) else {
+                return Ok(None);
+            };
+            member.last_used = Instant::now();
+            match (&member.client, &member.connector) {
+                (Some(client), _) => return Ok(Some(Arc::clone(client))),
+                (None, Some(connector)) => Arc::clone(connector),
+                (None, None) => return Ok(None),
+            }
+        };
+
+        // The lock is not held while connecting, so other members stay usable.
+        info!("[Group] Reconnecting '{}'", id);
+        let client = Arc::new(connector().await?);
+        let mut sessions = self.sessions.write().await;
+        match sessions.get_mut(id) {
+            Some(member) => {
+                member.last_used = Instant::now();
+                Ok(Some(Arc::clone(member.client.get_or_insert(client))))
+            }
+            // Removed while we were reconnecting.
+            None => Ok(None),
+        }
+    }
+
     /// Removes a client from the group by its server address.
     ///
     /// When the client is removed, its connection will be gracefully terminated
@@ -102,11 +234,19 @@ impl ClientSessionGroup {
         let mut all_tools = Vec::new();
         let mut join_handles = Vec::new();
 
-        let sessions = self.sessions.read().await;
+        self.evict_idle().await;
+        let ids: Vec<String> = self.sessions.read().await.keys().cloned().collect();
 
-        for client in sessions.values() {
-            let client_clone = Arc::clone(client);
-            let handle = tokio::spawn(async move { client_clone.list_tools().await });
+        for id in ids {
+            let client = match self.checkout(&id).await {
+                Ok(Some(client)) => client,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to reconnect to '{}': {}", id, e);
+                    continue;
+                }
+            };
+            let handle = tokio::spawn(async move { client.list_tools().await });
             join_handles.push(handle);
         }
 
@@ -135,7 +275,7 @@ impl ClientSessionGroup {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{network_adapter::NdjsonAdapter, server::Server, types::Tool};
+    use crate::{server::Server, types::Tool};
 
     use std::time::Duration;
     use tokio::{net::TcpListener, task::JoinHandle};
@@ -150,10 +290,6 @@ mod tests {
                 ..Default::default()
             },
             move |_handle, _args| {
-                let _tool_list = vec![Tool {
-                    name: tool_name.to_string(),
-                    ..Default::default()
-                }];
                 async { Ok(crate::types::CallToolResult::default()) } // Dummy handler
             },
         );
@@ -199,6 +335,31 @@ mod tests {
         assert!(all_tools.iter().any(|t| t.name == "tool-from-server-2"));
     }
 
+    #[tokio::test]
+    async fn test_connect_and_add_alongside_add_client() {
+        let (server1_addr, _server1_handle) = setup_mock_server("tool-by-addr").await;
+        let (server2_addr, _server2_handle) = setup_mock_server("tool-by-key").await;
+
+        let group = ClientSessionGroup::new();
+        group.connect_and_add(&server1_addr).await.unwrap();
+        let adapter2 = NdjsonAdapter::connect(&server2_addr).await.unwrap();
+        let client2 = Client::new(adapter2).await.unwrap();
+        group
+            .add_client("custom-key".to_string(), client2)
+            .await
+            .unwrap();
+
+        // `connect_and_add` keys by address, `add_client` by the caller's id.
+        let by_addr = group.get_client(&server1_addr).await.unwrap().unwrap();
+        assert_eq!(by_addr.list_tools().await.unwrap()[0].name, "tool-by-addr");
+        let by_key = group.get_client("custom-key").await.unwrap().unwrap();
+        assert_eq!(by_key.list_tools().await.unwrap()[0].name, "tool-by-key");
+        assert_eq!(group.list_tools_all().await.unwrap().len(), 2);
+
+        assert!(group.connect_and_add("127.0.0.1:1").await.is_err());
+        assert_eq!(group.sessions.read().await.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_remove_and_list_all() {
         let (server1_addr, _server1_handle) = setup_mock_server("tool-1").await;
@@ -222,4 +383,41 @@ mod tests {
         assert_eq!(all_tools.len(), 1);
         assert_eq!(all_tools[0].name, "tool-2");
     }
+
+    #[tokio::test]
+    async fn test_idle_client_is_evicted_and_reconnected() {
+        let (server_addr, _server_handle) = setup_mock_server("tool-idle").await;
+        let connects = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let group = ClientSessionGroup::new().with_idle_ttl(Duration::from_millis(50));
+        let connects_clone = Arc::clone(&connects);
+        let addr = server_addr.clone();
+        group
+            .add_reconnecting("idle".to_string(), move || {
+                connects_clone.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let addr = addr.clone();
+                async move { Client::new(NdjsonAdapter::connect(&addr).await?).await }
+            })
+            .await
+            .unwrap();
+        let adapter = NdjsonAdapter::connect(&server_addr).await.unwrap();
+        group
+            .add_client("fixed".to_string(), Client::new(adapter).await.unwrap())
+            .await
+            .unwrap();
+
+        // Used recently, so nothing is evicted yet.
+        assert_eq!(group.evict_idle().await, 0);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(group.evict_idle().await, 2);
+        assert!(group.sessions.read().await["idle"].client.is_none());
+        // A plain client cannot be re-established, so it leaves the group.
+        assert!(!group.sessions.read().await.contains_key("fixed"));
+
+        let client = group.get_client("idle").await.unwrap().unwrap();
+        assert_eq!(connects.load(std::sync::atomic::Ordering::SeqCst), 2);
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools[0].name, "tool-idle");
+        assert!(group.get_client("fixed").await.unwrap().is_none());
+    }
 }
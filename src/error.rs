@@ -1,6 +1,6 @@
 //! Defines the custom `Error` and `Result` types for the MCP SDK.
 
-use crate::types::ErrorData;
+use crate::types::{ErrorData, INTERNAL_ERROR, INVALID_PARAMS, NOT_FOUND, REQUEST_TIMED_OUT};
 use std::fmt;
 
 /// The primary error type for the MCP SDK.
@@ -26,13 +26,47 @@ pub enum Error {
     /// often indicating that a background task has panicked or been terminated.
     ChannelClosed,
 
+    /// The connection to the peer closed while a request was still waiting for
+    /// its response.
+    ConnectionClosed,
+
+    /// The request was not sent because the given number of requests were already
+    /// waiting for responses, see `Client::max_pending`.
+    TooManyPendingRequests(usize),
+
     /// The future waiting for a response timed out.
     Timeout,
 
+    /// The `initialize` handshake failed, e.g. because the peer's initialize request
+    /// or response was missing a required field such as `capabilities`.
+    Handshake(String),
+
+    /// The request's parameters were well-formed JSON but not acceptable, e.g. an
+    /// argument out of range. Handlers return this to answer with `INVALID_PARAMS`.
+    InvalidParams(String),
+
+    /// What the request refers to, such as a resource URI, doesn't exist. Handlers
+    /// return this to answer with [`NOT_FOUND`].
+    NotFound(String),
+
     /// A general-purpose error for miscellaneous issues that don't fit into other categories.
     Other(String),
 }
 
+impl Error {
+    /// The JSON-RPC error code a server answers with when a handler fails with
+    /// this error. Variants without a more specific code map to `INTERNAL_ERROR`.
+    pub fn rpc_code(&self) -> i32 {
+        match self {
+            Error::JsonRpc(e) => e.code,
+            Error::InvalidParams(_) => INVALID_PARAMS,
+            Error::NotFound(_) => NOT_FOUND,
+            Error::Timeout => REQUEST_TIMED_OUT,
+            _ => INTERNAL_ERROR,
+        }
+    }
+}
+
 /// A specialized `Result` type for the MCP SDK.
 ///
 /// This type alias is used throughout the SDK for functions that can return
@@ -46,9 +80,22 @@ impl fmt::Display for Error {
         match self {
             Error::Io(e) => write!(f, "I/O error: {}", e),
             Error::Serialization(e) => write!(f, "Serialization error: {}", e),
-            Error::JsonRpc(e) => write!(f, "JSON-RPC error (code {}): {}", e.code, e.message),
+            Error::JsonRpc(e) => {
+                write!(f, "JSON-RPC error (code {}): {}", e.code, e.message)?;
+                match &e.data {
+                    Some(data) => write!(f, " (data: {})", data),
+                    None => Ok(()),
+                }
+            }
             Error::ChannelClosed => write!(f, "Internal communication channel closed"),
+            Error::ConnectionClosed => write!(f, "Connection closed before a response arrived"),
+            Error::TooManyPendingRequests(limit) => {
+                write!(f, "Too many pending requests (limit {})", limit)
+            }
             Error::Timeout => write!(f, "Operation timed out"),
+            Error::Handshake(msg) => write!(f, "Handshake failed: {}", msg),
+            Error::InvalidParams(msg) => write!(f, "Invalid params: {}", msg),
+            Error::NotFound(msg) => write!(f, "Not found: {}", msg),
             Error::Other(msg) => write!(f, "An internal error occurred: {}", msg),
         }
     }
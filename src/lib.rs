@@ -15,6 +15,8 @@
 //! * `protocol`: Handles message serialization/deserialization over an adapter.
 //! * `client`: Provides the high-level API for creating MCP clients.
 //! * `server`: Provides the high-level API for creating MCP servers.
+//! * `test_util`: Connects a client to a server in-process, without networking.
+//! * `tls`: Loads PEM certificates into a rustls server config (`tls` feature).
 
 // --- Module Declarations ---
 
@@ -23,6 +25,9 @@ pub mod error;
 pub mod network_adapter;
 pub mod protocol; // Made public for integration tests
 pub mod server;
+pub mod test_util;
+#[cfg(feature = "tls")]
+pub mod tls;
 pub mod types;
 
 // --- ToolArguments Proc Macro ---
@@ -31,7 +36,9 @@ pub mod types;
 /// define their expected input arguments.
 ///
 /// The macro also generates an inherent method `pub fn mcp_input_schema() -> serde_json::Value`
-/// on the struct that implements the trait method.
+/// on the struct that implements the trait method, and likewise
+/// `pub fn mcp_arg_order() -> &'static [&'static str]` listing the property names
+/// (after `rename`, without `skip`ped fields) in declaration order.
 ///
 /// Structs deriving `ToolArguments` typically also need to derive `serde::Deserialize`
 /// to be usable with `Server::register_tool_typed`.
@@ -97,16 +104,31 @@ pub trait ToolArgumentsDescriptor {
     /// The schema defines the expected "properties" and "required" fields
     /// according to JSON Schema conventions, specifically tailored for MCP.
     fn mcp_input_schema() -> serde_json::Value;
+
+    /// Returns the schema's property names in declaration order, used to map
+    /// positional (array) arguments onto named ones.
+    ///
+    /// Defaults to no names, for implementations that don't have a natural order.
+    fn mcp_arg_order() -> &'static [&'static str] {
+        &[]
+    }
 }
 
 // --- Public API Re-exports ---
 pub use client::Client;
 pub use error::{Error, Result};
-pub use network_adapter::{LspAdapter, NdjsonAdapter, NetworkAdapter, StdioAdapter};
+pub use network_adapter::{DuplexAdapter, LspAdapter, NdjsonAdapter, NetworkAdapter, StdioAdapter};
 pub use protocol::ProtocolConnection;
-pub use server::{ConnectionHandle, Server};
+#[cfg(feature = "schema-validation")]
+pub use protocol::ValidationMode;
+pub use server::{ConnectionHandle, ConnectionInfo, Server};
+#[cfg(feature = "tls")]
+pub use tls::TlsConfig;
 pub use types::{
-    BlobResourceContents, CallToolResult, Content, GetPromptResult, ListPromptsResult,
-    ListToolsChangedParams, Notification, Prompt, PromptArgument, PromptMessage,
-    ReadResourceResult, Resource, ResourceContents, TextResourceContents, Tool, ToolAnnotations,
+    Annotations, BlobResourceContents, CallToolResult, CallToolResultBuilder, CloseParams, Content,
+    ElicitAction, ElicitParams, ElicitResult, GetPromptResult, ListPromptsChangedParams,
+    ListPromptsResult, ListResourcesChangedParams, ListToolsChangedParams, Notification,
+    PartialResultParams, ProgressNotificationParams, Prompt, PromptArgument, PromptMessage,
+    ReadResourceResult, Resource, ResourceContents, ResourceTemplate, Role, Root,
+    TextResourceContents, Tool, ToolAnnotations, ToolSchema,
 };
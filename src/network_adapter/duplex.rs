@@ -0,0 +1,76 @@
+// src/network_adapter/duplex.rs
+use super::r#trait::NetworkAdapter;
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+/// An in-memory `NetworkAdapter` connected to a peer created by [`duplex_pair`].
+///
+/// Messages passed to `send` are delivered, unframed, to the peer's `recv`. When one
+/// side is dropped, the other side's `recv` returns `Ok(None)` just like a closed socket.
+pub struct DuplexAdapter {
+    /// `None` once [`DuplexAdapter::close_send`] was called.
+    sender: Option<mpsc::UnboundedSender<String>>,
+    receiver: mpsc::UnboundedReceiver<String>,
+}
+
+/// Creates two connected in-memory adapters.
+///
+/// This lets a `Client` and a `Server` talk to each other in-process without any
+/// networking, which is handy for tests and examples.
+///
+/// # Example
+///
+/// ```rust
+/// use mcp_sdk::network_adapter::{duplex_pair, NetworkAdapter};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (mut left, mut right) = duplex_pair();
+/// left.send("{\"hello\":1}").await.unwrap();
+/// assert_eq!(right.recv().await.unwrap().as_deref(), Some("{\"hello\":1}"));
+/// # }
+/// ```
+pub fn duplex_pair() -> (DuplexAdapter, DuplexAdapter) {
+    let (left_tx, right_rx) = mpsc::unbounded_channel();
+    let (right_tx, left_rx) = mpsc::unbounded_channel();
+    (
+        DuplexAdapter {
+            sender: Some(left_tx),
+            receiver: left_rx,
+        },
+        DuplexAdapter {
+            sender: Some(right_tx),
+            receiver: right_rx,
+        },
+    )
+}
+
+impl DuplexAdapter {
+    /// Stops sending, like shutting down the write half of a socket: the peer's
+    /// `recv` returns `Ok(None)` once it has read everything sent so far, while
+    /// this side can still receive. Later calls to `send` fail.
+    pub fn close_send(&mut self) {
+        self.sender = None;
+    }
+}
+
+#[async_trait]
+impl NetworkAdapter for DuplexAdapter {
+    async fn send(&mut self, msg: &str) -> Result<()> {
+        self.sender
+            .as_ref()
+            .ok_or(Error::ChannelClosed)?
+            .send(msg.to_string())
+            .map_err(|_| Error::ChannelClosed)
+    }
+
+    async fn recv(&mut self) -> Result<Option<String>> {
+        Ok(self.receiver.recv().await)
+    }
+
+    fn supports_multiline(&self) -> bool {
+        // Each message travels as one channel item, so newlines are harmless.
+        true
+    }
+}
@@ -0,0 +1,28 @@
+// src/network_adapter/line.rs
+use crate::error::Result;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Reads one newline-delimited message into `buf` and returns it without the line
+/// terminator, or `None` once the stream is closed.
+///
+/// `buf` is cleared but not shrunk between calls, so a connection that keeps
+/// receiving large messages (e.g. a multi-megabyte `tools/call` argument) reuses
+/// the same allocation instead of growing a fresh buffer for every message.
+pub(crate) async fn read_line<R>(reader: &mut R, buf: &mut Vec<u8>) -> Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    buf.clear();
+    if reader.read_until(b'\n', buf).await? == 0 {
+        return Ok(None);
+    }
+    if buf.ends_with(b"\n") {
+        buf.pop();
+        if buf.ends_with(b"\r") {
+            buf.pop();
+        }
+    }
+    let line = std::str::from_utf8(buf)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    Ok(Some(line.to_owned()))
+}
@@ -2,13 +2,18 @@
 use super::r#trait::NetworkAdapter; // Use the trait from the parent module
 use crate::error::{Error, Result};
 use async_trait::async_trait;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
 
+/// The `Content-Type` written by [`LspAdapter::with_content_type`], as the LSP
+/// base protocol specifies it.
+const CONTENT_TYPE: &str = "application/vscode-jsonrpc; charset=utf-8";
+
 pub struct LspAdapter {
     writer: OwnedWriteHalf,
     reader: BufReader<OwnedReadHalf>,
+    content_type: bool,
 }
 
 impl From<TcpStream> for LspAdapter {
@@ -17,6 +22,7 @@ impl From<TcpStream> for LspAdapter {
         Self {
             writer: write_half,
             reader: BufReader::new(read_half),
+            content_type: false,
         }
     }
 }
@@ -27,13 +33,24 @@ impl LspAdapter {
         let stream = TcpStream::connect(addr).await?;
         Ok(Self::from(stream))
     }
+
+    /// Also writes a `Content-Type: application/vscode-jsonrpc; charset=utf-8`
+    /// header with every message, for strict LSP-style peers that expect it.
+    /// Off by default, since `Content-Length` alone is enough for most peers.
+    pub fn with_content_type(mut self, enabled: bool) -> Self {
+        self.content_type = enabled;
+        self
+    }
 }
 
 #[async_trait]
 impl NetworkAdapter for LspAdapter {
     async fn send(&mut self, msg: &str) -> Result<()> {
-        let msg_len = msg.len();
-        let header = format!("Content-Length: {}\r\n\r\n", msg_len);
+        let mut header = format!("Content-Length: {}\r\n", msg.len());
+        if self.content_type {
+            header.push_str(&format!("Content-Type: {}\r\n", CONTENT_TYPE));
+        }
+        header.push_str("\r\n");
         self.writer.write_all(header.as_bytes()).await?;
         self.writer.write_all(msg.as_bytes()).await?;
         self.writer.flush().await?;
@@ -41,31 +58,135 @@ impl NetworkAdapter for LspAdapter {
     }
 
     async fn recv(&mut self) -> Result<Option<String>> {
-        let mut content_length = 0;
-        loop {
-            let mut header_line = String::new();
-            let bytes_read = self.reader.read_line(&mut header_line).await?;
-            if bytes_read == 0 {
-                return Ok(None);
+        read_message(&mut self.reader).await
+    }
+}
+
+/// Reads one `Content-Length`-framed message, or `None` once the stream is closed
+/// between messages.
+///
+/// Every header line up to the blank separator is consumed. Header names are
+/// matched case-insensitively, headers other than `Content-Length` (such as
+/// `Content-Type`) are ignored, and lines may end in `\r\n` or a bare `\n`.
+async fn read_message<R>(reader: &mut R) -> Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut content_length = None;
+    let mut saw_header = false;
+    loop {
+        let mut header_line = String::new();
+        if reader.read_line(&mut header_line).await? == 0 {
+            if saw_header {
+                return Err(Error::Other(
+                    "Connection closed in the middle of message headers.".into(),
+                ));
             }
-            if header_line.trim().is_empty() {
+            return Ok(None);
+        }
+        let header_line = header_line.trim_end_matches(['\r', '\n']);
+        if header_line.is_empty() {
+            if saw_header {
                 break;
             }
-            if let Some(len_str) = header_line.strip_prefix("Content-Length:") {
-                if let Ok(len) = len_str.trim().parse::<usize>() {
-                    content_length = len;
-                }
-            }
+            // Tolerate stray blank lines between messages.
+            continue;
+        }
+        saw_header = true;
+        let Some((name, value)) = header_line.split_once(':') else {
+            return Err(Error::Other(format!(
+                "Malformed message header: '{}'",
+                header_line
+            )));
+        };
+        if name.trim().eq_ignore_ascii_case("Content-Length") {
+            let len = value.trim().parse::<usize>().map_err(|_| {
+                Error::Other(format!("Invalid Content-Length header: '{}'", value.trim()))
+            })?;
+            content_length = Some(len);
         }
-        if content_length == 0 {
-            return Err(Error::Other(
-                "Received message with no Content-Length header.".into(),
-            ));
+    }
+    let content_length = content_length
+        .ok_or_else(|| Error::Other("Received message with no Content-Length header.".into()))?;
+    let mut body_buf = vec![0; content_length];
+    reader.read_exact(&mut body_buf).await?;
+    let body_str = String::from_utf8(body_buf)
+        .map_err(|e| Error::Other(format!("Invalid UTF-8 in message body: {}", e)))?;
+    Ok(Some(body_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    async fn read_all(input: &[u8]) -> Vec<Result<Option<String>>> {
+        let mut reader = BufReader::new(Cursor::new(input.to_vec()));
+        let mut results = Vec::new();
+        loop {
+            let result = read_message(&mut reader).await;
+            let done = !matches!(result, Ok(Some(_)));
+            results.push(result);
+            if done {
+                return results;
+            }
         }
-        let mut body_buf = vec![0; content_length];
-        self.reader.read_exact(&mut body_buf).await?;
-        let body_str = String::from_utf8(body_buf)
-            .map_err(|e| Error::Other(format!("Invalid UTF-8 in message body: {}", e)))?;
-        Ok(Some(body_str))
+    }
+
+    #[tokio::test]
+    async fn test_ignores_content_type_and_unknown_headers() {
+        let input = b"Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n\
+                      content-length: 8\r\n\
+                      X-Custom: yes\r\n\r\n{\"id\":1}";
+        let results = read_all(input).await;
+        assert_eq!(results[0].as_ref().unwrap().as_deref(), Some("{\"id\":1}"));
+        assert!(matches!(results[1], Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn test_accepts_mixed_line_endings() {
+        let input = b"Content-Length: 8\n\n{\"id\":1}CONTENT-LENGTH: 8\r\n\n{\"id\":2}";
+        let results = read_all(input).await;
+        assert_eq!(results[0].as_ref().unwrap().as_deref(), Some("{\"id\":1}"));
+        assert_eq!(results[1].as_ref().unwrap().as_deref(), Some("{\"id\":2}"));
+        assert!(matches!(results[2], Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_malformed_or_missing_length() {
+        let results = read_all(b"Content-Length: abc\r\n\r\n{}").await;
+        let err = results[0].as_ref().unwrap_err().to_string();
+        assert!(
+            err.contains("Invalid Content-Length header: 'abc'"),
+            "{}",
+            err
+        );
+
+        let results = read_all(b"Content-Type: text/plain\r\n\r\n{}").await;
+        let err = results[0].as_ref().unwrap_err().to_string();
+        assert!(err.contains("no Content-Length header"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_send_writes_content_type_header_when_enabled() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let mut adapter = LspAdapter::connect(&addr)
+            .await
+            .unwrap()
+            .with_content_type(true);
+        let (mut peer, _) = listener.accept().await.unwrap();
+
+        adapter.send("{\"id\":1}").await.unwrap();
+        let expected = "Content-Length: 8\r\n\
+                        Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n\
+                        \r\n\
+                        {\"id\":1}";
+        let mut written = vec![0; expected.len()];
+        peer.read_exact(&mut written).await.unwrap();
+        assert_eq!(String::from_utf8(written.clone()).unwrap(), expected);
+
+        let results = read_all(&written).await;
+        assert_eq!(results[0].as_ref().unwrap().as_deref(), Some("{\"id\":1}"));
     }
 }
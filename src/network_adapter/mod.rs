@@ -1,9 +1,12 @@
 // src/network_adapter/mod.rs
+pub mod duplex;
+mod line;
 pub mod lsp;
 pub mod ndjson;
 pub mod stdio;
 pub mod r#trait; // Using r# to use the keyword `trait` as a module name
 
+pub use duplex::{duplex_pair, DuplexAdapter};
 pub use lsp::LspAdapter;
 pub use ndjson::NdjsonAdapter;
 pub use r#trait::NetworkAdapter;
@@ -1,14 +1,19 @@
 // src/network_adapter/ndjson.rs
+use super::line::read_line;
 use super::r#trait::NetworkAdapter;
 use crate::error::Result;
 use async_trait::async_trait;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncWriteExt, BufReader};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
 
 pub struct NdjsonAdapter {
     writer: OwnedWriteHalf,
     reader: BufReader<OwnedReadHalf>,
+    line_buf: Vec<u8>,
+    /// Skip blank and `#` comment lines instead of returning them, see
+    /// [`NdjsonAdapter::lenient`].
+    lenient: bool,
 }
 
 impl NdjsonAdapter {
@@ -17,6 +22,15 @@ impl NdjsonAdapter {
         let stream = TcpStream::connect(addr).await?;
         Ok(Self::from(stream))
     }
+
+    /// Makes `recv` skip blank lines and lines starting with `#`, reading on until
+    /// a real message or the end of the stream. Useful when replaying fixtures or
+    /// logs that interleave comments with the JSON. Off by default, where every
+    /// line is returned as a message.
+    pub fn lenient(mut self, enabled: bool) -> Self {
+        self.lenient = enabled;
+        self
+    }
 }
 
 impl From<TcpStream> for NdjsonAdapter {
@@ -25,6 +39,8 @@ impl From<TcpStream> for NdjsonAdapter {
         Self {
             writer: write_half,
             reader: BufReader::new(read_half),
+            line_buf: Vec::new(),
+            lenient: false,
         }
     }
 }
@@ -39,19 +55,55 @@ impl NetworkAdapter for NdjsonAdapter {
     }
 
     async fn recv(&mut self) -> Result<Option<String>> {
-        let mut line = String::new();
-        match self.reader.read_line(&mut line).await {
-            Ok(0) => Ok(None),
-            Ok(_) => {
-                if line.ends_with('\n') {
-                    line.pop();
-                }
-                if line.ends_with('\r') {
-                    line.pop();
-                }
-                Ok(Some(line))
+        loop {
+            let line = read_line(&mut self.reader, &mut self.line_buf).await?;
+            match line {
+                Some(line) if self.lenient && is_filler(&line) => continue,
+                line => return Ok(line),
             }
-            Err(e) => Err(e.into()),
         }
     }
 }
+
+/// Whether `line` is a blank or comment line, which lenient mode skips.
+fn is_filler(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Connects an adapter to a loopback peer that writes `input` and hangs up.
+    async fn adapter_reading(input: &'static str, lenient: bool) -> NdjsonAdapter {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let adapter = NdjsonAdapter::connect(&addr)
+            .await
+            .unwrap()
+            .lenient(lenient);
+        let (mut peer, _) = listener.accept().await.unwrap();
+        peer.write_all(input.as_bytes()).await.unwrap();
+        adapter
+    }
+
+    const INPUT: &str = "# fixture\n{\"id\":1}\n\n  \r\n# between\n{\"id\":2}\n\n";
+
+    #[tokio::test]
+    async fn test_lenient_recv_skips_blank_and_comment_lines() {
+        let mut adapter = adapter_reading(INPUT, true).await;
+        assert_eq!(adapter.recv().await.unwrap().as_deref(), Some("{\"id\":1}"));
+        assert_eq!(adapter.recv().await.unwrap().as_deref(), Some("{\"id\":2}"));
+        assert_eq!(adapter.recv().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_strict_recv_returns_every_line() {
+        let mut adapter = adapter_reading(INPUT, false).await;
+        assert_eq!(adapter.recv().await.unwrap().as_deref(), Some("# fixture"));
+        assert_eq!(adapter.recv().await.unwrap().as_deref(), Some("{\"id\":1}"));
+        assert_eq!(adapter.recv().await.unwrap().as_deref(), Some(""));
+    }
+}
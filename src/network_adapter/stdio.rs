@@ -1,22 +1,55 @@
 // src/network_adapter/stdio.rs
 
+use super::line::read_line;
 use super::r#trait::NetworkAdapter;
 use crate::error::Result;
 use async_trait::async_trait;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Stdin, Stdout};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 use tracing::info;
 
-/// A NetworkAdapter implementation that uses process stdin/stdout.
+type BoxedWriter = Box<dyn AsyncWrite + Unpin + Send + Sync>;
+type BoxedReader = Box<dyn AsyncRead + Unpin + Send + Sync>;
+
+/// A NetworkAdapter implementation that exchanges newline-delimited messages over
+/// a pair of stdio streams: either this process's own stdin/stdout, or the pipes
+/// of a spawned child process.
 pub struct StdioAdapter {
-    writer: Stdout,
-    reader: BufReader<Stdin>,
+    writer: BoxedWriter,
+    reader: BufReader<BoxedReader>,
+    line_buf: Vec<u8>,
+}
+
+impl Default for StdioAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StdioAdapter {
+    /// Creates an adapter over the current process's stdin and stdout.
     pub fn new() -> Self {
         Self {
-            writer: tokio::io::stdout(),
-            reader: BufReader::new(tokio::io::stdin()),
+            writer: Box::new(tokio::io::stdout()),
+            reader: BufReader::new(Box::new(tokio::io::stdin())),
+            line_buf: Vec::new(),
+        }
+    }
+
+    /// Creates an adapter over an arbitrary writer/reader pair, typically the piped
+    /// stdio of a child process.
+    ///
+    /// Messages are written to `child_stdin` and read from `child_stdout`. Any
+    /// `AsyncWrite`/`AsyncRead` halves work, which also makes in-memory streams
+    /// usable for testing.
+    pub fn from_child<W, R>(child_stdin: W, child_stdout: R) -> Self
+    where
+        W: AsyncWrite + Unpin + Send + Sync + 'static,
+        R: AsyncRead + Unpin + Send + Sync + 'static,
+    {
+        Self {
+            writer: Box::new(child_stdin),
+            reader: BufReader::new(Box::new(child_stdout)),
+            line_buf: Vec::new(),
         }
     }
 }
@@ -31,24 +64,43 @@ impl NetworkAdapter for StdioAdapter {
     }
 
     async fn recv(&mut self) -> Result<Option<String>> {
-        let mut line = String::new();
-        match self.reader.read_line(&mut line).await {
+        let line = read_line(&mut self.reader, &mut self.line_buf).await?;
+        if line.is_none() {
             // 0 bytes read means stdin was closed.
-            Ok(0) => {
-                info!("[Adapter] the client closed the stream");
-                Ok(None)
-            }
-            Ok(_) => {
-                // Remove the trailing newline character before returning.
-                if line.ends_with('\n') {
-                    line.pop();
-                    if line.ends_with('\r') {
-                        line.pop();
-                    }
-                }
-                Ok(Some(line))
-            }
-            Err(e) => Err(e.into()),
+            info!("[Adapter] the client closed the stream");
         }
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn test_from_child_parses_line_framed_messages() {
+        let scripted = b"{\"id\":1}\n{\"id\":2}\r\n{\"id\":3}".to_vec();
+        let mut adapter = StdioAdapter::from_child(tokio::io::sink(), Cursor::new(scripted));
+
+        assert_eq!(adapter.recv().await.unwrap().as_deref(), Some("{\"id\":1}"));
+        assert_eq!(adapter.recv().await.unwrap().as_deref(), Some("{\"id\":2}"));
+        // The final message has no trailing newline but must still be delivered.
+        assert_eq!(adapter.recv().await.unwrap().as_deref(), Some("{\"id\":3}"));
+        assert_eq!(adapter.recv().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_from_child_writes_newline_terminated_messages() {
+        let (writer, mut peer) = tokio::io::duplex(1024);
+        let mut adapter = StdioAdapter::from_child(writer, tokio::io::empty());
+        adapter.send("{\"id\":1}").await.unwrap();
+        drop(adapter);
+
+        let mut written = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut peer, &mut written)
+            .await
+            .unwrap();
+        assert_eq!(written, "{\"id\":1}\n");
     }
 }
@@ -6,4 +6,10 @@ use async_trait::async_trait;
 pub trait NetworkAdapter: Send + Sync {
     async fn send(&mut self, msg: &str) -> Result<()>;
     async fn recv(&mut self) -> Result<Option<String>>;
+
+    /// Whether a message may span several lines without breaking this adapter's
+    /// framing. Line-delimited and header-framed transports must keep the default.
+    fn supports_multiline(&self) -> bool {
+        false
+    }
 }
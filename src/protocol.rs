@@ -4,125 +4,252 @@
 //! for sending and receiving MCP messages. It is responsible for all `serde_json`
 //! operations, keeping the client/server logic clean and focused on application tasks.
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::network_adapter::NetworkAdapter;
 use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+use tracing::warn;
 #[cfg(feature = "schema-validation")]
 use tracing::{error, info};
 
+/// The default number of attempts made to send a message whose write fails with a
+/// transient I/O error.
+const DEFAULT_MAX_SEND_ATTEMPTS: u32 = 3;
+/// The pause before retrying a send, multiplied by the number of failed attempts.
+const SEND_RETRY_DELAY: Duration = Duration::from_millis(10);
+
 #[cfg(feature = "schema-validation")]
-mod validator {
+pub(crate) mod validator {
     use super::*;
     use crate::{types::LATEST_PROTOCOL_VERSION, Error};
     use jsonschema;
     use serde_json::Value;
+    use std::future::Future;
+    use std::path::Path;
     use tokio::sync::OnceCell;
 
-    // Conditional imports
-    #[cfg(not(test))]
-    use reqwest;
-    #[cfg(test)]
-    use std::{fs, path::Path};
-    #[cfg(test)]
-    use tracing::warn;
-
     const SCHEMA_URL_CONST: &str = "https://raw.githubusercontent.com/modelcontextprotocol/modelcontextprotocol/main/schema/**/schema.json";
 
-    static ASYNC_INIT_SCHEMA: OnceCell<jsonschema::Validator> = OnceCell::const_new();
+    /// Names a local schema file to use instead of fetching the official one, for
+    /// deployments without network access.
+    pub const SCHEMA_PATH_ENV: &str = "MCP_SCHEMA_PATH";
 
-    async fn get_or_init_schema() -> &'static jsonschema::Validator {
-        ASYNC_INIT_SCHEMA.get_or_init(|| async {
-            info!("[Validator] Initializing schema (async)...");
+    static ASYNC_INIT_SCHEMA: OnceCell<jsonschema::Validator> = OnceCell::const_new();
 
-            let schema_content_loader = || -> std::result::Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-                let schema_url_val = String::from(SCHEMA_URL_CONST).replace("**", LATEST_PROTOCOL_VERSION);
-
-                #[cfg(test)]
-                {
-                    let version = LATEST_PROTOCOL_VERSION;
-                    let local_schema_path_str = format!("schemas/{}/schema.json", version);
-                    let local_schema_path = Path::new(&local_schema_path_str);
-
-                    info!("[Validator] TEST MODE: Attempting to load schema from local file: {}", local_schema_path_str);
-                    if local_schema_path.exists() {
-                        match fs::read_to_string(local_schema_path) {
-                            Ok(file_content) => {
-                                match serde_json::from_str::<Value>(&file_content) {
-                                    Ok(schema_value) => {
-                                        info!("[Validator] TEST MODE: Successfully loaded schema from local file: {}", local_schema_path_str);
-                                        return Ok(schema_value);
-                                    }
-                                    Err(e) => {
-                                        warn!("[Validator] TEST MODE: Failed to parse local schema JSON from '{}'. Error: {}. Falling back to network fetch.", local_schema_path_str, e);
-                                        // Proceed to network fetch below
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                warn!("[Validator] TEST MODE: Failed to read local schema file '{}'. Error: {}. Falling back to network fetch.", local_schema_path_str, e);
-                                // Proceed to network fetch below
-                            }
-                        }
-                    } else {
-                        info!("[Validator] TEST MODE: Local schema file not found at '{}'. Falling back to network fetch.", local_schema_path_str);
-                        // Proceed to network fetch below
-                    }
-                }
+    /// Reads and parses a schema file from disk.
+    pub fn read_schema_file(path: &Path) -> Result<Value> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            Error::Other(format!(
+                "Failed to read schema file '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            Error::Other(format!(
+                "Failed to parse schema file '{}': {}",
+                path.display(),
+                e
+            ))
+        })
+    }
 
-                // Network fetch (executes if not test, or if test mode failed to return Ok above)
-                info!("[Validator] Fetching schema from URL: {}", schema_url_val);
-                let fetched_value = reqwest::blocking::get(schema_url_val)
-                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
-                    .json::<Value>()
-                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-                Ok(fetched_value)
-            };
-
-            let schema_value = match tokio::task::spawn_blocking(schema_content_loader).await {
-                Ok(Ok(val)) => val,
-                Ok(Err(e)) => panic!("Schema content loader failed: {}", e),
-                Err(join_err) => panic!("Spawn_blocking for schema content loader panicked: {}", join_err),
-            };
-
-            // Perform blocking schema compilation in spawn_blocking
-            let compiled_validator = match tokio::task::spawn_blocking(move || {
-                jsonschema::validator_for(&schema_value) // Using the original API call
-                    .expect("Failed to compile official MCP schema (from spawn_blocking)")
-            }).await {
-                Ok(validator) => validator,
-                Err(join_err) => panic!("Spawn_blocking for schema compilation panicked: {}", join_err),
-            };
+    /// Loads the official schema: from `MCP_SCHEMA_PATH` when set, from the bundled
+    /// copy under `schemas/` in tests, and from the network otherwise.
+    async fn load_schema() -> Result<Value> {
+        if let Ok(path) = std::env::var(SCHEMA_PATH_ENV) {
+            info!(
+                "[Validator] Loading schema from {}={}",
+                SCHEMA_PATH_ENV, path
+            );
+            return read_schema_file(Path::new(&path));
+        }
 
-            info!("[Validator] Schema successfully compiled (async).");
-            compiled_validator
-        }).await
-    }
-
-    /// Validates a given JSON-RPC message (Request, Response, etc.) against the root schema.
-    pub async fn validate_message(value: &Value) -> Result<()> {
-        let validator_instance = get_or_init_schema().await;
-        match validator_instance.validate(value) {
-            Ok(_) => Ok(()),
-            Err(validation_error) => {
-                // validation_error is a single ValidationError struct
-                Err(Error::Other(format!(
-                    "Schema validation failed: {}",
-                    validation_error.to_string() // Convert the single error to string
-                )))
+        #[cfg(test)]
+        {
+            let local_schema_path = format!("schemas/{}/schema.json", LATEST_PROTOCOL_VERSION);
+            info!(
+                "[Validator] TEST MODE: Attempting to load schema from local file: {}",
+                local_schema_path
+            );
+            match read_schema_file(Path::new(&local_schema_path)) {
+                Ok(schema_value) => return Ok(schema_value),
+                Err(e) => warn!(
+                    "[Validator] TEST MODE: {}. Falling back to network fetch.",
+                    e
+                ),
             }
         }
+
+        let schema_url = SCHEMA_URL_CONST.replace("**", LATEST_PROTOCOL_VERSION);
+        info!("[Validator] Fetching schema from URL: {}", schema_url);
+        let fetch_error =
+            |e: reqwest::Error| Error::Other(format!("Failed to fetch schema: {}", e));
+        reqwest::get(&schema_url)
+            .await
+            .map_err(fetch_error)?
+            .json::<Value>()
+            .await
+            .map_err(fetch_error)
+    }
+
+    /// Loads and compiles the official schema on first use. A failure is not
+    /// cached, so a later message tries again, e.g. once the network is back.
+    async fn get_or_init_schema() -> Result<&'static jsonschema::Validator> {
+        init_schema(&ASYNC_INIT_SCHEMA, load_schema()).await
+    }
+
+    /// Fills `cell` with the schema produced by `load`, compiled, unless it is
+    /// already set. Load and compile failures are returned rather than cached.
+    pub(crate) async fn init_schema(
+        cell: &OnceCell<jsonschema::Validator>,
+        load: impl Future<Output = Result<Value>>,
+    ) -> Result<&jsonschema::Validator> {
+        cell.get_or_try_init(|| async {
+            info!("[Validator] Initializing schema (async)...");
+            let schema_value = load.await?;
+
+            // Compiling the schema is CPU-bound, so keep it off the async workers.
+            let compiled_validator = tokio::task::spawn_blocking(move || {
+                jsonschema::validator_for(&schema_value).map_err(|e| {
+                    Error::Other(format!("Failed to compile official MCP schema: {}", e))
+                })
+            })
+            .await
+            .map_err(|e| Error::Other(format!("Schema compilation task failed: {}", e)))??;
+
+            info!("[Validator] Schema successfully compiled (async).");
+            Ok(compiled_validator)
+        })
+        .await
+    }
+
+    /// Compiles a caller-supplied schema, used instead of the official one.
+    pub fn compile(schema: &Value) -> Result<jsonschema::Validator> {
+        jsonschema::validator_for(schema)
+            .map_err(|e| Error::Other(format!("Invalid validation schema: {}", e)))
+    }
+
+    /// Validates a given JSON-RPC message (Request, Response, etc.) against the root
+    /// schema, or against `schema` when one was supplied.
+    pub async fn validate_message(
+        value: &Value,
+        schema: Option<&jsonschema::Validator>,
+    ) -> Result<()> {
+        let validator_instance = match schema {
+            Some(validator) => validator,
+            None => get_or_init_schema().await?,
+        };
+        validator_instance
+            .validate(value)
+            .map_err(|validation_error| {
+                Error::Other(format!(
+                    "Schema validation failed at {}: {}",
+                    validation_error.schema_path, validation_error
+                ))
+            })
     }
 }
 
+/// Which messages a [`ProtocolConnection`] checks against the MCP schema.
+#[cfg(feature = "schema-validation")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValidationMode {
+    /// Nothing is validated.
+    Off,
+    /// Messages are validated before they are sent.
+    #[default]
+    Outgoing,
+    /// Messages are validated both before sending and after receiving.
+    Both,
+}
+
 /// A connection that handles MCP protocol logic over a generic `NetworkAdapter`.
 pub struct ProtocolConnection<A: NetworkAdapter> {
     adapter: A,
+    max_send_attempts: u32,
+    pretty: bool,
+    #[cfg(feature = "schema-validation")]
+    validation: ValidationMode,
+    // A pre-loaded schema; `None` falls back to the official, fetched one.
+    #[cfg(feature = "schema-validation")]
+    schema: Option<std::sync::Arc<jsonschema::Validator>>,
 }
 
 impl<A: NetworkAdapter> ProtocolConnection<A> {
     /// Creates a new `ProtocolConnection` that will use the given adapter for communication.
     pub fn new(adapter: A) -> Self {
-        Self { adapter }
+        Self {
+            adapter,
+            max_send_attempts: DEFAULT_MAX_SEND_ATTEMPTS,
+            pretty: false,
+            #[cfg(feature = "schema-validation")]
+            validation: ValidationMode::default(),
+            #[cfg(feature = "schema-validation")]
+            schema: None,
+        }
+    }
+
+    /// Chooses which messages are validated against the schema. Defaults to
+    /// [`ValidationMode::Outgoing`].
+    #[cfg(feature = "schema-validation")]
+    pub fn set_validation(&mut self, mode: ValidationMode) {
+        self.validation = mode;
+    }
+
+    /// Validates against `schema` instead of fetching the official MCP schema from
+    /// the network. Fails if `schema` is not itself a valid JSON Schema.
+    #[cfg(feature = "schema-validation")]
+    pub fn with_schema(mut self, schema: serde_json::Value) -> Result<Self> {
+        self.schema = Some(std::sync::Arc::new(validator::compile(&schema)?));
+        Ok(self)
+    }
+
+    /// Like [`Self::with_schema`], reading the schema from a local file such as a
+    /// copy of the official `schema.json`.
+    ///
+    /// To replace the official schema for every connection instead, set the
+    /// `MCP_SCHEMA_PATH` environment variable to the file's path.
+    #[cfg(feature = "schema-validation")]
+    pub fn with_schema_file(self, path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let schema = validator::read_schema_file(path.as_ref())?;
+        self.with_schema(schema)
+    }
+
+    #[cfg(feature = "schema-validation")]
+    async fn validate(&self, value: &serde_json::Value) -> Result<()> {
+        validator::validate_message(value, self.schema.as_deref()).await
+    }
+
+    /// Pretty-prints outgoing messages, which makes raw frames easier to read
+    /// while debugging.
+    ///
+    /// This is meant for the in-memory [`DuplexAdapter`](crate::DuplexAdapter)
+    /// used in tests. Adapters whose framing relies on single-line messages, such
+    /// as NDJSON or stdio, report so through
+    /// [`NetworkAdapter::supports_multiline`] and the setting is ignored for them.
+    pub fn set_pretty(&mut self, pretty: bool) {
+        if pretty && !self.adapter.supports_multiline() {
+            warn!("[Protocol] Pretty output is not supported by this adapter; ignoring.");
+            return;
+        }
+        self.pretty = pretty;
+    }
+
+    /// Sets how many times a send is attempted before giving up, when the adapter
+    /// fails with a transient I/O error such as `Interrupted`. Other errors are
+    /// never retried. Defaults to 3; a value of 1 disables retrying.
+    pub fn with_max_send_attempts(mut self, attempts: u32) -> Self {
+        self.max_send_attempts = attempts.max(1);
+        self
+    }
+
+    /// Consumes the connection and returns the underlying adapter.
+    ///
+    /// Useful when the transport must be handed off after the MCP exchange, e.g. to
+    /// continue speaking a different protocol over the same stream.
+    pub fn into_inner(self) -> A {
+        self.adapter
     }
 
     /// Serializes a message struct into a JSON string and sends it via the adapter.
@@ -130,31 +257,44 @@ impl<A: NetworkAdapter> ProtocolConnection<A> {
         let value = serde_json::to_value(&msg)?;
 
         #[cfg(feature = "schema-validation")]
-        {
-            match validator::validate_message(&value).await {
-                Ok(_) => {
-                    info!(
-                        "[Validator] Message is valid after async validation: {}",
-                        value
-                    );
-                }
-                Err(e) => {
-                    // Log the detailed error here before returning it
-                    error!(
-                        "[Validator] Schema validation failed for value {}: {}",
-                        value, e
-                    );
-                    return Err(e); // Propagate the error
-                }
+        if self.validation != ValidationMode::Off {
+            if let Err(e) = self.validate(&value).await {
+                error!(
+                    "[Validator] Schema validation failed for value {}: {}",
+                    value, e
+                );
+                return Err(e);
             }
+            info!("[Validator] Outgoing message is valid: {}", value);
         }
-        let json_string = serde_json::to_string(&value)?;
-        self.adapter.send(&json_string).await
+        let json_string = if self.pretty {
+            serde_json::to_string_pretty(&value)?
+        } else {
+            serde_json::to_string(&value)?
+        };
+        self.send_with_retry(&json_string).await
     }
 
     /// Sends a raw, already-serialized JSON string over the adapter.
     pub async fn send_raw(&mut self, json_string: &str) -> Result<()> {
-        self.adapter.send(json_string).await
+        self.send_with_retry(json_string).await
+    }
+
+    async fn send_with_retry(&mut self, json_string: &str) -> Result<()> {
+        let mut attempt = 1;
+        loop {
+            match self.adapter.send(json_string).await {
+                Err(e) if attempt < self.max_send_attempts && is_transient(&e) => {
+                    warn!(
+                        "[Protocol] Transient send failure (attempt {}/{}): {}",
+                        attempt, self.max_send_attempts, e
+                    );
+                    tokio::time::sleep(SEND_RETRY_DELAY * attempt).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
     }
 
     /// Receives a raw JSON string from the adapter and deserializes it into a message struct.
@@ -165,6 +305,18 @@ impl<A: NetworkAdapter> ProtocolConnection<A> {
                 if json_string.trim().is_empty() {
                     return Ok(None);
                 }
+                #[cfg(feature = "schema-validation")]
+                if self.validation == ValidationMode::Both {
+                    let value: serde_json::Value = serde_json::from_str(&json_string)?;
+                    if let Err(e) = self.validate(&value).await {
+                        error!(
+                            "[Validator] Schema validation failed for incoming value {}: {}",
+                            value, e
+                        );
+                        return Err(e);
+                    }
+                    return Ok(Some(serde_json::from_value(value)?));
+                }
                 let msg = serde_json::from_str::<T>(&json_string)?;
                 Ok(Some(msg))
             }
@@ -173,6 +325,18 @@ impl<A: NetworkAdapter> ProtocolConnection<A> {
     }
 }
 
+/// Whether a failed send is worth retrying, judged by the underlying I/O error kind.
+fn is_transient(error: &Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        error,
+        Error::Io(e) if matches!(
+            e.kind(),
+            ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+        )
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -224,6 +388,7 @@ mod tests {
             params: Some(CallToolParams {
                 name: "test-tool".to_string(),
                 arguments: json!({ "arg1": "value1" }),
+                meta: None,
             }),
         };
 
@@ -247,4 +412,206 @@ mod tests {
         let received: Option<Response<()>> = proto_conn.recv_message().await.unwrap();
         assert!(received.is_none());
     }
+
+    #[tokio::test]
+    async fn test_into_inner_recovers_adapter() {
+        let mut proto_conn = ProtocolConnection::new(InMemoryAdapter::new());
+        proto_conn.send_raw("{\"pending\":true}").await.unwrap();
+
+        // The recovered adapter still holds whatever the connection wrote to it.
+        let mut adapter = proto_conn.into_inner();
+        assert_eq!(
+            adapter.recv().await.unwrap(),
+            Some("{\"pending\":true}".to_string())
+        );
+    }
+
+    /// Fails every write with the given error kind, counting the attempts.
+    struct FailingAdapter {
+        kind: std::io::ErrorKind,
+        attempts: u32,
+    }
+
+    #[async_trait]
+    impl NetworkAdapter for FailingAdapter {
+        async fn send(&mut self, _msg: &str) -> Result<()> {
+            self.attempts += 1;
+            Err(std::io::Error::from(self.kind).into())
+        }
+        async fn recv(&mut self) -> Result<Option<String>> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_are_bounded_and_skip_fatal_errors() {
+        let mut transient = ProtocolConnection::new(FailingAdapter {
+            kind: std::io::ErrorKind::Interrupted,
+            attempts: 0,
+        });
+        assert!(transient.send_raw("{}").await.is_err());
+        assert_eq!(transient.into_inner().attempts, DEFAULT_MAX_SEND_ATTEMPTS);
+
+        let mut fatal = ProtocolConnection::new(FailingAdapter {
+            kind: std::io::ErrorKind::BrokenPipe,
+            attempts: 0,
+        });
+        assert!(fatal.send_raw("{}").await.is_err());
+        assert_eq!(fatal.into_inner().attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_pretty_output_is_multiline_and_round_trips() {
+        let (left, right) = crate::network_adapter::duplex_pair();
+        let mut sender = ProtocolConnection::new(left);
+        let mut raw_receiver = right;
+        sender.set_pretty(true);
+
+        let request = Request {
+            jsonrpc: "2.0".to_string(),
+            id: RequestId::Num(1),
+            method: "tools/call".to_string(),
+            params: Some(CallToolParams {
+                name: "pretty".to_string(),
+                arguments: json!({ "nested": { "a": 1 } }),
+                meta: None,
+            }),
+        };
+        sender.send_serializable(request.clone()).await.unwrap();
+
+        let raw = raw_receiver.recv().await.unwrap().unwrap();
+        assert!(raw.contains('\n'));
+        let parsed: Request<CallToolParams> = serde_json::from_str(&raw).unwrap();
+        assert_eq!(parsed, request);
+    }
+
+    #[tokio::test]
+    async fn test_pretty_is_ignored_for_single_line_adapters() {
+        let mut proto_conn = ProtocolConnection::new(InMemoryAdapter::new());
+        proto_conn.set_pretty(true);
+        proto_conn
+            .send_serializable(json!({ "nested": { "a": 1 } }))
+            .await
+            .unwrap();
+
+        let raw = proto_conn.into_inner().recv().await.unwrap().unwrap();
+        assert!(!raw.contains('\n'));
+    }
+
+    #[cfg(feature = "schema-validation")]
+    fn strict_jsonrpc_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["jsonrpc"],
+            "properties": { "jsonrpc": { "const": "2.0" } }
+        })
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[tokio::test]
+    async fn test_outgoing_validation_rejects_malformed_messages() {
+        let mut proto_conn = ProtocolConnection::new(InMemoryAdapter::new())
+            .with_schema(strict_jsonrpc_schema())
+            .unwrap();
+        proto_conn.set_validation(ValidationMode::Outgoing);
+
+        let err = proto_conn
+            .send_serializable(json!({ "jsonrpc": "1.0", "id": 1 }))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::Other(msg) if msg.contains("/properties/jsonrpc/const")),
+            "unexpected error: {err}"
+        );
+        assert!(proto_conn.into_inner().recv().await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[tokio::test]
+    async fn test_validation_off_lets_malformed_messages_through() {
+        let mut proto_conn = ProtocolConnection::new(InMemoryAdapter::new())
+            .with_schema(strict_jsonrpc_schema())
+            .unwrap();
+        proto_conn.set_validation(ValidationMode::Off);
+
+        proto_conn
+            .send_serializable(json!({ "jsonrpc": "1.0", "id": 1 }))
+            .await
+            .unwrap();
+        let received: Option<serde_json::Value> = proto_conn.recv_message().await.unwrap();
+        assert_eq!(received, Some(json!({ "jsonrpc": "1.0", "id": 1 })));
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[tokio::test]
+    async fn test_both_mode_validates_incoming_messages() {
+        let mut proto_conn = ProtocolConnection::new(InMemoryAdapter::new())
+            .with_schema(strict_jsonrpc_schema())
+            .unwrap();
+        proto_conn
+            .send_raw("{\"jsonrpc\":\"1.0\",\"id\":1}")
+            .await
+            .unwrap();
+        proto_conn.set_validation(ValidationMode::Both);
+
+        let result: Result<Option<serde_json::Value>> = proto_conn.recv_message().await;
+        assert!(matches!(result, Err(Error::Other(_))));
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[tokio::test]
+    async fn test_bundled_schema_file_accepts_initialize_request() {
+        let path = format!(
+            "schemas/{}/schema.json",
+            crate::types::LATEST_PROTOCOL_VERSION
+        );
+        let mut proto_conn = ProtocolConnection::new(InMemoryAdapter::new())
+            .with_schema_file(&path)
+            .unwrap();
+        proto_conn.set_validation(ValidationMode::Both);
+
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": crate::types::LATEST_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "schema-test", "version": "1.0.0" }
+            }
+        });
+        proto_conn
+            .send_serializable(initialize.clone())
+            .await
+            .unwrap();
+        let received: Option<serde_json::Value> = proto_conn.recv_message().await.unwrap();
+        assert_eq!(received, Some(initialize));
+
+        assert!(ProtocolConnection::new(InMemoryAdapter::new())
+            .with_schema_file("schemas/missing/schema.json")
+            .is_err());
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[tokio::test]
+    async fn test_schema_load_and_compile_failures_are_errors() {
+        let cell = tokio::sync::OnceCell::new();
+
+        let unreachable = async { Err(Error::Other("Failed to fetch schema: offline".into())) };
+        let err = validator::init_schema(&cell, unreachable)
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, Error::Other(msg) if msg.contains("offline")));
+
+        let not_a_schema = async { Ok(json!({ "type": 42 })) };
+        let err = validator::init_schema(&cell, not_a_schema)
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, Error::Other(msg) if msg.contains("compile")));
+
+        // Neither failure is cached: a later load still fills the cell.
+        let schema = async { Ok(strict_jsonrpc_schema()) };
+        let validator = validator::init_schema(&cell, schema).await.unwrap();
+        assert!(validator.is_valid(&json!({ "jsonrpc": "2.0" })));
+    }
 }
@@ -0,0 +1,107 @@
+//! Hooks for recording request volume, errors, and latency.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Receives measurements from every request a server handles. Installed with
+/// [`Server::with_metrics`](super::Server::with_metrics).
+///
+/// All methods default to doing nothing, so implementations only need to
+/// override what they care about. They are called on the session's task and
+/// should return quickly.
+pub trait MetricsRecorder: Send + Sync {
+    /// Called when a request is received, before it is handled.
+    fn on_request(&self, _method: &str) {}
+
+    /// Called when a request is answered with a JSON-RPC error or fails without
+    /// a response. Tool results with `is_error` set are not counted.
+    fn on_error(&self, _method: &str) {}
+
+    /// Called with the time taken to handle a request, errors included.
+    fn on_latency(&self, _method: &str, _duration: Duration) {}
+
+    /// Called when a session ends, with the totals for that one connection.
+    fn on_session_end(&self, _stats: &SessionStats) {}
+}
+
+/// Lets a recorder be shared, e.g. to read its totals while a server uses it.
+impl<T: MetricsRecorder + ?Sized> MetricsRecorder for std::sync::Arc<T> {
+    fn on_request(&self, method: &str) {
+        (**self).on_request(method)
+    }
+
+    fn on_error(&self, method: &str) {
+        (**self).on_error(method)
+    }
+
+    fn on_latency(&self, method: &str, duration: Duration) {
+        (**self).on_latency(method, duration)
+    }
+
+    fn on_session_end(&self, stats: &SessionStats) {
+        (**self).on_session_end(stats)
+    }
+}
+
+/// Totals for a single session, see [`ServerSession::stats`](super::ServerSession::stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    /// The number of requests received, `initialize` excluded.
+    pub requests: u64,
+    /// The number of requests that ended in an error.
+    pub errors: u64,
+    /// The number of notifications the server sent. Requests the server makes
+    /// of the client, such as `roots/list`, are not counted.
+    pub notifications_sent: u64,
+}
+
+/// A recorder that discards everything; used when no recorder is installed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl MetricsRecorder for NoopMetrics {}
+
+/// A recorder keeping running totals in memory, across all methods.
+#[derive(Debug, Default)]
+pub struct AtomicMetrics {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_micros: AtomicU64,
+}
+
+impl AtomicMetrics {
+    /// Creates a recorder with all totals at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of requests received.
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// The number of requests that ended in an error.
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// The time spent handling all requests combined.
+    pub fn total_latency(&self) -> Duration {
+        Duration::from_micros(self.latency_micros.load(Ordering::Relaxed))
+    }
+}
+
+impl MetricsRecorder for AtomicMetrics {
+    fn on_request(&self, _method: &str) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_error(&self, _method: &str) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_latency(&self, _method: &str, duration: Duration) {
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        self.latency_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+}
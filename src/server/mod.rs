@@ -5,9 +5,13 @@
 
 // 1. Declare the child modules. The code for these lives in
 //    `server/server.rs` and `server/session.rs`.
+mod metrics;
+mod result_log;
+#[allow(clippy::module_inception)]
 mod server;
 pub mod session; // Made public for integration tests
 
 // 2. Publicly re-export the types that consumers of our library will use.
-pub use server::Server;
+pub use metrics::{AtomicMetrics, MetricsRecorder, NoopMetrics, SessionStats};
+pub use server::{AuditEvent, ConfigError, ConnectionInfo, Server};
 pub use session::{ConnectionHandle, ServerSession}; // Also re-export ServerSession
@@ -0,0 +1,57 @@
+//! Appends tool call results to an NDJSON file for offline replay and analysis.
+
+use crate::error::Result;
+use crate::types::CallToolResult;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// The log file behind [`Server::with_result_log`](super::Server::with_result_log).
+///
+/// Shared by every session of a server; the lock keeps records from concurrent
+/// calls on separate lines.
+pub(crate) struct ResultLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl ResultLog {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Appends one record holding the tool name, its arguments, and its result.
+    pub(crate) async fn append(
+        &self,
+        tool_name: &str,
+        arguments: &Value,
+        result: &CallToolResult,
+    ) -> Result<()> {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default();
+        let mut line = serde_json::to_string(&json!({
+            "timestampMs": timestamp_ms,
+            "tool": tool_name,
+            "arguments": arguments,
+            "result": result,
+        }))?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
@@ -1,22 +1,34 @@
 //! Defines the main `Server` struct and its builder API for setting up handlers.
 
+use super::metrics::MetricsRecorder;
+use super::result_log::ResultLog;
+#[cfg(feature = "schema-validation")]
+use super::session::ToolInputValidator;
 use super::session::{ConnectionHandle, ServerSession};
 use crate::{
-    error::Result,
+    error::{Error, Result},
     network_adapter::NetworkAdapter,
     protocol::ProtocolConnection,
     types::{
-        CallToolResult, GetPromptResult, ListPromptsResult, ReadResourceResult, Resource, Tool,
+        CallToolResult, ErrorData, GetPromptResult, ListPromptsResult, ListToolsChangedParams,
+        Notification, ReadResourceResult, Resource, ResourceContents, ResourceTemplate, Tool,
+        CONNECTION_LIMIT_REACHED, INVALID_PARAMS, LATEST_PROTOCOL_VERSION,
     },
+    ToolArgumentsDescriptor,
 };
 use serde_json::Value;
+use std::any::Any;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime};
 use std::{future::Future, pin::Pin, sync::Arc};
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
+use tokio::sync::mpsc;
 use tracing::{error, info};
 
-use crate::types::Content; // Added for error reporting in typed handlers
 use serde::de::DeserializeOwned; // For register_tool_typed
 
 // Type alias for the boxed future returned by handlers
@@ -44,7 +56,42 @@ pub(crate) enum ToolHandler {
     ),
 }
 
+/// Wraps a handler taking raw JSON arguments into a [`ToolHandler::Untyped`].
+fn untyped_handler<F, Fut>(handler: F) -> Arc<ToolHandler>
+where
+    F: Fn(ConnectionHandle, Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
+{
+    Arc::new(ToolHandler::Untyped(Box::new(
+        move |conn_handle, json_args_arc: Arc<Value>| {
+            // The public handler takes the arguments by value.
+            Box::pin(handler(conn_handle, (*json_args_arc).clone()))
+        },
+    )))
+}
+
+/// A tool as the server stores it.
+pub(crate) struct RegisteredTool {
+    pub(crate) tool: Tool,
+    pub(crate) handler: Arc<ToolHandler>,
+    /// Checks arguments against `tool.input_schema`, compiled on first use.
+    #[cfg(feature = "schema-validation")]
+    pub(crate) input_validator: ToolInputValidator,
+}
+
+impl RegisteredTool {
+    fn new(tool: Tool, handler: Arc<ToolHandler>) -> Arc<Self> {
+        Arc::new(Self {
+            #[cfg(feature = "schema-validation")]
+            input_validator: ToolInputValidator::new(tool.input_schema.clone()),
+            tool,
+            handler,
+        })
+    }
+}
+
 // --- Handler Type Definitions ---
+pub(crate) type ToolMap = HashMap<String, Arc<RegisteredTool>>;
 // The old ToolHandler type alias is replaced by the enum above.
 
 pub(crate) type ListResourcesHandler = Arc<
@@ -52,6 +99,11 @@ pub(crate) type ListResourcesHandler = Arc<
         + Send
         + Sync,
 >;
+pub(crate) type ListResourceTemplatesHandler = Arc<
+    dyn Fn(ConnectionHandle) -> Pin<Box<dyn Future<Output = Result<Vec<ResourceTemplate>>> + Send>>
+        + Send
+        + Sync,
+>;
 pub(crate) type ReadResourceHandler = Arc<
     dyn Fn(
             ConnectionHandle,
@@ -65,6 +117,25 @@ pub(crate) type ListPromptsHandler = Arc<
         + Send
         + Sync,
 >;
+pub(crate) type PromptCompletionHandler = Arc<
+    dyn Fn(String, String) -> Pin<Box<dyn Future<Output = Result<Vec<String>>> + Send>>
+        + Send
+        + Sync,
+>;
+pub(crate) type UnknownMethodHandler = Arc<
+    dyn Fn(ConnectionHandle, String, Value) -> Pin<Box<dyn Future<Output = Result<Value>> + Send>>
+        + Send
+        + Sync,
+>;
+pub(crate) type NotificationHandler = Arc<
+    dyn Fn(ConnectionHandle, Value) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>
+        + Send
+        + Sync,
+>;
+pub(crate) type AuditHandler = Arc<dyn Fn(AuditEvent) + Send + Sync>;
+pub(crate) type ToolGuard = Arc<dyn Fn(&str, &Value) -> Result<()> + Send + Sync>;
+pub(crate) type ResultTransform = Arc<dyn Fn(&mut CallToolResult) + Send + Sync>;
+pub(crate) type ConnectionStateInit = Arc<dyn Fn() -> Arc<dyn Any + Send + Sync> + Send + Sync>;
 pub(crate) type GetPromptHandler = Arc<
     dyn Fn(
             ConnectionHandle,
@@ -75,6 +146,96 @@ pub(crate) type GetPromptHandler = Arc<
         + Sync,
 >;
 
+/// A record of a single `tools/call`, delivered to the callback set with
+/// [`Server::on_audit`] once the call has finished.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    /// The remote address of the client, when known (e.g. for TCP connections).
+    pub peer: Option<String>,
+    /// The name of the tool that was called.
+    pub tool_name: String,
+    /// A hash of the call's arguments. Identical arguments produce identical hashes
+    /// within a process, which allows correlating calls without retaining the input.
+    pub args_hash: u64,
+    /// The raw arguments, only populated when [`Server::audit_full_args`] is enabled.
+    pub args: Option<Value>,
+    /// `true` if the handler returned a result that is not flagged `is_error`.
+    pub success: bool,
+    /// How long the call took, including the tool guard and the handler.
+    pub duration: Duration,
+}
+
+/// A client with an open session, as reported by [`Server::connected_clients`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// The remote address of the client, when known (e.g. for TCP connections).
+    pub peer_addr: Option<String>,
+    /// When the session started.
+    pub connected_at: SystemTime,
+}
+
+/// A live session in the server's registry. The entry is stale once `notifier` is
+/// closed, which happens when the session ends and drops its receiver.
+pub(crate) struct SessionEntry {
+    pub(crate) notifier: mpsc::Sender<String>,
+    pub(crate) info: ConnectionInfo,
+}
+
+/// A configuration problem detected by [`Server::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// No tools, resource handlers, or prompt handlers were registered.
+    NoHandlers,
+    /// A tool was registered with an empty name.
+    EmptyToolName,
+    /// More than one tool was registered under the same name; only the last one is kept.
+    DuplicateTool(String),
+    /// A tool's `input_schema` is not a JSON object.
+    InvalidInputSchema(String),
+    /// A tool is annotated as both read-only and destructive.
+    ContradictoryAnnotations(String),
+    /// A handler was registered without the handler it depends on, e.g.
+    /// `prompts/get` without `prompts/list`.
+    MissingHandler {
+        registered: &'static str,
+        missing: &'static str,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::NoHandlers => write!(f, "no tools, resources, or prompts are registered"),
+            ConfigError::EmptyToolName => write!(f, "a tool is registered with an empty name"),
+            ConfigError::DuplicateTool(name) => {
+                write!(f, "tool '{}' is registered more than once", name)
+            }
+            ConfigError::InvalidInputSchema(name) => {
+                write!(
+                    f,
+                    "tool '{}' has an input schema that is not an object",
+                    name
+                )
+            }
+            ConfigError::ContradictoryAnnotations(name) => write!(
+                f,
+                "tool '{}' is annotated as both read-only and destructive",
+                name
+            ),
+            ConfigError::MissingHandler {
+                registered,
+                missing,
+            } => write!(
+                f,
+                "a '{}' handler is registered without a '{}' handler",
+                registered, missing
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 /// A high-level, asynchronous server for handling MCP requests.
 ///
 /// This struct uses a builder pattern to register handlers for different MCP methods.
@@ -103,8 +264,10 @@ pub(crate) type GetPromptHandler = Arc<
 ///             Ok(CallToolResult {
 ///                 content: vec![Content::Text {
 ///                     text: "Success!".to_string(),
+///                     annotations: None,
 ///                 }],
 ///                 is_error: false,
+///                 ..Default::default()
 ///             })
 ///         },
 ///     );
@@ -118,12 +281,115 @@ pub(crate) type GetPromptHandler = Arc<
 #[derive(Default, Clone)]
 pub struct Server {
     pub(crate) name: String,
-    // Consolidated tools and handlers: tool_name -> (Tool_metadata, Arc_to_handler_enum)
-    pub(crate) tools_and_handlers: HashMap<String, (Tool, Arc<ToolHandler>)>,
+    pub(crate) version: Option<String>,
+    pub(crate) instructions: Option<String>,
+    // Consolidated tools and handlers: tool_name -> (Tool_metadata, Arc_to_handler_enum).
+    // Shared by every clone of the server so that tools can be added while serving.
+    pub(crate) tools_and_handlers: Arc<RwLock<ToolMap>>,
+    // The live sessions, used to broadcast list changes and to list connected clients.
+    pub(crate) sessions: Arc<Mutex<Vec<SessionEntry>>>,
     pub(crate) list_resources_handler: Option<ListResourcesHandler>,
+    pub(crate) list_resource_templates_handler: Option<ListResourceTemplatesHandler>,
     pub(crate) read_resource_handler: Option<ReadResourceHandler>,
+    // Resources registered with `register_resource`, in registration order.
+    pub(crate) static_resources: Vec<(Resource, ResourceContents)>,
     pub(crate) list_prompts_handler: Option<ListPromptsHandler>,
     pub(crate) get_prompt_handler: Option<GetPromptHandler>,
+    // prompt_name -> completion handler for that prompt's arguments
+    pub(crate) prompt_completion_handlers: HashMap<String, PromptCompletionHandler>,
+    pub(crate) unknown_method_handler: Option<UnknownMethodHandler>,
+    // notification method -> handler for notifications sent by the client
+    pub(crate) notification_handlers: HashMap<String, NotificationHandler>,
+    pub(crate) tool_guard: Option<ToolGuard>,
+    pub(crate) result_transform: Option<ResultTransform>,
+    pub(crate) connection_state_init: Option<ConnectionStateInit>,
+    pub(crate) audit_handler: Option<AuditHandler>,
+    pub(crate) audit_full_args: bool,
+    pub(crate) result_log: Option<Arc<ResultLog>>,
+    pub(crate) metrics: Option<Arc<dyn MetricsRecorder>>,
+    pub(crate) tool_guard_error_code: Option<i32>,
+    pub(crate) duplicate_tool_names: Vec<String>,
+    // Protocol versions offered during `initialize`, most preferred first.
+    // Empty means only `LATEST_PROTOCOL_VERSION`.
+    pub(crate) supported_protocol_versions: Vec<String>,
+    pub(crate) max_connections: Option<usize>,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) tool_timeout: Option<Duration>,
+    pub(crate) skip_initialize: bool,
+    pub(crate) idle_timeout: Option<Duration>,
+    // Capacity of each session's outgoing message queue; `None` means the default.
+    pub(crate) notification_buffer: Option<usize>,
+    pub(crate) notification_send_timeout: Option<Duration>,
+    #[cfg(feature = "schema-validation")]
+    pub(crate) validate_tool_input: bool,
+    // Set once `tcp_listen_with_shutdown` begins shutting down; shared by clones.
+    pub(crate) shutting_down: Arc<AtomicBool>,
+}
+
+/// Wraps a typed tool `handler` so it receives its arguments turned into `Args`
+/// by `deserializer`. Arguments that don't convert get an error result naming
+/// `tool`'s expected schema, and the handler is not called.
+fn typed_handler<Args, D, Fut, F>(tool: &Tool, deserializer: D, handler: F) -> Arc<ToolHandler>
+where
+    Args: Send + Sync + 'static,
+    D: Fn(Value) -> Result<Args> + Send + Sync + 'static,
+    Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
+    F: Fn(ConnectionHandle, Args) -> Fut + Send + Sync + 'static,
+{
+    let user_handler_arc = Arc::new(handler);
+    let deserializer = Arc::new(deserializer);
+    let tool_name_clone_for_error = tool.name.clone(); // For error messages
+    let expected_schema: Arc<str> = serde_json::to_string_pretty(&tool.input_schema)
+        .unwrap_or_default()
+        .into();
+
+    Arc::new(ToolHandler::Typed(Box::new(
+        move |conn_handle: ConnectionHandle, json_args: Arc<Value>| {
+            let user_handler = Arc::clone(&user_handler_arc);
+            let deserializer = Arc::clone(&deserializer);
+            let tool_name = tool_name_clone_for_error.clone();
+            let expected_schema = Arc::clone(&expected_schema);
+
+            Box::pin(async move {
+                match deserializer((*json_args).clone()) {
+                    Ok(typed_args) => (user_handler)(conn_handle, typed_args).await,
+                    Err(e) => {
+                        // Report serde's own message rather than the wrapped one.
+                        let reason = match e {
+                            crate::Error::Serialization(e) => e.to_string(),
+                            other => other.to_string(),
+                        };
+                        error!(tool_name = %tool_name, error = %reason, "Failed to deserialize arguments for tool");
+                        Ok(CallToolResult::error(format!(
+                            "Invalid arguments for tool '{}': {}. Expected schema: {}",
+                            tool_name, reason, expected_schema
+                        )))
+                    }
+                }
+            })
+        },
+    )))
+}
+
+/// Turns positional `arguments` into an object keyed by `names`, in order. Anything
+/// other than an array is returned unchanged.
+fn positional_to_named(names: &[&str], arguments: Value) -> Result<Value> {
+    let Value::Array(values) = arguments else {
+        return Ok(arguments);
+    };
+    if values.len() > names.len() {
+        return Err(Error::InvalidParams(format!(
+            "expected at most {} positional arguments, got {}",
+            names.len(),
+            values.len()
+        )));
+    }
+    let named = names
+        .iter()
+        .map(|name| name.to_string())
+        .zip(values)
+        .collect::<serde_json::Map<_, _>>();
+    Ok(Value::Object(named))
 }
 
 impl Server {
@@ -140,34 +406,227 @@ impl Server {
         }
     }
 
+    /// Sets the version reported in `serverInfo` during the handshake. Defaults to
+    /// the version of this SDK.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Sets the `instructions` sent to clients during the handshake, describing
+    /// how the server is meant to be used.
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Caps the number of clients [`Self::tcp_listen`] serves at once. Connections
+    /// beyond the cap are sent a `CONNECTION_LIMIT_REACHED` error and closed.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = Some(max);
+        self
+    }
+
+    /// Closes a session once its client has sent nothing for `timeout`, so clients
+    /// that connect and go quiet don't hold a session open forever. Queued
+    /// notifications are still flushed before the session ends.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how many notifications and server-to-client requests each session
+    /// queues before [`ConnectionHandle::send_notification`] has to wait for room.
+    /// Defaults to 32; raise it for tools that emit notifications in bursts.
+    /// A capacity of 0 is treated as 1.
+    pub fn notification_buffer(mut self, capacity: usize) -> Self {
+        self.notification_buffer = Some(capacity);
+        self
+    }
+
+    /// Makes [`ConnectionHandle::send_notification`] fail with
+    /// [`Error::Timeout`](crate::error::Error::Timeout) when the session's queue
+    /// stays full for `timeout`, instead of waiting for room indefinitely.
+    pub fn notification_send_timeout(mut self, timeout: Duration) -> Self {
+        self.notification_send_timeout = Some(timeout);
+        self
+    }
+
+    /// Starts every session already initialized, so the first message is
+    /// dispatched like any other instead of having to be `initialize`. Handlers
+    /// then see default (empty) client capabilities.
+    ///
+    /// This breaks the MCP handshake and is only meant for trusted transports,
+    /// e.g. stdio pipelines whose client uses [`Client::new_without_handshake`].
+    /// A client that does send `initialize` is still answered normally.
+    ///
+    /// [`Client::new_without_handshake`]: crate::Client::new_without_handshake
+    pub fn skip_initialize(mut self, skip: bool) -> Self {
+        self.skip_initialize = skip;
+        self
+    }
+
+    /// Checks the arguments of every `tools/call` against the tool's `input_schema`
+    /// before its handler runs, answering `INVALID_PARAMS` when they don't match.
+    ///
+    /// Typed tools already reject arguments that don't deserialize; this covers
+    /// tools registered with [`Self::register_tool`] too. Tools whose schema is
+    /// `null` are not checked.
+    #[cfg(feature = "schema-validation")]
+    pub fn validate_tool_input(mut self, enabled: bool) -> Self {
+        self.validate_tool_input = enabled;
+        self
+    }
+
+    /// Creates per-connection state with `init` whenever a session starts, e.g. a
+    /// session id or the user a connection authenticated as. Handlers get it back
+    /// with [`ConnectionHandle::state`].
+    pub fn with_connection_state_init<T, F>(mut self, init: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        self.connection_state_init = Some(Arc::new(move || Arc::new(init())));
+        self
+    }
+
+    /// Bounds how long handling any single request may take. A tool, resource or
+    /// prompt handler still running when `timeout` elapses is dropped and the
+    /// client is sent a `REQUEST_TIMED_OUT` error instead.
+    pub fn set_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Bounds how long a tool handler may run, overriding
+    /// [`Self::set_request_timeout`] for `tools/call`. A handler still running when
+    /// `timeout` elapses is dropped, cancelling it, and the client is sent a
+    /// `REQUEST_TIMED_OUT` error; the session then moves on to the next request.
+    pub fn tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
     /// Registers a tool, its metadata, and its execution handler at the same time.
     pub fn register_tool<F, Fut>(mut self, tool: Tool, handler: F) -> Self
     where
         F: Fn(ConnectionHandle, Value) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
     {
-        let tool_name = tool.name.clone();
-        let handler_arc = Arc::new(ToolHandler::Untyped(Box::new(
-            move |conn_handle, json_args_arc| {
-                // The original handler expects Value, not Arc<Value>.
-                // We clone from Arc if the original handler needs ownership,
-                // or pass a reference if it can work with &Value.
-                // For now, let's assume the original handler took Value by value.
-                // The public API of register_tool took `Value`, not `Arc<Value>`.
-                // So, the closure it stores needs to match the new internal ToolHandler::Untyped signature.
-                // This means the outer Box<dyn Fn...> for Untyped should match the new signature.
-                // The handler passed to `register_tool` is `F: Fn(ConnectionHandle, Value) -> Fut`
-                // The new Untyped signature is `Fn(ConnectionHandle, Arc<Value>) -> BoxedFuture<...>`
-                // So we need to adapt:
-                let value_clone = (*json_args_arc).clone(); // Clone Value from Arc<Value>
-                Box::pin(handler(conn_handle, value_clone))
-            },
-        )));
-        self.tools_and_handlers
-            .insert(tool_name, (tool, handler_arc));
+        self.insert_tool(tool.name.clone(), tool, untyped_handler(handler));
         self
     }
 
+    /// Adds a tool to a server that may already be serving clients.
+    ///
+    /// Unlike [`Self::register_tool`], this takes `&self`: the tool map is shared by
+    /// every clone of the server, so a clone kept aside before calling
+    /// [`Self::tcp_listen`] can be used to change the tools at runtime. Connected
+    /// clients are sent `notifications/tools/list_changed`, waiting for room in a
+    /// session's full queue like [`ConnectionHandle::send_notification`] does. A
+    /// tool with the same name is replaced.
+    pub async fn add_tool<F, Fut>(&self, tool: Tool, handler: F)
+    where
+        F: Fn(ConnectionHandle, Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
+    {
+        self.replace_tool(tool, untyped_handler(handler)).await;
+    }
+
+    /// Like [`Self::add_tool`], for a handler that accepts strongly-typed
+    /// arguments as with [`Self::register_tool_typed`].
+    pub async fn add_tool_typed<Args, Fut, F>(&self, tool: Tool, handler: F)
+    where
+        Args: DeserializeOwned + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
+        F: Fn(ConnectionHandle, Args) -> Fut + Send + Sync + 'static,
+    {
+        let handler = typed_handler(
+            &tool,
+            |json_args| Ok(serde_json::from_value::<Args>(json_args)?),
+            handler,
+        );
+        self.replace_tool(tool, handler).await;
+    }
+
+    /// Removes a tool from a server that may already be serving clients, notifying
+    /// connected clients when it existed. Returns whether a tool was removed.
+    pub async fn remove_tool(&self, name: &str) -> bool {
+        let removed = self
+            .tools_and_handlers
+            .write()
+            .unwrap()
+            .remove(name)
+            .is_some();
+        if removed {
+            self.broadcast_tools_list_changed().await;
+        }
+        removed
+    }
+
+    /// Stores a tool added at runtime and tells connected clients.
+    async fn replace_tool(&self, tool: Tool, handler: Arc<ToolHandler>) {
+        self.tools_and_handlers
+            .write()
+            .unwrap()
+            .insert(tool.name.clone(), RegisteredTool::new(tool, handler));
+        self.broadcast_tools_list_changed().await;
+    }
+
+    /// Sends `notifications/tools/list_changed` to every live session, forgetting
+    /// the ones that have ended. A session whose queue stays full for longer than
+    /// [`Self::notification_send_timeout`] misses it.
+    async fn broadcast_tools_list_changed(&self) {
+        let notification = Notification {
+            jsonrpc: "2.0".to_string(),
+            method: "notifications/tools/list_changed".to_string(),
+            params: Some(ListToolsChangedParams {}),
+        };
+        let json = match serde_json::to_string(&notification) {
+            Ok(json) => json,
+            Err(e) => {
+                error!("[Server] Failed to serialize tools/list_changed: {}", e);
+                return;
+            }
+        };
+        let notifiers: Vec<mpsc::Sender<String>> = {
+            let mut sessions = self.sessions.lock().unwrap();
+            sessions.retain(|session| !session.notifier.is_closed());
+            sessions
+                .iter()
+                .map(|session| session.notifier.clone())
+                .collect()
+        };
+        let send_timeout = self.notification_send_timeout;
+        futures::future::join_all(notifiers.iter().map(|notifier| {
+            let json = json.clone();
+            async move {
+                let sent = match send_timeout {
+                    Some(limit) => tokio::time::timeout(limit, notifier.send(json))
+                        .await
+                        .map_err(|_| Error::Timeout)
+                        .and_then(|sent| sent.map_err(Error::from)),
+                    None => notifier.send(json).await.map_err(Error::from),
+                };
+                if let Err(e) = sent {
+                    error!("[Server] Could not send tools/list_changed: {}", e);
+                }
+            }
+        }))
+        .await;
+    }
+
+    /// The clients with an open session on this server or any of its clones, in the
+    /// order they connected.
+    pub fn connected_clients(&self) -> Vec<ConnectionInfo> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|session| !session.notifier.is_closed());
+        sessions
+            .iter()
+            .map(|session| session.info.clone())
+            .collect()
+    }
+
     /// Registers a tool with a handler that accepts strongly-typed arguments.
     ///
     /// This method is preferred for new tool implementations as it provides better
@@ -227,8 +686,9 @@ impl Server {
     /// ) -> SdkResult<CallToolResult> {
     ///     let repeated_message = args.message.repeat(args.repeat.unwrap_or(1) as usize);
     ///     Ok(CallToolResult {
-    ///         content: vec![Content::Text { text: repeated_message }],
+    ///         content: vec![Content::Text { text: repeated_message, annotations: None }],
     ///         is_error: false,
+    ///         ..Default::default()
     ///     })
     /// }
     ///
@@ -239,50 +699,80 @@ impl Server {
     ///     );
     /// // Server is now ready to listen for connections and handle "echo" tool calls.
     /// ```
-    pub fn register_tool_typed<Args, Fut, F>(mut self, tool: Tool, handler: F) -> Self
+    pub fn register_tool_typed<Args, Fut, F>(self, tool: Tool, handler: F) -> Self
     where
         Args: DeserializeOwned + Send + Sync + 'static,
         Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
         F: Fn(ConnectionHandle, Args) -> Fut + Send + Sync + 'static,
     {
-        let user_handler_arc = Arc::new(handler);
-        let tool_name_clone_for_error = tool.name.clone(); // For error messages
-        let tool_input_schema_clone_for_error = tool.input_schema.clone(); // For error messages
-
-        let wrapped_handler = Arc::new(ToolHandler::Typed(Box::new(
-            move |conn_handle: ConnectionHandle, json_args: Arc<Value>| {
-                let user_handler = Arc::clone(&user_handler_arc);
-                let tool_name = tool_name_clone_for_error.clone();
-                let input_schema = tool_input_schema_clone_for_error.clone();
-
-                Box::pin(async move {
-                    match serde_json::from_value::<Args>((*json_args).clone()) {
-                        Ok(typed_args) => (user_handler)(conn_handle, typed_args).await,
-                        Err(e) => {
-                            error!(tool_name = %tool_name, error = %e, "Failed to deserialize arguments for tool");
-                            Ok(CallToolResult {
-                                content: vec![Content::Text {
-                                    text: format!(
-                                        "Invalid arguments for tool '{}': {}. Expected schema: {}",
-                                        tool_name,
-                                        e,
-                                        serde_json::to_string_pretty(&input_schema)
-                                            .unwrap_or_default()
-                                    ),
-                                }],
-                                is_error: true,
-                            })
-                        }
-                    }
-                })
+        self.register_tool_typed_with(
+            tool,
+            |json_args| Ok(serde_json::from_value::<Args>(json_args)?),
+            handler,
+        )
+    }
+
+    /// Like [`Self::register_tool_typed`], but also accepts arguments sent as a JSON
+    /// array, as some clients do. Array elements are mapped onto `Args`' properties
+    /// in declaration order ([`ToolArgumentsDescriptor::mcp_arg_order`]) before
+    /// deserializing; object arguments are deserialized as usual.
+    ///
+    /// An array with more elements than `Args` has properties is rejected like any
+    /// other invalid arguments; missing trailing elements are simply absent.
+    pub fn register_tool_typed_positional<Args, Fut, F>(self, tool: Tool, handler: F) -> Self
+    where
+        Args: ToolArgumentsDescriptor + DeserializeOwned + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
+        F: Fn(ConnectionHandle, Args) -> Fut + Send + Sync + 'static,
+    {
+        self.register_tool_typed_with(
+            tool,
+            |json_args| {
+                let json_args = positional_to_named(Args::mcp_arg_order(), json_args)?;
+                Ok(serde_json::from_value::<Args>(json_args)?)
             },
-        )));
+            handler,
+        )
+    }
 
-        self.tools_and_handlers
-            .insert(tool.name.clone(), (tool, wrapped_handler));
+    /// Like [`Self::register_tool_typed`], but turns the raw JSON arguments into
+    /// `Args` with `deserializer` instead of plain `serde_json::from_value`.
+    ///
+    /// This allows custom coercion rules, such as accepting `"42"` where a number
+    /// is expected. An `Err` from `deserializer` is reported to the client as an
+    /// error `CallToolResult`, exactly like a deserialization failure in
+    /// `register_tool_typed`, and the handler is not called.
+    pub fn register_tool_typed_with<Args, D, Fut, F>(
+        mut self,
+        tool: Tool,
+        deserializer: D,
+        handler: F,
+    ) -> Self
+    where
+        Args: Send + Sync + 'static,
+        D: Fn(Value) -> Result<Args> + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResult>> + Send + 'static,
+        F: Fn(ConnectionHandle, Args) -> Fut + Send + Sync + 'static,
+    {
+        let handler = typed_handler(&tool, deserializer, handler);
+        self.insert_tool(tool.name.clone(), tool, handler);
         self
     }
 
+    /// Stores a tool, remembering any name that was registered more than once so
+    /// that [`Self::validate`] can report it.
+    fn insert_tool(&mut self, name: String, tool: Tool, handler: Arc<ToolHandler>) {
+        if self
+            .tools_and_handlers
+            .write()
+            .unwrap()
+            .insert(name.clone(), RegisteredTool::new(tool, handler))
+            .is_some()
+        {
+            self.duplicate_tool_names.push(name);
+        }
+    }
+
     /// Registers a handler for the `resources/list` request.
     pub fn on_list_resources<F, Fut>(mut self, handler: F) -> Self
     where
@@ -293,6 +783,35 @@ impl Server {
         self
     }
 
+    /// Registers a static resource together with its contents.
+    ///
+    /// Static resources are listed ahead of anything returned by the
+    /// [`Self::on_list_resources`] handler, and `resources/read` requests for their
+    /// URI are answered directly; other URIs still go to [`Self::on_read_resource`].
+    /// Registering a resource with an already registered URI replaces it.
+    pub fn register_resource(mut self, resource: Resource, content: ResourceContents) -> Self {
+        match self
+            .static_resources
+            .iter_mut()
+            .find(|(existing, _)| existing.uri == resource.uri)
+        {
+            Some(entry) => *entry = (resource, content),
+            None => self.static_resources.push((resource, content)),
+        }
+        self
+    }
+
+    /// Registers a handler for the `resources/templates/list` request.
+    pub fn on_list_resource_templates<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(ConnectionHandle) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<ResourceTemplate>>> + Send + 'static,
+    {
+        self.list_resource_templates_handler =
+            Some(Arc::new(move |handle| Box::pin(handler(handle))));
+        self
+    }
+
     /// Registers a handler for the `resources/read` request.
     pub fn on_read_resource<F, Fut>(mut self, handler: F) -> Self
     where
@@ -325,14 +844,318 @@ impl Server {
         }));
         self
     }
+
+    /// Registers a handler for the `prompts/get` request that receives the prompt's
+    /// arguments already deserialized into `Args`.
+    ///
+    /// Missing arguments are treated as an empty object, so an `Args` whose fields are
+    /// all optional still works. Arguments that don't fit `Args` are answered with an
+    /// `INVALID_PARAMS` error and the handler is not called.
+    pub fn on_get_prompt_typed<Args, F, Fut>(self, handler: F) -> Self
+    where
+        Args: DeserializeOwned + Send + 'static,
+        F: Fn(ConnectionHandle, String, Args) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<GetPromptResult>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.on_get_prompt(move |handle, name, arguments| {
+            let handler = Arc::clone(&handler);
+            async move {
+                let arguments = arguments.unwrap_or_else(|| Value::Object(Default::default()));
+                let args = serde_json::from_value::<Args>(arguments).map_err(|e| {
+                    crate::Error::JsonRpc(ErrorData {
+                        code: INVALID_PARAMS,
+                        message: format!("Invalid arguments for prompt '{}': {}", name, e),
+                        data: None,
+                    })
+                })?;
+                handler(handle, name, args).await
+            }
+        })
+    }
+
+    /// Registers a completion handler for the arguments of the prompt named `prompt_name`.
+    ///
+    /// When a client sends `completion/complete` with a `ref/prompt` reference to this
+    /// prompt, the handler is called with the argument name and the partial value typed
+    /// so far, and returns candidate values. Only the first 100 candidates are sent,
+    /// with `hasMore` set when the list was truncated. Prompts without a registered
+    /// handler complete to an empty list.
+    pub fn on_prompt_argument_complete<F, Fut>(mut self, prompt_name: &str, handler: F) -> Self
+    where
+        F: Fn(String, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<String>>> + Send + 'static,
+    {
+        self.prompt_completion_handlers.insert(
+            prompt_name.to_string(),
+            Arc::new(move |argument, value| Box::pin(handler(argument, value))),
+        );
+        self
+    }
+
+    /// Registers a fallback handler for requests whose method the server does not know.
+    ///
+    /// Without a fallback, such requests are answered with `METHOD_NOT_FOUND`. With one,
+    /// the handler receives the method name and the raw params, and the value it returns
+    /// is sent back as the response `result`. This enables transparent proxying of
+    /// arbitrary methods. An error from the handler becomes an `INTERNAL_ERROR` response.
+    pub fn on_unknown_method<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(ConnectionHandle, String, Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        self.unknown_method_handler = Some(Arc::new(move |handle, method, params| {
+            Box::pin(handler(handle, method, params))
+        }));
+        self
+    }
+
+    /// Registers a handler for client notifications with the given `method`, e.g.
+    /// `notifications/roots/list_changed`.
+    ///
+    /// The handler receives the notification's `params`, or `Value::Null` when they
+    /// are missing or `null`. Notifications never get a response, so an error from
+    /// the handler is only logged.
+    pub fn on_notification<F, Fut>(mut self, method: &str, handler: F) -> Self
+    where
+        F: Fn(ConnectionHandle, Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.notification_handlers.insert(
+            method.to_string(),
+            Arc::new(move |handle, params| Box::pin(handler(handle, params))),
+        );
+        self
+    }
+
+    /// Registers a guard that authorizes every `tools/call` before its handler runs.
+    ///
+    /// The guard receives the tool name and the raw arguments. Returning an error
+    /// denies the call: the handler is never invoked and the client receives a
+    /// JSON-RPC error carrying the guard's message. The error code defaults to
+    /// [`TOOL_CALL_DENIED`](crate::types::TOOL_CALL_DENIED) and can be changed with
+    /// [`Self::tool_guard_error_code`]. The guard applies to both typed and untyped tools.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mcp_sdk::server::Server;
+    ///
+    /// let server = Server::new("guarded").with_tool_guard(|tool, _args| {
+    ///     if tool.starts_with("admin_") {
+    ///         return Err("admin tools are disabled".into());
+    ///     }
+    ///     Ok(())
+    /// });
+    /// ```
+    pub fn with_tool_guard<F>(mut self, guard: F) -> Self
+    where
+        F: Fn(&str, &Value) -> Result<()> + Send + Sync + 'static,
+    {
+        self.tool_guard = Some(Arc::new(guard));
+        self
+    }
+
+    /// Sets the JSON-RPC error code sent when the tool guard denies a call.
+    pub fn tool_guard_error_code(mut self, code: i32) -> Self {
+        self.tool_guard_error_code = Some(code);
+        self
+    }
+
+    /// Sets the protocol versions this server accepts, most preferred first.
+    ///
+    /// During `initialize`, a client requesting one of these versions gets the same
+    /// version back. Otherwise, as the spec requires, the server answers with its
+    /// preferred version and leaves it to the client to disconnect if it can't use
+    /// it. Defaults to just [`LATEST_PROTOCOL_VERSION`].
+    pub fn supported_protocol_versions<I, S>(mut self, versions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.supported_protocol_versions = versions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Picks the protocol version to answer an `initialize` request with.
+    pub(crate) fn negotiate_protocol_version(&self, requested: &str) -> String {
+        if self.supported_protocol_versions.is_empty() {
+            return LATEST_PROTOCOL_VERSION.to_string();
+        }
+        self.supported_protocol_versions
+            .iter()
+            .find(|version| *version == requested)
+            .unwrap_or(&self.supported_protocol_versions[0])
+            .clone()
+    }
+
+    /// Registers a callback that receives an [`AuditEvent`] after every tool call.
+    ///
+    /// Denied, failed, and panicking calls are reported too, with `success` set to
+    /// `false`. By default only a hash of the arguments is recorded; see
+    /// [`Self::audit_full_args`] to include them verbatim.
+    pub fn on_audit<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(AuditEvent) + Send + Sync + 'static,
+    {
+        self.audit_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Reports the volume, errors, and latency of every request to `recorder`.
+    ///
+    /// See [`AtomicMetrics`](super::AtomicMetrics) for a ready-made recorder.
+    pub fn with_metrics(mut self, recorder: impl MetricsRecorder + 'static) -> Self {
+        self.metrics = Some(Arc::new(recorder));
+        self
+    }
+
+    /// Includes the raw tool arguments in audit events. Disabled by default, since
+    /// arguments may carry sensitive data.
+    pub fn audit_full_args(mut self, enabled: bool) -> Self {
+        self.audit_full_args = enabled;
+        self
+    }
+
+    /// Registers a function applied to every tool result after its handler returns
+    /// and before it is sent, e.g. to redact secrets or append a footer.
+    ///
+    /// Results of calls that fail with an error, rather than an error
+    /// `CallToolResult`, are not passed through it.
+    pub fn with_result_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&mut CallToolResult) + Send + Sync + 'static,
+    {
+        self.result_transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Appends every tool result, together with the name and arguments of the call
+    /// that produced it, to the NDJSON file at `path`.
+    ///
+    /// Unlike [`Self::on_audit`], each line is a complete record that can be replayed
+    /// or analysed later, so arguments are always written in full. The file is
+    /// created if needed and never truncated. Failing to write it is logged but does
+    /// not fail the call.
+    pub fn with_result_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.result_log = Some(Arc::new(ResultLog::new(path.into())));
+        self
+    }
+
+    /// Checks the server configuration for mistakes that would otherwise only show up
+    /// once clients start calling it.
+    ///
+    /// All problems are collected rather than stopping at the first one. Calling this
+    /// is optional, but doing so before [`Self::tcp_listen`] turns these surprises
+    /// into startup errors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mcp_sdk::server::{ConfigError, Server};
+    ///
+    /// let errors = Server::new("empty").validate().unwrap_err();
+    /// assert_eq!(errors, vec![ConfigError::NoHandlers]);
+    /// ```
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        let tools_and_handlers = self.tools_and_handlers.read().unwrap();
+        if tools_and_handlers.is_empty()
+            && self.list_resources_handler.is_none()
+            && self.static_resources.is_empty()
+            && self.list_resource_templates_handler.is_none()
+            && self.read_resource_handler.is_none()
+            && self.list_prompts_handler.is_none()
+            && self.get_prompt_handler.is_none()
+        {
+            errors.push(ConfigError::NoHandlers);
+        }
+
+        let mut duplicates = self.duplicate_tool_names.clone();
+        duplicates.sort();
+        duplicates.dedup();
+        errors.extend(duplicates.into_iter().map(ConfigError::DuplicateTool));
+
+        let mut tools: Vec<&Tool> = tools_and_handlers.values().map(|t| &t.tool).collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        for tool in tools {
+            if tool.name.is_empty() {
+                errors.push(ConfigError::EmptyToolName);
+            }
+            if !tool.input_schema.is_object() {
+                errors.push(ConfigError::InvalidInputSchema(tool.name.clone()));
+            }
+            if let Some(annotations) = &tool.annotations {
+                if annotations.read_only_hint == Some(true)
+                    && annotations.destructive_hint == Some(true)
+                {
+                    errors.push(ConfigError::ContradictoryAnnotations(tool.name.clone()));
+                }
+            }
+        }
+
+        if self.read_resource_handler.is_some()
+            && self.list_resources_handler.is_none()
+            && self.static_resources.is_empty()
+        {
+            errors.push(ConfigError::MissingHandler {
+                registered: "resources/read",
+                missing: "resources/list",
+            });
+        }
+        if self.get_prompt_handler.is_some() && self.list_prompts_handler.is_none() {
+            errors.push(ConfigError::MissingHandler {
+                registered: "prompts/get",
+                missing: "prompts/list",
+            });
+        }
+        if !self.prompt_completion_handlers.is_empty() && self.get_prompt_handler.is_none() {
+            errors.push(ConfigError::MissingHandler {
+                registered: "completion/complete",
+                missing: "prompts/get",
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     /// Takes a single, pre-existing network adapter and runs a session for it.
     /// This is the core logic block used by both `serve` and `tcp_listen`.
     pub async fn handle_connection<A>(&self, adapter: A) -> Result<()>
+    where
+        A: NetworkAdapter + 'static,
+    {
+        self.run_session(adapter, None).await
+    }
+
+    /// Runs a single session over an already-accepted TCP connection.
+    ///
+    /// This is what [`Self::tcp_listen`] does for every connection it accepts, for
+    /// applications that run their own accept loop, e.g. on a listener shared with
+    /// an HTTP server. The session is tagged with the stream's peer address.
+    pub async fn serve_stream<A>(&self, stream: TcpStream) -> Result<()>
+    where
+        A: NetworkAdapter + From<TcpStream> + 'static,
+    {
+        let peer = stream.peer_addr().ok().map(|addr| addr.to_string());
+        self.run_session(A::from(stream), peer).await
+    }
+
+    /// Runs a session for `adapter`, tagging it with the peer's address when known.
+    async fn run_session<A>(&self, adapter: A, peer: Option<String>) -> Result<()>
     where
         A: NetworkAdapter + 'static,
     {
         let conn = ProtocolConnection::new(adapter);
-        let session = ServerSession::new(conn, Arc::new(self.clone()));
+        let mut session = ServerSession::new(conn, Arc::new(self.clone()));
+        if let Some(peer) = peer {
+            session = session.with_peer(peer);
+        }
         session.run().await
     }
 
@@ -356,21 +1179,64 @@ impl Server {
     /// listener to the specified address. This can happen if the port is already
     /// in use or if the application lacks the necessary permissions to bind to
     pub async fn tcp_listen<A>(self, addr: &str) -> Result<()>
+    where
+        A: NetworkAdapter + From<TcpStream> + 'static,
+    {
+        self.tcp_listen_with_shutdown::<A>(addr, std::future::pending())
+            .await
+    }
+
+    /// Like [`Self::tcp_listen`], but stops accepting connections once `shutdown`
+    /// completes.
+    ///
+    /// Connections that are already open stay up: a request that is being handled
+    /// when shutdown begins runs to completion, while any request received after
+    /// that is answered with a `SERVER_SHUTTING_DOWN` error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the listener cannot be bound to `addr` or accepting a
+    /// connection fails.
+    pub async fn tcp_listen_with_shutdown<A>(
+        self,
+        addr: &str,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<()>
     where
         A: NetworkAdapter + From<TcpStream> + 'static,
     {
         let listener = TcpListener::bind(addr).await?;
         info!("[Server] Listening on {}", addr);
         let server = Arc::new(self);
+        let active = Arc::new(AtomicUsize::new(0));
+        tokio::pin!(shutdown);
 
         loop {
-            let (stream, client_addr) = listener.accept().await?;
+            let (stream, client_addr) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = &mut shutdown => {
+                    info!("[Server] Shutting down; no longer accepting connections on {}", addr);
+                    server.shutting_down.store(true, Ordering::SeqCst);
+                    return Ok(());
+                }
+            };
+            if let Some(max) = server.max_connections {
+                if active.load(Ordering::SeqCst) >= max {
+                    info!(
+                        "[Server] Refusing {}: connection limit of {} reached",
+                        client_addr, max
+                    );
+                    tokio::spawn(refuse_connection(A::from(stream), max));
+                    continue;
+                }
+            }
             info!("[Server] Accepted connection from: {}", client_addr);
             let server_clone = Arc::clone(&server);
+            let slot = ConnectionSlot::acquire(&active);
 
             tokio::spawn(async move {
-                let adapter = A::from(stream);
-                if let Err(e) = server_clone.handle_connection(adapter).await {
+                let _slot = slot;
+                if let Err(e) = server_clone.serve_stream::<A>(stream).await {
                     error!("[Server] Session failed for {}: {}", client_addr, e);
                 }
             });
@@ -378,18 +1244,81 @@ impl Server {
     }
 }
 
+/// Counts one active connection for as long as it is alive.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn acquire(active: &Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::SeqCst);
+        ConnectionSlot(Arc::clone(active))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Tells a client it was turned away before dropping its connection. There is no
+/// request to answer yet, so the error carries a null id.
+async fn refuse_connection<A: NetworkAdapter>(mut adapter: A, max: usize) {
+    let refusal = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": null,
+        "error": {
+            "code": CONNECTION_LIMIT_REACHED,
+            "message": format!("Server is at its limit of {} connections", max),
+        },
+    });
+    if let Err(e) = adapter.send(&refusal.to_string()).await {
+        error!("[Server] Failed to notify refused connection: {}", e);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::types::{CallToolResult, ListPromptsResult};
     use serde_json::json;
 
+    #[tokio::test]
+    async fn test_tools_list_changed_waits_for_room_in_a_full_queue() {
+        let server = Server::new("test");
+        let (notifier, mut queue) = mpsc::channel(1);
+        notifier.send("earlier".to_string()).await.unwrap();
+        server.sessions.lock().unwrap().push(SessionEntry {
+            notifier,
+            info: ConnectionInfo {
+                peer_addr: None,
+                connected_at: SystemTime::now(),
+            },
+        });
+
+        let adding = tokio::spawn(async move {
+            server
+                .add_tool(
+                    Tool {
+                        name: "late".to_string(),
+                        ..Default::default()
+                    },
+                    |_handle, _args| async { Ok(CallToolResult::default()) },
+                )
+                .await;
+        });
+        assert_eq!(queue.recv().await.unwrap(), "earlier");
+        adding.await.unwrap();
+        let notification: Value = serde_json::from_str(&queue.recv().await.unwrap()).unwrap();
+        assert_eq!(notification["method"], "notifications/tools/list_changed");
+    }
+
     #[tokio::test]
     async fn test_handler_registration() {
         // 1. Setup
         // Create a dummy tool definition
         let dummy_tool = Tool {
             name: "my-test-tool".to_string(),
+            title: None,
             description: Some("A tool for testing.".to_string()),
             input_schema: json!({ "type": "object" }),
             annotations: None,
@@ -402,13 +1331,82 @@ mod tests {
         // Create a server and register the tool
         let server = Server::new("test-server")
             .register_tool(dummy_tool.clone(), dummy_handler)
-            .on_list_prompts(|_| async { Ok(ListPromptsResult { prompts: vec![] }) });
+            .on_list_prompts(|_| async {
+                Ok(ListPromptsResult {
+                    prompts: vec![],
+                    meta: None,
+                })
+            });
 
-        assert_eq!(server.tools_and_handlers.len(), 1);
-        assert!(server.tools_and_handlers.contains_key("my-test-tool"));
-        let (registered_tool, _handler) = server.tools_and_handlers.get("my-test-tool").unwrap();
+        let tools_and_handlers = server.tools_and_handlers.read().unwrap();
+        assert_eq!(tools_and_handlers.len(), 1);
+        assert!(tools_and_handlers.contains_key("my-test-tool"));
+        let registered_tool = &tools_and_handlers.get("my-test-tool").unwrap().tool;
         assert_eq!(registered_tool.name, dummy_tool.name);
 
         assert!(server.list_prompts_handler.is_some());
     }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let handler =
+            |_handle: ConnectionHandle, _args: Value| async { Ok(CallToolResult::default()) };
+        let server = Server::new("misconfigured")
+            .register_tool(
+                Tool {
+                    name: "dup".to_string(),
+                    input_schema: json!({ "type": "object" }),
+                    ..Default::default()
+                },
+                handler,
+            )
+            .register_tool(
+                Tool {
+                    name: "dup".to_string(),
+                    input_schema: json!({ "type": "object" }),
+                    ..Default::default()
+                },
+                handler,
+            )
+            .register_tool(
+                Tool {
+                    name: "wipe".to_string(),
+                    input_schema: json!({ "type": "object" }),
+                    annotations: Some(crate::types::ToolAnnotations {
+                        title: None,
+                        read_only_hint: Some(true),
+                        destructive_hint: Some(true),
+                    }),
+                    ..Default::default()
+                },
+                handler,
+            )
+            .on_get_prompt(|_, _, _| async { Err("unused".into()) });
+
+        let errors = server.validate().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ConfigError::DuplicateTool("dup".to_string()),
+                ConfigError::ContradictoryAnnotations("wipe".to_string()),
+                ConfigError::MissingHandler {
+                    registered: "prompts/get",
+                    missing: "prompts/list",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_accepts_well_formed_server() {
+        let server = Server::new("ok").register_tool(
+            Tool {
+                name: "echo".to_string(),
+                input_schema: json!({ "type": "object" }),
+                ..Default::default()
+            },
+            |_handle, _args| async { Ok(CallToolResult::default()) },
+        );
+        assert!(server.validate().is_ok());
+    }
 }
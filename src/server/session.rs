@@ -1,38 +1,371 @@
 //! Defines the ServerSession, which manages the state and logic for a single client connection.
 
-use super::server::Server;
+use super::metrics::{NoopMetrics, SessionStats};
+use super::server::{AuditEvent, ConnectionInfo, Server, SessionEntry};
 use crate::error::{Error, Result};
 use crate::network_adapter::NetworkAdapter;
 use crate::protocol::ProtocolConnection;
 use crate::types::{
-    CallToolParams, ErrorData, ErrorResponse, GetPromptParams, Implementation,
-    InitializeRequestParams, InitializeResult, ListPromptsParams, ListResourcesParams,
-    ListToolsResult, Notification, ReadResourceParams, Request, RequestId, Response,
-    ServerCapabilities, Tool, ToolsCapability, LATEST_PROTOCOL_VERSION, METHOD_NOT_FOUND,
+    CallToolParams, ClientCapabilities, CloseParams, CompleteParams, CompleteResult, Completion,
+    CompletionReference, Content, ElicitParams, ElicitResult, ErrorData, ErrorResponse,
+    GetPromptParams, Implementation, InitializeRequestParams, InitializeResult,
+    ListPromptsChangedParams, ListPromptsParams, ListResourceTemplatesParams,
+    ListResourceTemplatesResult, ListResourcesChangedParams, ListResourcesParams, ListRootsResult,
+    ListToolsChangedParams, ListToolsResult, Notification, PartialResultParams,
+    ProgressNotificationParams, PromptsCapability, ReadResourceParams, ReadResourceResult, Request,
+    RequestId, Resource, ResourcesCapability, Response, Root, ServerCapabilities, Tool,
+    ToolsCapability, INTERNAL_ERROR, INVALID_PARAMS, METHOD_NOT_FOUND, REQUEST_TIMED_OUT,
+    SERVER_NOT_INITIALIZED, SERVER_SHUTTING_DOWN, TOOL_CALL_DENIED,
 };
-use serde::Serialize;
+use dashmap::DashMap;
+use futures::FutureExt;
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
 use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tracing::{error, info};
+#[cfg(feature = "schema-validation")]
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tracing::{error, info, info_span, warn, Instrument};
 
-/// A handle given to user-code to allow sending notifications back to the client.
+/// The spec caps the number of values in a single completion response.
+const MAX_COMPLETION_VALUES: usize = 100;
+
+/// Capacity of a session's outgoing queue unless [`Server::notification_buffer`] is set.
+const DEFAULT_NOTIFICATION_BUFFER: usize = 32;
+
+/// Validates a tool's arguments against its input schema, which is compiled the
+/// first time it is needed and reused after that.
+#[cfg(feature = "schema-validation")]
+pub(crate) struct ToolInputValidator {
+    schema: Value,
+    compiled: OnceLock<std::result::Result<jsonschema::Validator, String>>,
+}
+
+#[cfg(feature = "schema-validation")]
+impl ToolInputValidator {
+    pub(crate) fn new(schema: Value) -> Self {
+        Self {
+            schema,
+            compiled: OnceLock::new(),
+        }
+    }
+
+    /// A `null` schema accepts anything; a schema that doesn't compile is the
+    /// server's fault, not the caller's.
+    fn check(&self, tool_name: &str, arguments: &Value) -> Result<()> {
+        if self.schema.is_null() {
+            return Ok(());
+        }
+        let validator = self
+            .compiled
+            .get_or_init(|| {
+                crate::protocol::validator::compile(&self.schema).map_err(|e| e.to_string())
+            })
+            .as_ref()
+            .map_err(|e| Error::Other(format!("Tool '{}': {}", tool_name, e)))?;
+        validator.validate(arguments).map_err(|e| {
+            Error::InvalidParams(format!("Invalid arguments for tool '{}': {}", tool_name, e))
+        })
+    }
+}
+
+// Requests the server sent to the client, by id, awaiting the client's response.
+type PendingRequests = Arc<DashMap<RequestId, oneshot::Sender<Result<Value>>>>;
+
+/// A handle given to user-code to allow sending notifications and requests back
+/// to the client.
 #[derive(Clone)]
 pub struct ConnectionHandle {
     pub(crate) notification_sender: mpsc::Sender<String>,
+    pending_requests: PendingRequests,
+    next_request_id: Arc<AtomicI64>,
+    /// Produced by the server's connection state initializer when the session started.
+    state: Option<Arc<dyn Any + Send + Sync>>,
+    /// What the client advertised in its `initialize` request.
+    client_capabilities: ClientCapabilities,
+    /// How long `send_notification` waits for room in a full queue, if bounded.
+    send_timeout: Option<Duration>,
+    /// The id of the request whose handler this handle was given to.
+    request_id: Option<RequestId>,
+    /// The `_meta` object sent with that request's params.
+    meta: Option<Value>,
+    /// Signalled by [`Self::close_with_reason`] to end the session.
+    close: Arc<Notify>,
 }
 
 impl ConnectionHandle {
+    pub(crate) fn new(notification_sender: mpsc::Sender<String>) -> Self {
+        Self {
+            notification_sender,
+            pending_requests: Arc::new(DashMap::new()),
+            next_request_id: Arc::new(AtomicI64::new(0)),
+            state: None,
+            client_capabilities: ClientCapabilities::default(),
+            send_timeout: None,
+            request_id: None,
+            meta: None,
+            close: Arc::new(Notify::new()),
+        }
+    }
+
+    /// The id of the request being handled, e.g. to pass to
+    /// [`Self::stream_content`]. `None` in notification handlers.
+    pub fn request_id(&self) -> Option<RequestId> {
+        self.request_id.clone()
+    }
+
+    /// The `_meta` object the client sent with the request being handled, if
+    /// any. `None` in notification handlers.
+    pub fn meta(&self) -> Option<&Value> {
+        self.meta.as_ref()
+    }
+
+    /// The `progressToken` from the request's `_meta`, for use with
+    /// [`Self::send_progress`].
+    pub fn progress_token(&self) -> Option<RequestId> {
+        let token = self.meta.as_ref()?.get("progressToken")?;
+        serde_json::from_value(token.clone()).ok()
+    }
+
+    /// The capabilities the client advertised during the handshake, e.g. to check
+    /// for `roots` support before calling [`Self::list_roots`].
+    pub fn client_capabilities(&self) -> ClientCapabilities {
+        self.client_capabilities.clone()
+    }
+
+    /// The state created for this connection by the initializer set with
+    /// [`Server::with_connection_state_init`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server has no initializer or it produces a type other than
+    /// `T`. Use [`Self::try_state`] to check instead.
+    pub fn state<T: Send + Sync + 'static>(&self) -> Arc<T> {
+        self.try_state().unwrap_or_else(|| {
+            panic!(
+                "no connection state of type {}; is Server::with_connection_state_init set?",
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    /// Like [`Self::state`], but returns `None` instead of panicking.
+    pub fn try_state<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        Arc::clone(self.state.as_ref()?).downcast::<T>().ok()
+    }
+
+    /// Asks the client for the filesystem roots it exposes.
+    ///
+    /// Fails with the client's JSON-RPC error if it doesn't support roots, or with
+    /// [`Error::ChannelClosed`] if the connection ends before it answers.
+    pub async fn list_roots(&self) -> Result<Vec<Root>> {
+        let result: ListRootsResult = self.send_request("roots/list", None::<Value>).await?;
+        Ok(result.roots)
+    }
+
+    /// Asks the client to collect structured input from the user, e.g. in the middle
+    /// of a tool call, and waits for the answer.
+    ///
+    /// The user may decline or cancel instead of accepting; check
+    /// [`ElicitResult::action`] before using `content`.
+    pub async fn elicit(&self, params: ElicitParams) -> Result<ElicitResult> {
+        self.send_request("elicitation/create", Some(params)).await
+    }
+
+    /// Sends a request to the client and waits for its response.
+    pub(crate) async fn send_request<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Option<P>,
+    ) -> Result<R> {
+        let id = RequestId::Num(self.next_request_id.fetch_add(1, Ordering::Relaxed));
+        let request = serde_json::to_string(&Request {
+            jsonrpc: "2.0".to_string(),
+            id: id.clone(),
+            method: method.to_string(),
+            params,
+        })?;
+        let (tx, rx) = oneshot::channel();
+        self.pending_requests.insert(id.clone(), tx);
+        if let Err(e) = self.notification_sender.send(request).await {
+            self.pending_requests.remove(&id);
+            return Err(e.into());
+        }
+        let result = rx.await??;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Completes the pending request `raw_response` answers. Returns `false` if no
+    /// request is waiting for it.
+    fn resolve(&self, raw_response: Value) -> bool {
+        let Some(id) = raw_response
+            .get("id")
+            .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok())
+        else {
+            return false;
+        };
+        let Some((_, sender)) = self.pending_requests.remove(&id) else {
+            return false;
+        };
+        let result = match raw_response {
+            Value::Object(mut fields) => match fields.remove("error") {
+                Some(error) => serde_json::from_value::<ErrorData>(error)
+                    .map_err(Error::from)
+                    .and_then(|data| Err(Error::JsonRpc(data))),
+                None => Ok(fields.remove("result").unwrap_or(Value::Null)),
+            },
+            _ => Ok(Value::Null),
+        };
+        // The requester may have given up, e.g. after a timeout.
+        let _ = sender.send(result);
+        true
+    }
+
     /// Sends a notification to the client associated with this connection.
+    ///
+    /// Waits while the session's queue is full; with
+    /// [`Server::notification_send_timeout`] set, gives up with [`Error::Timeout`]
+    /// once that elapses.
     pub async fn send_notification<T: Serialize>(
         &self,
         notification: Notification<T>,
     ) -> Result<()> {
         let json_string = serde_json::to_string(&notification)?;
-        self.notification_sender.send(json_string).await?;
+        match self.send_timeout {
+            Some(limit) => tokio::time::timeout(limit, self.notification_sender.send(json_string))
+                .await
+                .map_err(|_| Error::Timeout)??,
+            None => self.notification_sender.send(json_string).await?,
+        }
+        Ok(())
+    }
+
+    /// Tells the client that the list of available tools has changed.
+    pub async fn notify_tools_list_changed(&self) -> Result<()> {
+        self.send_list_changed(
+            "notifications/tools/list_changed",
+            ListToolsChangedParams {},
+        )
+        .await
+    }
+
+    /// Tells the client that the list of available resources has changed.
+    pub async fn notify_resources_list_changed(&self) -> Result<()> {
+        self.send_list_changed(
+            "notifications/resources/list_changed",
+            ListResourcesChangedParams {},
+        )
+        .await
+    }
+
+    /// Tells the client that the list of available prompts has changed.
+    pub async fn notify_prompts_list_changed(&self) -> Result<()> {
+        self.send_list_changed(
+            "notifications/prompts/list_changed",
+            ListPromptsChangedParams {},
+        )
+        .await
+    }
+
+    /// Reports progress on a long-running request. `token` is the `progressToken`
+    /// the client sent in the request's `_meta`; `total` is `None` when unknown.
+    pub async fn send_progress(
+        &self,
+        token: RequestId,
+        progress: f64,
+        total: Option<f64>,
+    ) -> Result<()> {
+        self.send_progress_params(ProgressNotificationParams {
+            progress_token: token,
+            progress,
+            total,
+            message: None,
+        })
+        .await
+    }
+
+    /// Reports progress as a percentage between 0 and 100. Values outside that
+    /// range are clamped.
+    pub async fn send_progress_pct(&self, token: RequestId, percent: f64) -> Result<()> {
+        self.send_progress(token, percent.clamp(0.0, 100.0), Some(100.0))
+            .await
+    }
+
+    /// Reports that work is ongoing without knowing how much is left, so the
+    /// client can show a spinner along with `message`.
+    pub async fn send_progress_indeterminate(
+        &self,
+        token: RequestId,
+        message: impl Into<String>,
+    ) -> Result<()> {
+        self.send_progress_params(ProgressNotificationParams {
+            progress_token: token,
+            progress: 0.0,
+            total: None,
+            message: Some(message.into()),
+        })
+        .await
+    }
+
+    /// Streams one chunk of a tool's output to the client as a
+    /// `notifications/tools/partial_result` notification. Chunks are delivered in
+    /// order, before the response to the request identified by `request_id`.
+    pub async fn stream_content(&self, request_id: RequestId, chunk: Content) -> Result<()> {
+        self.send_notification(Notification {
+            jsonrpc: "2.0".to_string(),
+            method: "notifications/tools/partial_result".to_string(),
+            params: Some(PartialResultParams {
+                request_id,
+                content: chunk,
+            }),
+        })
+        .await
+    }
+
+    /// Closes the connection, first telling the client why with a
+    /// `notifications/close` notification carrying `code` and `reason`.
+    ///
+    /// Messages queued before the call, and the response to the request being
+    /// handled, are still sent; the transport is shut right after.
+    pub async fn close_with_reason(&self, code: i32, reason: impl Into<String>) -> Result<()> {
+        self.send_notification(Notification {
+            jsonrpc: "2.0".to_string(),
+            method: "notifications/close".to_string(),
+            params: Some(CloseParams {
+                code,
+                reason: reason.into(),
+            }),
+        })
+        .await?;
+        self.close.notify_one();
         Ok(())
     }
+
+    async fn send_progress_params(&self, params: ProgressNotificationParams) -> Result<()> {
+        self.send_notification(Notification {
+            jsonrpc: "2.0".to_string(),
+            method: "notifications/progress".to_string(),
+            params: Some(params),
+        })
+        .await
+    }
+
+    async fn send_list_changed<T: Serialize>(&self, method: &str, params: T) -> Result<()> {
+        self.send_notification(Notification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: Some(params),
+        })
+        .await
+    }
 }
 
 /// Represents a single, active client connection and manages its lifecycle.
@@ -40,81 +373,318 @@ pub struct ServerSession<A: NetworkAdapter> {
     // Made public for integration tests
     connection: ProtocolConnection<A>,
     server: Arc<Server>,
-    is_initialized: bool,
+    state: SessionState,
+    peer: Option<String>,
+    // Whether an error response was sent for the request being handled.
+    sent_error: bool,
+    // Cloned into every handler; its sender feeds `outgoing_rx`.
+    handle: ConnectionHandle,
+    outgoing_rx: mpsc::Receiver<String>,
+    // Messages that arrived while a handler was running, handled once it finishes.
+    deferred: VecDeque<Value>,
+    peer_closed: bool,
+    stats: SessionStats,
+}
+
+/// Where a session is in the `initialize` handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SessionState {
+    /// Waiting for the client's `initialize` request.
+    AwaitingInitialize,
+    /// The initialize result was sent; waiting for `notifications/initialized`.
+    Initializing,
+    /// The handshake is complete and regular requests are accepted.
+    Initialized,
 }
 
 impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
     pub fn new(connection: ProtocolConnection<A>, server: Arc<Server>) -> Self {
         // Made public for integration tests
+        let state = if server.skip_initialize {
+            SessionState::Initialized
+        } else {
+            SessionState::AwaitingInitialize
+        };
+        let capacity = server
+            .notification_buffer
+            .unwrap_or(DEFAULT_NOTIFICATION_BUFFER)
+            .max(1);
+        let (outgoing_tx, outgoing_rx) = mpsc::channel::<String>(capacity);
+        let mut handle = ConnectionHandle::new(outgoing_tx);
+        handle.send_timeout = server.notification_send_timeout;
+        handle.state = server.connection_state_init.as_ref().map(|init| init());
         Self {
             connection,
             server,
-            is_initialized: false,
+            state,
+            peer: None,
+            sent_error: false,
+            handle,
+            outgoing_rx,
+            deferred: VecDeque::new(),
+            peer_closed: false,
+            stats: SessionStats::default(),
         }
     }
 
+    /// Records the remote peer's address, reported in audit events.
+    pub fn with_peer(mut self, peer: impl Into<String>) -> Self {
+        self.peer = Some(peer.into());
+        self
+    }
+
+    /// What this session has handled so far.
+    pub fn stats(&self) -> SessionStats {
+        self.stats
+    }
+
     pub async fn run(mut self) -> Result<()> {
         // Made public for integration tests
         info!("[Session] New session task started. Waiting for messages.");
-        let (notification_tx, mut notification_rx) = mpsc::channel::<String>(32);
+        self.server.sessions.lock().unwrap().push(SessionEntry {
+            notifier: self.handle.notification_sender.clone(),
+            info: ConnectionInfo {
+                peer_addr: self.peer.clone(),
+                connected_at: SystemTime::now(),
+            },
+        });
+
+        let result = self.serve().await;
+        let stats = self.stats;
+        info!(
+            requests = stats.requests,
+            errors = stats.errors,
+            notifications_sent = stats.notifications_sent,
+            "[Session] Session ended."
+        );
+        if let Some(metrics) = &self.server.metrics {
+            metrics.on_session_end(&stats);
+        }
+        result
+    }
+
+    /// The session's main loop, returning once the connection is closed.
+    async fn serve(&mut self) -> Result<()> {
+        let idle_timeout = self.server.idle_timeout;
+        let idle_deadline = |timeout: Duration| tokio::time::Instant::now() + timeout;
+        let mut deadline = idle_timeout.map(idle_deadline);
+        let close = Arc::clone(&self.handle.close);
+
+        loop {
+            let raw_req = if let Some(msg) = self.deferred.pop_front() {
+                msg
+            } else if self.peer_closed {
+                info!("[Session] Connection closed. Draining pending notifications.");
+                // DRAIN NOTIFICATIONS BEFORE RETURNING
+                self.outgoing_rx.close(); // Close the sender side of the channel
+                while let Some(notif_json) = self.outgoing_rx.recv().await {
+                    self.send_queued(&notif_json).await?;
+                }
+                return Ok(());
+            } else {
+                tokio::select! {
+                    result = self.connection.recv_message::<Value>() => {
+                        match result {
+                            Ok(Some(msg)) => {
+                                deadline = idle_timeout.map(idle_deadline);
+                                msg
+                            }
+                            Ok(None) => {
+                                self.peer_closed = true;
+                                continue;
+                            }
+                            Err(e) => {
+                                warn!("[Session] Transport error, closing session: {}", e);
+                                self.peer_closed = true;
+                                continue;
+                            }
+                        }
+                    },
+                    Some(notif_json) = self.outgoing_rx.recv() => {
+                        self.send_queued(&notif_json).await?;
+                        continue;
+                    }
+                    _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)),
+                        if deadline.is_some() =>
+                    {
+                        info!("[Session] No message from client within {:?}, closing.", idle_timeout);
+                        self.peer_closed = true;
+                        continue;
+                    }
+                    _ = close.notified() => {
+                        info!("[Session] Closed by the server.");
+                        self.peer_closed = true;
+                        continue;
+                    }
+                }
+            };
+            if is_response(&raw_req) {
+                if !self.handle.resolve(raw_req) {
+                    warn!("[Session] Dropping response to an unknown request");
+                }
+                continue;
+            }
+            let handle = self.handle.clone();
+            if let Err(e) = self.dispatch_request(raw_req, handle).await {
+                error!("[Server] Error dispatching request: {}", e);
+            }
+            // Time spent in a handler doesn't count towards the idle timeout.
+            deadline = idle_timeout.map(idle_deadline);
+        }
+    }
 
+    /// Awaits a handler's future while keeping the connection serviced.
+    ///
+    /// Messages the handler queues are sent as they come, and all of them are sent
+    /// before this returns, so they reach the client ahead of the response.
+    /// Responses to its requests (e.g. [`ConnectionHandle::list_roots`]) are routed
+    /// back to it. Any other incoming message waits in `deferred` until the
+    /// handler is done.
+    async fn serve_while<T>(&mut self, fut: impl Future<Output = T>) -> Result<T> {
+        tokio::pin!(fut);
         loop {
             tokio::select! {
-                result = self.connection.recv_message::<Value>() => {
-                    let raw_req = match result {
-                        Ok(Some(msg)) => msg,
-                        Ok(None) => {
-                            info!("[Session] Connection closed by client. Draining pending notifications.");
-                            // DRAIN NOTIFICATIONS BEFORE RETURNING
-                            notification_rx.close(); // Close the sender side of the channel
-                            while let Some(notif_json) = notification_rx.recv().await {
-                                self.connection.send_raw(&notif_json).await?;
+                output = &mut fut => {
+                    while let Ok(msg) = self.outgoing_rx.try_recv() {
+                        self.send_queued(&msg).await?;
+                    }
+                    return Ok(output);
+                }
+                Some(msg) = self.outgoing_rx.recv() => {
+                    self.send_queued(&msg).await?;
+                }
+                result = self.connection.recv_message::<Value>(), if !self.peer_closed => {
+                    match result {
+                        Ok(Some(msg)) if is_response(&msg) => {
+                            if !self.handle.resolve(msg) {
+                                warn!("[Session] Dropping response to an unknown request");
                             }
-                            return Ok(());
                         }
+                        Ok(Some(msg)) => self.deferred.push_back(msg),
+                        Ok(None) => self.peer_closed = true,
                         Err(e) => {
-                            info!("[Session] something strage here {}",e);
-                            notification_rx.close();
-                            while let Some(notif_json) = notification_rx.recv().await {
-                                self.connection.send_raw(&notif_json).await?;
-                            }
-                            return Ok(());
+                            warn!("[Session] Transport error, closing session: {}", e);
+                            self.peer_closed = true;
                         }
-                    };
-                    let handle = ConnectionHandle { notification_sender: notification_tx.clone() };
-                    if let Err(e) = self.dispatch_request(raw_req, handle).await {
-                         error!("[Server] Error dispatching request: {}", e);
                     }
-                },
-                Some(notif_json) = notification_rx.recv() => {
-                    self.connection.send_raw(&notif_json).await?;
                 }
             }
         }
     }
 
+    /// Sends a message taken from `outgoing_rx`, counting it in the stats unless
+    /// it is one of the server's own requests, which share the queue.
+    async fn send_queued(&mut self, msg: &str) -> Result<()> {
+        self.connection.send_raw(msg).await?;
+        if !is_queued_request(msg) {
+            self.stats.notifications_sent += 1;
+        }
+        Ok(())
+    }
+
+    /// Runs a handler's future under [`Self::serve_while`], giving up once the
+    /// server's request timeout (if any) elapses.
+    async fn run_handler<T>(
+        &mut self,
+        fut: impl Future<Output = T>,
+    ) -> Result<std::result::Result<T, Duration>> {
+        let limit = self.server.request_timeout;
+        self.run_handler_within(limit, fut).await
+    }
+
+    /// Like [`Self::run_handler`], with an explicit `limit`. The future is dropped
+    /// once the limit elapses, so the handler doesn't keep running.
+    async fn run_handler_within<T>(
+        &mut self,
+        limit: Option<Duration>,
+        fut: impl Future<Output = T>,
+    ) -> Result<std::result::Result<T, Duration>> {
+        self.serve_while(async move {
+            match limit {
+                Some(limit) => tokio::time::timeout(limit, fut).await.map_err(|_| limit),
+                None => Ok(fut.await),
+            }
+        })
+        .await
+    }
+
     async fn dispatch_request(&mut self, raw_req: Value, handle: ConnectionHandle) -> Result<()> {
         if raw_req.get("id").is_none() && raw_req.get("method").is_some() {
-            // Attempt to parse as a generic notification to get the method name.
-            if let Ok(notif) = serde_json::from_value::<Notification<Value>>(raw_req.clone()) {
-                if notif.method == "notifications/initialized" {
-                    // LSP spec uses "initialized", not "notifications/initialized"
-                    info!("[Session] Successful setup notification from client received.");
-                    // It's a notification, so we do nothing and wait for the next message.
-                    return Ok(());
-                } else {
-                    info!("Received unknown notification: {}", notif.method);
+            // Anything with a method but no id is a notification, whatever its params.
+            match raw_req["method"].as_str() {
+                Some(method) => {
+                    let params = raw_req.get("params").cloned().unwrap_or(Value::Null);
+                    self.handle_notification(method, params, handle).await;
                 }
-            } else {
-                info!("Received unparsable notification");
+                None => info!("Received unparsable notification"),
             }
+            // Notifications never get a response.
+            return Ok(());
         }
 
-        if !self.is_initialized {
+        let is_initialize = raw_req.get("method").and_then(Value::as_str) == Some("initialize");
+        if self.state == SessionState::AwaitingInitialize
+            || (self.server.skip_initialize && is_initialize)
+        {
             return self.handle_initialize(raw_req).await;
         }
 
         let req: Request<Value> = serde_json::from_value(raw_req)?;
+        let mut handle = handle;
+        handle.request_id = Some(req.id.clone());
+        handle.meta = req
+            .params
+            .as_ref()
+            .and_then(|params| params.get("_meta"))
+            .cloned();
+        // Correlates everything logged while handling this request with its id.
+        let span = info_span!("mcp_request", method = %req.method, id = ?req.id);
+
+        let server = Arc::clone(&self.server);
+        let metrics = server.metrics.as_deref().unwrap_or(&NoopMetrics);
+        let method = req.method.clone();
+        let started = Instant::now();
+        metrics.on_request(&method);
+        self.stats.requests += 1;
+        self.sent_error = false;
+        let result = self.handle_request(req, handle).instrument(span).await;
+        if result.is_err() || self.sent_error {
+            metrics.on_error(&method);
+            self.stats.errors += 1;
+        }
+        metrics.on_latency(&method, started.elapsed());
+        result
+    }
+
+    async fn handle_request(
+        &mut self,
+        req: Request<Value>,
+        handle: ConnectionHandle,
+    ) -> Result<()> {
+        if self.server.shutting_down.load(Ordering::SeqCst) {
+            info!(
+                "[Session] Rejecting '{}': server is shutting down",
+                req.method
+            );
+            return self
+                .send_error(req.id, SERVER_SHUTTING_DOWN, "Server is shutting down")
+                .await;
+        }
+
+        // The spec allows `ping` at any time, including during the handshake.
+        if self.state == SessionState::Initializing && req.method != "ping" {
+            info!(
+                "[Session] Rejecting '{}' received before notifications/initialized",
+                req.method
+            );
+            return self
+                .send_error(
+                    req.id,
+                    SERVER_NOT_INITIALIZED,
+                    "Server not initialized: waiting for notifications/initialized",
+                )
+                .await;
+        }
 
         use super::server::ToolHandler as ServerToolHandlerEnum; // Alias to avoid confusion if needed, and for clarity
 
@@ -124,10 +694,12 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
                 let tools: Vec<Tool> = self
                     .server
                     .tools_and_handlers
+                    .read()
+                    .unwrap()
                     .values()
-                    .map(|(tool, _handler)| tool.clone())
+                    .map(|registered| registered.tool.clone())
                     .collect();
-                let result = ListToolsResult { tools };
+                let result = ListToolsResult { tools, meta: None };
                 let response = Response {
                     id: req.id,
                     jsonrpc: "2.0".to_string(),
@@ -142,14 +714,87 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
                 };
                 let params: CallToolParams = serde_json::from_value(has_param)?;
                 // Adjusted to use tools_and_handlers and new handler signature
-                if let Some((_tool_meta, handler_arc)) =
-                    self.server.tools_and_handlers.get(&params.name)
-                {
+                // Clone the tool out so the lock is not held while its handler runs.
+                let registered = self
+                    .server
+                    .tools_and_handlers
+                    .read()
+                    .unwrap()
+                    .get(&params.name)
+                    .map(Arc::clone);
+                if let Some(registered) = registered {
+                    let started = Instant::now();
+                    if let Some(guard) = &self.server.tool_guard {
+                        if let Err(e) = guard(&params.name, &params.arguments) {
+                            info!("[Session] Tool '{}' denied by guard: {}", params.name, e);
+                            self.emit_audit(&params.name, &params.arguments, false, started);
+                            let code = self
+                                .server
+                                .tool_guard_error_code
+                                .unwrap_or(TOOL_CALL_DENIED);
+                            return self.send_error(req.id, code, &e.to_string()).await;
+                        }
+                    }
+                    #[cfg(feature = "schema-validation")]
+                    if self.server.validate_tool_input {
+                        let checked = registered
+                            .input_validator
+                            .check(&params.name, &params.arguments);
+                        if let Err(e) = checked {
+                            info!("[Session] Tool '{}' input rejected: {}", params.name, e);
+                            self.emit_audit(&params.name, &params.arguments, false, started);
+                            return self.send_handler_error(req.id, e).await;
+                        }
+                    }
                     let arguments_arc = Arc::new(params.arguments); // Wrap arguments in Arc<Value>
-                    let result = match **handler_arc {
-                        ServerToolHandlerEnum::Untyped(ref h) => h(handle, arguments_arc).await?,
-                        ServerToolHandlerEnum::Typed(ref h) => h(handle, arguments_arc).await?,
+                    let handler_future = match *registered.handler {
+                        ServerToolHandlerEnum::Untyped(ref h) => {
+                            h(handle, Arc::clone(&arguments_arc))
+                        }
+                        ServerToolHandlerEnum::Typed(ref h) => {
+                            h(handle, Arc::clone(&arguments_arc))
+                        }
+                    };
+                    // A panicking handler must not take the whole session down with it.
+                    let limit = self.server.tool_timeout.or(self.server.request_timeout);
+                    let outcome = match self
+                        .run_handler_within(limit, AssertUnwindSafe(handler_future).catch_unwind())
+                        .await?
+                    {
+                        Ok(outcome) => outcome,
+                        Err(limit) => {
+                            self.emit_audit(&params.name, &arguments_arc, false, started);
+                            return self.send_timeout_error(req.id, &req.method, limit).await;
+                        }
+                    };
+                    let success = matches!(&outcome, Ok(Ok(result)) if !result.is_error);
+                    self.emit_audit(&params.name, &arguments_arc, success, started);
+                    let mut result = match outcome {
+                        Ok(Ok(result)) => result,
+                        Ok(Err(err)) => return self.send_handler_error(req.id, err).await,
+                        Err(panic) => {
+                            error!(
+                                tool_name = %params.name,
+                                panic = panic_message(&panic),
+                                "Tool handler panicked"
+                            );
+                            return self
+                                .send_error(
+                                    req.id,
+                                    INTERNAL_ERROR,
+                                    &format!("Tool '{}' failed unexpectedly", params.name),
+                                )
+                                .await;
+                        }
                     };
+                    if let Some(transform) = &self.server.result_transform {
+                        transform(&mut result);
+                    }
+                    if let Some(log) = &self.server.result_log {
+                        if let Err(e) = log.append(&params.name, &arguments_arc, &result).await {
+                            warn!(tool_name = %params.name, "Failed to write result log: {}", e);
+                        }
+                    }
                     let response = Response {
                         id: req.id,
                         jsonrpc: "2.0".to_string(),
@@ -166,14 +811,62 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
                 }
             }
             "resources/list" => {
-                let handler = self.server.list_resources_handler.clone();
-                self.dispatch(req, &handler, |h, _: ListResourcesParams| h(handle.clone()))
-                    .await
+                let server = Arc::clone(&self.server);
+                let handler = (server.list_resources_handler.is_some()
+                    || !server.static_resources.is_empty())
+                .then_some(server);
+                self.dispatch(req, &handler, |server, _: ListResourcesParams| {
+                    let handle = handle.clone();
+                    async move {
+                        let mut resources: Vec<Resource> = server
+                            .static_resources
+                            .iter()
+                            .map(|(resource, _)| resource.clone())
+                            .collect();
+                        if let Some(h) = &server.list_resources_handler {
+                            resources.extend(h(handle).await?);
+                        }
+                        Ok(resources)
+                    }
+                })
+                .await
+            }
+            "resources/templates/list" => {
+                let handler = self.server.list_resource_templates_handler.clone();
+                self.dispatch(req, &handler, |h, _: ListResourceTemplatesParams| {
+                    let templates = h(handle.clone());
+                    async move {
+                        Ok(ListResourceTemplatesResult {
+                            resource_templates: templates.await?,
+                            meta: None,
+                        })
+                    }
+                })
+                .await
             }
             "resources/read" => {
-                let handler = self.server.read_resource_handler.clone();
-                self.dispatch(req, &handler, |h, p: ReadResourceParams| {
-                    h(handle.clone(), p.uri)
+                let server = Arc::clone(&self.server);
+                let handler = (server.read_resource_handler.is_some()
+                    || !server.static_resources.is_empty())
+                .then_some(server);
+                self.dispatch(req, &handler, |server, p: ReadResourceParams| {
+                    let handle = handle.clone();
+                    async move {
+                        let static_match = server
+                            .static_resources
+                            .iter()
+                            .find(|(resource, _)| resource.uri == p.uri);
+                        if let Some((_, contents)) = static_match {
+                            return Ok(ReadResourceResult {
+                                contents: vec![contents.clone()],
+                                meta: None,
+                            });
+                        }
+                        match &server.read_resource_handler {
+                            Some(h) => h(handle, p.uri).await,
+                            None => Err(Error::NotFound(format!("resource {}", p.uri))),
+                        }
+                    }
                 })
                 .await
             }
@@ -182,6 +875,14 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
                 self.dispatch(req, &handler, |h, _: ListPromptsParams| h(handle.clone()))
                     .await
             }
+            "ping" => {
+                let response = Response {
+                    id: req.id,
+                    jsonrpc: "2.0".to_string(),
+                    result: serde_json::Map::new(),
+                };
+                self.connection.send_serializable(response).await
+            }
             "prompts/get" => {
                 let handler = self.server.get_prompt_handler.clone();
                 self.dispatch(req, &handler, |h, p: GetPromptParams| {
@@ -189,10 +890,46 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
                 })
                 .await
             }
+            "completion/complete" => {
+                let handlers = self.server.prompt_completion_handlers.clone();
+                self.dispatch(
+                    req,
+                    &Some(handlers),
+                    |handlers, p: CompleteParams| async move {
+                        let handler = match &p.ref_ {
+                            CompletionReference::Prompt { name } => handlers.get(name).cloned(),
+                            CompletionReference::Resource { .. } => None,
+                        };
+                        let mut values = match handler {
+                            Some(h) => h(p.argument.name, p.argument.value).await?,
+                            None => Vec::new(),
+                        };
+                        let total = values.len();
+                        values.truncate(MAX_COMPLETION_VALUES);
+                        Ok(CompleteResult {
+                            completion: Completion {
+                                values,
+                                total: Some(total as u32),
+                                has_more: Some(total > MAX_COMPLETION_VALUES),
+                            },
+                        })
+                    },
+                )
+                .await
+            }
             "initialize" => Err(Error::Other(
                 "Client sent 'initialize' request twice.".into(),
             )),
             unhandled_method => {
+                if self.server.unknown_method_handler.is_some() {
+                    let handler = self.server.unknown_method_handler.clone();
+                    let method = unhandled_method.to_string();
+                    return self
+                        .dispatch(req, &handler, |h, p: Value| {
+                            h(handle.clone(), method.clone(), p)
+                        })
+                        .await;
+                }
                 self.send_error(
                     req.id,
                     METHOD_NOT_FOUND,
@@ -203,47 +940,122 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
         }
     }
 
+    async fn handle_notification(&mut self, method: &str, params: Value, handle: ConnectionHandle) {
+        if method == "notifications/initialized" && self.state == SessionState::Initializing {
+            // LSP spec uses "initialized", not "notifications/initialized"
+            self.state = SessionState::Initialized;
+            info!("[Session] Successful setup notification from client received.");
+        }
+        if let Some(handler) = self.server.notification_handlers.get(method).cloned() {
+            let outcome = self.serve_while(handler(handle, params)).await;
+            if let Err(e) = outcome.and_then(|result| result) {
+                error!(
+                    "[Session] Notification handler for '{}' failed: {}",
+                    method, e
+                );
+            }
+        } else if method != "notifications/initialized" {
+            info!("Received unknown notification: {}", method);
+        }
+    }
+
     async fn handle_initialize(&mut self, raw_req: Value) -> Result<()> {
         info!("[Session] Initialize handshake started. Session is now in pending.");
         if let Some("initialize") = raw_req.get("method").and_then(Value::as_str) {
-            let init_req: Request<InitializeRequestParams> = serde_json::from_value(raw_req)?;
+            let req: Request<Value> = serde_json::from_value(raw_req)
+                .map_err(|e| Error::Handshake(format!("malformed initialize request: {}", e)))?;
+            let params = req.params.unwrap_or(Value::Null);
+            let init_params: InitializeRequestParams = match serde_json::from_value(params) {
+                Ok(params) => params,
+                Err(e) => {
+                    let message = format!("Invalid initialize params: {}", e);
+                    self.send_error(req.id, INVALID_PARAMS, &message).await?;
+                    return Err(Error::Handshake(message));
+                }
+            };
+            let protocol_version = self
+                .server
+                .negotiate_protocol_version(&init_params.protocol_version);
+            if protocol_version != init_params.protocol_version {
+                warn!(
+                    "[Session] Client '{}' requested unsupported protocol version {}; offering {}",
+                    init_params.client_info.name, init_params.protocol_version, protocol_version
+                );
+            }
             // --- DYNAMIC CAPABILITIES LOGIC ---
             // 1. Start with default, empty capabilities.
             let mut capabilities = ServerCapabilities::default();
 
             // 2. Check if any tools have been registered.
             // Adjusted to use tools_and_handlers
-            if !self.server.tools_and_handlers.is_empty() {
+            if !self.server.tools_and_handlers.read().unwrap().is_empty() {
                 // If so, add the "tools" capability to our announcement.
                 capabilities.tools = Some(ToolsCapability {
-                    // For now, we can hardcode this sub-capability to false.
-                    // It signals that we don't support the `tools/listChanged` notification.
+                    // Tools can be added and removed at runtime (`Server::add_tool`),
+                    // which announces `notifications/tools/list_changed`.
+                    list_changed: Some(true),
+                });
+            }
+            // 3. Likewise for resources and prompts, so clients can skip calling
+            //    methods this server has no handlers for.
+            if self.server.list_resources_handler.is_some()
+                || self.server.read_resource_handler.is_some()
+                || self.server.list_resource_templates_handler.is_some()
+                || !self.server.static_resources.is_empty()
+            {
+                capabilities.resources = Some(ResourcesCapability {
+                    subscribe: Some(false),
+                    list_changed: Some(false),
+                });
+            }
+            if self.server.list_prompts_handler.is_some()
+                || self.server.get_prompt_handler.is_some()
+            {
+                capabilities.prompts = Some(PromptsCapability {
                     list_changed: Some(false),
                 });
             }
             let init_response = Response {
                 jsonrpc: "2.0".to_string(),
-                id: init_req.id,
+                id: req.id,
                 result: InitializeResult {
-                    protocol_version: LATEST_PROTOCOL_VERSION.to_string(),
+                    protocol_version,
                     server_info: Implementation {
                         name: self.server.name.clone(),
-                        version: env!("CARGO_PKG_VERSION").to_string(),
+                        version: self
+                            .server
+                            .version
+                            .clone()
+                            .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string()),
                     },
-                    capabilities: capabilities,
+                    capabilities,
+                    instructions: self.server.instructions.clone(),
                 },
             };
             self.connection.send_serializable(init_response).await?;
-            self.is_initialized = true;
-            info!("[Session] Initialize handshake successful. Session is now initialized.");
+            // Every handle given to a handler from now on is cloned from this one.
+            self.handle.client_capabilities = init_params.capabilities;
+            self.state = SessionState::Initializing;
+            info!("[Session] Initialize result sent. Waiting for notifications/initialized.");
             Ok(())
         } else {
-            Err(Error::Other(
-                "First message from client was not an 'initialize' request.".into(),
+            Err(Error::Handshake(
+                "first message from client was not an 'initialize' request".into(),
             ))
         }
     }
 
+    async fn send_timeout_error(
+        &mut self,
+        id: RequestId,
+        method: &str,
+        limit: Duration,
+    ) -> Result<()> {
+        warn!("[Session] '{}' timed out after {:?}", method, limit);
+        let message = format!("Request '{}' timed out after {:?}", method, limit);
+        self.send_error(id, REQUEST_TIMED_OUT, &message).await
+    }
+
     async fn dispatch<H, P, R, F, Fut>(
         &mut self,
         req: Request<Value>,
@@ -264,7 +1076,13 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
             };
             match serde_json::from_value(raw_param) {
                 Ok(params) => {
-                    match f(handler, params).await {
+                    let outcome = match self.run_handler(f(handler, params)).await? {
+                        Ok(outcome) => outcome,
+                        Err(limit) => {
+                            return self.send_timeout_error(req.id, &req.method, limit).await
+                        }
+                    };
+                    match outcome {
                         Ok(result) => {
                             // On success, send the result back.
                             let response = Response {
@@ -274,11 +1092,7 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
                             };
                             self.connection.send_serializable(response).await
                         }
-                        Err(err) => {
-                            // If the handler returns an error, send a JSON-RPC error response.
-                            self.send_error(req.id, crate::types::INTERNAL_ERROR, &err.to_string())
-                                .await
-                        }
+                        Err(err) => self.send_handler_error(req.id, err).await,
                     }
                 }
                 Err(e) => {
@@ -297,26 +1111,107 @@ impl<A: NetworkAdapter + Send + 'static> ServerSession<A> {
         }
     }
 
+    /// Reports a finished tool call to the server's audit callback, if one is set.
+    fn emit_audit(&self, tool_name: &str, args: &Value, success: bool, started: Instant) {
+        if let Some(audit) = &self.server.audit_handler {
+            audit(AuditEvent {
+                peer: self.peer.clone(),
+                tool_name: tool_name.to_string(),
+                args_hash: hash_args(args),
+                args: self.server.audit_full_args.then(|| args.clone()),
+                success,
+                duration: started.elapsed(),
+            });
+        }
+    }
+
+    /// Reports a handler's failure to the client under the code the error maps to
+    /// (see [`Error::rpc_code`]). A handler may also pick the JSON-RPC error itself,
+    /// `data` included, by returning [`Error::JsonRpc`].
+    async fn send_handler_error(&mut self, id: RequestId, err: Error) -> Result<()> {
+        match err {
+            Error::JsonRpc(error) => {
+                self.send_error_with_data(id, error.code, &error.message, error.data)
+                    .await
+            }
+            other => {
+                self.send_error(id, other.rpc_code(), &other.to_string())
+                    .await
+            }
+        }
+    }
+
     async fn send_error(&mut self, id: RequestId, code: i32, message: &str) -> Result<()> {
+        self.send_error_with_data(id, code, message, None).await
+    }
+
+    /// Like [`Self::send_error`], with structured detail in the error's `data`.
+    async fn send_error_with_data(
+        &mut self,
+        id: RequestId,
+        code: i32,
+        message: &str,
+        data: Option<Value>,
+    ) -> Result<()> {
+        self.sent_error = true;
         let error_response = ErrorResponse {
             jsonrpc: "2.0".to_string(),
             id,
             error: ErrorData {
                 code,
                 message: message.to_string(),
+                data,
             },
         };
         self.connection.send_serializable(error_response).await
     }
 }
 
+/// Whether `msg` answers a request, as opposed to being a request or notification.
+fn is_response(msg: &Value) -> bool {
+    msg.get("method").is_none()
+        && msg.get("id").is_some()
+        && (msg.get("result").is_some() || msg.get("error").is_some())
+}
+
+/// Whether a message from `outgoing_rx` is a request, such as `roots/list`, as
+/// opposed to a notification. Only the `id` is looked at, not decoded.
+fn is_queued_request(msg: &str) -> bool {
+    #[derive(serde::Deserialize)]
+    struct Queued {
+        id: Option<serde::de::IgnoredAny>,
+    }
+    serde_json::from_str::<Queued>(msg).is_ok_and(|queued| queued.id.is_some())
+}
+
+/// Hashes tool arguments so audit records can correlate calls without storing raw input.
+fn hash_args(args: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    args.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Extracts a printable message from a panic payload for logging purposes.
+fn panic_message(panic: &Box<dyn Any + Send>) -> &str {
+    if let Some(msg) = panic.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = panic.downcast_ref::<String>() {
+        msg
+    } else {
+        "<non-string panic payload>"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        network_adapter::NetworkAdapter,
+        network_adapter::{duplex_pair, DuplexAdapter, NetworkAdapter},
         server::server::Server,
-        types::{CallToolResult, Content, JSONRPCResponse, ListToolsChangedParams, Tool},
+        types::{
+            CallToolResult, Content, JSONRPCResponse, ListToolsChangedParams, Tool,
+            LATEST_PROTOCOL_VERSION,
+        },
         ProtocolConnection,
     };
     use async_trait::async_trait;
@@ -324,47 +1219,45 @@ mod tests {
     use std::collections::VecDeque;
     use std::sync::Mutex;
 
-    // --- Mock Infrastructure ---
-    #[derive(Default, Clone)]
-    struct MockAdapter {
-        incoming: Arc<Mutex<VecDeque<String>>>,
-        outgoing: Arc<Mutex<VecDeque<String>>>,
-    }
-    impl MockAdapter {
-        fn push_incoming(&self, msg: String) {
-            self.incoming.lock().unwrap().push_back(msg);
+    /// Sends `messages` from the client's end of a fresh connection, then stops
+    /// sending, so the session sees the client hang up after the last one.
+    async fn client_sending(messages: Vec<String>) -> (DuplexAdapter, DuplexAdapter) {
+        let (mut client, server_end) = duplex_pair();
+        for msg in messages {
+            client.send(&msg).await.unwrap();
         }
+        client.close_send();
+        (client, server_end)
     }
-    #[async_trait]
-    impl NetworkAdapter for MockAdapter {
-        async fn send(&mut self, msg: &str) -> Result<()> {
-            Ok(self.outgoing.lock().unwrap().push_back(msg.to_string()))
-        }
-        async fn recv(&mut self) -> Result<Option<String>> {
-            Ok(self.incoming.lock().unwrap().pop_front())
+
+    /// Everything the session sent to `client`, once the session is gone.
+    async fn sent_to(client: &mut DuplexAdapter) -> VecDeque<String> {
+        let mut sent = VecDeque::new();
+        while let Some(msg) = client.recv().await.unwrap() {
+            sent.push_back(msg);
         }
+        sent
     }
 
     /// Test helper to run a session and collect its output.
     async fn run_session_with_requests(
         server: Arc<Server>,
         requests: Vec<String>,
-    ) -> Arc<Mutex<VecDeque<String>>> {
-        let adapter = MockAdapter::default();
-        let outgoing_buffer = Arc::clone(&adapter.outgoing);
-        for req in requests {
-            adapter.push_incoming(req);
-        }
-
-        let conn = ProtocolConnection::new(adapter);
-        let session = ServerSession::new(conn, server);
+    ) -> VecDeque<String> {
+        let (mut client, server_end) = client_sending(requests).await;
+        let session = ServerSession::new(ProtocolConnection::new(server_end), server);
 
         tokio::time::timeout(std::time::Duration::from_secs(1), session.run())
             .await
             .expect("Session run timed out")
             .expect("Session run failed");
 
-        outgoing_buffer
+        sent_to(&mut client).await
+    }
+
+    fn make_initialized_notification() -> String {
+        serde_json::to_string(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .unwrap()
     }
 
     fn make_init_request() -> String {
@@ -388,8 +1281,10 @@ mod tests {
                 Ok(CallToolResult {
                     content: vec![Content::Text {
                         text: "Success!".to_string(),
+                        annotations: None,
                     }],
                     is_error: false,
+                    ..Default::default()
                 })
             },
         ));
@@ -398,9 +1293,17 @@ mod tests {
             &json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list", "params": {} }),
         )
         .unwrap();
-        let outgoing = run_session_with_requests(server, vec![make_init_request(), list_req]).await;
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                list_req,
+            ],
+        )
+        .await;
 
-        let responses = outgoing.lock().unwrap();
+        let responses = outgoing;
         assert_eq!(responses.len(), 2);
         let list_response_str = responses.iter().find(|s| s.contains("\"id\":1")).unwrap();
         // Changed Vec<Tool> to ListToolsResult for deserialization
@@ -441,9 +1344,17 @@ mod tests {
         ));
 
         let call_req = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "notification-tool", "arguments": {}} })).unwrap();
-        let outgoing = run_session_with_requests(server, vec![make_init_request(), call_req]).await;
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call_req,
+            ],
+        )
+        .await;
 
-        let responses = outgoing.lock().unwrap();
+        let responses = outgoing;
         // The server sends a response to init, a response to the call, AND a notification.
         assert_eq!(responses.len(), 3);
         let notif_found = responses.iter().any(|s| s.contains("test/notification"));
@@ -464,10 +1375,18 @@ mod tests {
         .unwrap();
 
         // 3. Run the session.
-        let outgoing = run_session_with_requests(server, vec![make_init_request(), call_req]).await;
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call_req,
+            ],
+        )
+        .await;
 
         // 4. Assert that the server sent back a well-formed "Method not found" error.
-        let responses = outgoing.lock().unwrap();
+        let responses = outgoing;
         let error_response_str = responses.iter().find(|s| s.contains("\"id\":101")).unwrap();
         let error_response: JSONRPCResponse<Value> =
             serde_json::from_str(error_response_str).unwrap();
@@ -483,4 +1402,1357 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_tool_guard_denies_call_without_running_handler() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let handler_ran = Arc::new(AtomicBool::new(false));
+        let handler_ran_clone = Arc::clone(&handler_ran);
+        let server = Arc::new(
+            Server::new("test")
+                .register_tool(
+                    Tool {
+                        name: "secret-tool".to_string(),
+                        ..Default::default()
+                    },
+                    move |_handle, _args| {
+                        handler_ran_clone.store(true, Ordering::SeqCst);
+                        async { Ok(CallToolResult::default()) }
+                    },
+                )
+                .with_tool_guard(|tool, _args| {
+                    if tool == "secret-tool" {
+                        Err("access to 'secret-tool' denied".into())
+                    } else {
+                        Ok(())
+                    }
+                })
+                .tool_guard_error_code(-32050),
+        );
+
+        let call_req = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 7, "method": "tools/call", "params": {"name": "secret-tool", "arguments": {}} })).unwrap();
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call_req,
+            ],
+        )
+        .await;
+
+        let responses = outgoing;
+        let response_str = responses.iter().find(|s| s.contains("\"id\":7")).unwrap();
+        let response: JSONRPCResponse<Value> = serde_json::from_str(response_str).unwrap();
+        match response {
+            JSONRPCResponse::Success(_) => panic!("Expected the guard to deny the call"),
+            JSONRPCResponse::Error(err) => {
+                assert_eq!(err.error.code, -32050);
+                assert!(err.error.message.contains("denied"));
+            }
+        }
+        assert!(
+            !handler_ran.load(Ordering::SeqCst),
+            "Handler must not run for a denied tool"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_guard_allows_typed_tool() {
+        #[derive(serde::Deserialize)]
+        struct EchoArgs {
+            message: String,
+        }
+
+        let server = Arc::new(
+            Server::new("test")
+                .register_tool_typed(
+                    Tool {
+                        name: "echo".to_string(),
+                        ..Default::default()
+                    },
+                    |_handle, args: EchoArgs| async move {
+                        Ok(CallToolResult {
+                            content: vec![Content::Text {
+                                text: args.message,
+                                annotations: None,
+                            }],
+                            is_error: false,
+                            ..Default::default()
+                        })
+                    },
+                )
+                .with_tool_guard(|_tool, args| {
+                    if args.get("message").is_some() {
+                        Ok(())
+                    } else {
+                        Err("missing message".into())
+                    }
+                }),
+        );
+
+        let call_req = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 8, "method": "tools/call", "params": {"name": "echo", "arguments": {"message": "hi"}} })).unwrap();
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call_req,
+            ],
+        )
+        .await;
+
+        let responses = outgoing;
+        let response_str = responses.iter().find(|s| s.contains("\"id\":8")).unwrap();
+        let response: JSONRPCResponse<CallToolResult> = serde_json::from_str(response_str).unwrap();
+        match response {
+            JSONRPCResponse::Success(res) => {
+                assert_eq!(
+                    res.result.content,
+                    vec![Content::Text {
+                        text: "hi".to_string(),
+                        annotations: None,
+                    }]
+                );
+            }
+            JSONRPCResponse::Error(err) => panic!("Expected success, got {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_tool_returns_error_and_session_survives() {
+        let server = Arc::new(
+            Server::new("test")
+                .register_tool(
+                    Tool {
+                        name: "panicky".to_string(),
+                        ..Default::default()
+                    },
+                    |_handle, _args| async {
+                        panic!("secret internal detail");
+                    },
+                )
+                .register_tool(
+                    Tool {
+                        name: "steady".to_string(),
+                        ..Default::default()
+                    },
+                    |_handle, _args| async {
+                        Ok(CallToolResult {
+                            content: vec![Content::Text {
+                                text: "still here".to_string(),
+                                annotations: None,
+                            }],
+                            is_error: false,
+                            ..Default::default()
+                        })
+                    },
+                ),
+        );
+
+        let panic_req = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "panicky", "arguments": {}} })).unwrap();
+        let steady_req = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "steady", "arguments": {}} })).unwrap();
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                panic_req,
+                steady_req,
+            ],
+        )
+        .await;
+
+        let responses = outgoing;
+        let panic_response_str = responses.iter().find(|s| s.contains("\"id\":1")).unwrap();
+        match serde_json::from_str::<JSONRPCResponse<Value>>(panic_response_str).unwrap() {
+            JSONRPCResponse::Success(_) => panic!("Expected an error for the panicking tool"),
+            JSONRPCResponse::Error(err) => {
+                assert_eq!(err.error.code, INTERNAL_ERROR);
+                assert!(err.error.message.contains("panicky"));
+                assert!(!err.error.message.contains("secret internal detail"));
+            }
+        }
+
+        let steady_response_str = responses.iter().find(|s| s.contains("\"id\":2")).unwrap();
+        match serde_json::from_str::<JSONRPCResponse<CallToolResult>>(steady_response_str).unwrap()
+        {
+            JSONRPCResponse::Success(res) => assert!(!res.result.is_error),
+            JSONRPCResponse::Error(err) => panic!("Expected success, got {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_method_fallback_handles_request() {
+        let server = Arc::new(
+            Server::new("test").on_unknown_method(|_handle, method, params| async move {
+                Ok(json!({ "forwarded": method, "echo": params }))
+            }),
+        );
+
+        let custom_req = serde_json::to_string(
+            &json!({ "jsonrpc": "2.0", "id": 3, "method": "custom/thing", "params": {"x": 1} }),
+        )
+        .unwrap();
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                custom_req,
+            ],
+        )
+        .await;
+
+        let responses = outgoing;
+        let response_str = responses.iter().find(|s| s.contains("\"id\":3")).unwrap();
+        match serde_json::from_str::<JSONRPCResponse<Value>>(response_str).unwrap() {
+            JSONRPCResponse::Success(res) => {
+                assert_eq!(
+                    res.result,
+                    json!({ "forwarded": "custom/thing", "echo": {"x": 1} })
+                );
+            }
+            JSONRPCResponse::Error(err) => panic!("Expected success, got {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prompt_argument_completion() {
+        let server = Arc::new(Server::new("test").on_prompt_argument_complete(
+            "greet",
+            |argument, value| async move {
+                assert_eq!(argument, "language");
+                let candidates = ["English", "Esperanto", "French"];
+                Ok(candidates
+                    .iter()
+                    .filter(|c| c.starts_with(&value))
+                    .map(|c| c.to_string())
+                    .collect())
+            },
+        ));
+
+        let complete_req = serde_json::to_string(&json!({
+            "jsonrpc": "2.0", "id": 4, "method": "completion/complete",
+            "params": {
+                "ref": { "type": "ref/prompt", "name": "greet" },
+                "argument": { "name": "language", "value": "E" }
+            }
+        }))
+        .unwrap();
+        let unknown_prompt_req = serde_json::to_string(&json!({
+            "jsonrpc": "2.0", "id": 5, "method": "completion/complete",
+            "params": {
+                "ref": { "type": "ref/prompt", "name": "other" },
+                "argument": { "name": "language", "value": "E" }
+            }
+        }))
+        .unwrap();
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                complete_req,
+                unknown_prompt_req,
+            ],
+        )
+        .await;
+
+        let responses = outgoing;
+        let response_str = responses.iter().find(|s| s.contains("\"id\":4")).unwrap();
+        match serde_json::from_str::<JSONRPCResponse<CompleteResult>>(response_str).unwrap() {
+            JSONRPCResponse::Success(res) => {
+                assert_eq!(res.result.completion.values, vec!["English", "Esperanto"]);
+                assert_eq!(res.result.completion.has_more, Some(false));
+            }
+            JSONRPCResponse::Error(err) => panic!("Expected success, got {:?}", err),
+        }
+
+        let response_str = responses.iter().find(|s| s.contains("\"id\":5")).unwrap();
+        match serde_json::from_str::<JSONRPCResponse<CompleteResult>>(response_str).unwrap() {
+            JSONRPCResponse::Success(res) => assert!(res.result.completion.values.is_empty()),
+            JSONRPCResponse::Error(err) => panic!("Expected success, got {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_events_for_successful_and_failed_calls() {
+        let events = Arc::new(Mutex::new(Vec::<AuditEvent>::new()));
+        let events_clone = Arc::clone(&events);
+        let server = Arc::new(
+            Server::new("test")
+                .register_tool(
+                    Tool {
+                        name: "ok-tool".to_string(),
+                        ..Default::default()
+                    },
+                    |_handle, _args| async { Ok(CallToolResult::default()) },
+                )
+                .register_tool(
+                    Tool {
+                        name: "failing-tool".to_string(),
+                        ..Default::default()
+                    },
+                    |_handle, _args| async {
+                        Ok(CallToolResult {
+                            content: vec![],
+                            is_error: true,
+                            ..Default::default()
+                        })
+                    },
+                )
+                .on_audit(move |event| events_clone.lock().unwrap().push(event)),
+        );
+
+        let ok_req = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "ok-tool", "arguments": {"secret": "hunter2"}} })).unwrap();
+        let fail_req = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "failing-tool", "arguments": {"secret": "hunter2"}} })).unwrap();
+        run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                ok_req,
+                fail_req,
+            ],
+        )
+        .await;
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].tool_name, "ok-tool");
+        assert!(events[0].success);
+        assert_eq!(events[1].tool_name, "failing-tool");
+        assert!(!events[1].success);
+        // Identical arguments hash identically, and raw args are withheld by default.
+        assert_eq!(events[0].args_hash, events[1].args_hash);
+        assert!(events[0].args.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_audit_full_args_opt_in() {
+        let events = Arc::new(Mutex::new(Vec::<AuditEvent>::new()));
+        let events_clone = Arc::clone(&events);
+        let server = Arc::new(
+            Server::new("test")
+                .register_tool(
+                    Tool {
+                        name: "ok-tool".to_string(),
+                        ..Default::default()
+                    },
+                    |_handle, _args| async { Ok(CallToolResult::default()) },
+                )
+                .on_audit(move |event| events_clone.lock().unwrap().push(event))
+                .audit_full_args(true),
+        );
+
+        let ok_req = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "ok-tool", "arguments": {"n": 1}} })).unwrap();
+        run_session_with_requests(
+            server,
+            vec![make_init_request(), make_initialized_notification(), ok_req],
+        )
+        .await;
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].args, Some(json!({"n": 1})));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_initialize_is_a_handshake_error() {
+        let (mut client, server_end) = duplex_pair();
+        let mut session = ServerSession::new(
+            ProtocolConnection::new(server_end),
+            Arc::new(Server::new("test")),
+        );
+        // `capabilities` is missing.
+        let init = json!({
+            "jsonrpc": "2.0", "id": 0, "method": "initialize",
+            "params": { "protocolVersion": "test", "clientInfo": {"name": "test", "version": "0"} }
+        });
+
+        let err = session.handle_initialize(init).await.unwrap_err();
+        match err {
+            Error::Handshake(msg) => assert!(msg.contains("capabilities"), "got: {}", msg),
+            other => panic!("expected a handshake error, got {:?}", other),
+        }
+        assert_eq!(session.state, SessionState::AwaitingInitialize);
+
+        let raw = client.recv().await.unwrap().unwrap();
+        let response: ErrorResponse = serde_json::from_str(&raw).unwrap();
+        assert_eq!(response.error.code, INVALID_PARAMS);
+        assert!(response
+            .error
+            .message
+            .contains("missing field `capabilities`"));
+    }
+
+    async fn negotiated_version(server: Server, params: Value) -> Value {
+        let init = serde_json::to_string(&json!({
+            "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": params
+        }))
+        .unwrap();
+        let mut responses = run_session_with_requests(Arc::new(server), vec![init]).await;
+        let raw = responses.pop_front().unwrap();
+        serde_json::from_str(&raw).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_echoes_supported_protocol_version() {
+        let server = Server::new("test").supported_protocol_versions(["2025-03-26", "2024-11-05"]);
+        let response = negotiated_version(
+            server,
+            json!({ "protocolVersion": "2024-11-05", "clientInfo": {"name": "test", "version": "0"}, "capabilities": {} }),
+        )
+        .await;
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+    }
+
+    #[tokio::test]
+    async fn test_initialize_offers_preferred_version_on_mismatch() {
+        let server = Server::new("test").supported_protocol_versions(["2025-03-26", "2024-11-05"]);
+        let response = negotiated_version(
+            server,
+            json!({ "protocolVersion": "1999-01-01", "clientInfo": {"name": "test", "version": "0"}, "capabilities": {} }),
+        )
+        .await;
+        assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+
+        // Without explicit configuration only the latest version is offered.
+        let response = negotiated_version(
+            Server::new("test"),
+            json!({ "protocolVersion": "1999-01-01", "clientInfo": {"name": "test", "version": "0"}, "capabilities": {} }),
+        )
+        .await;
+        assert_eq!(
+            response["result"]["protocolVersion"],
+            LATEST_PROTOCOL_VERSION
+        );
+    }
+
+    #[tokio::test]
+    async fn test_initialize_without_protocol_version_is_rejected() {
+        let response = negotiated_version(
+            Server::new("test"),
+            json!({ "clientInfo": {"name": "test", "version": "0"}, "capabilities": {} }),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("protocolVersion"));
+    }
+
+    #[tokio::test]
+    async fn test_request_before_initialized_notification_is_rejected() {
+        let server = Arc::new(Server::new("test").register_tool(
+            Tool {
+                name: "test-tool".to_string(),
+                ..Default::default()
+            },
+            |_handle, _args| async { Ok(CallToolResult::default()) },
+        ));
+        let list_req = |id: i64| {
+            serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": id, "method": "tools/list" }))
+                .unwrap()
+        };
+
+        let outgoing = run_session_with_requests(
+            server,
+            vec![
+                make_init_request(),
+                list_req(1),
+                make_initialized_notification(),
+                list_req(2),
+            ],
+        )
+        .await;
+        let responses: Vec<Value> = outgoing
+            .iter()
+            .map(|raw| serde_json::from_str(raw).unwrap())
+            .collect();
+
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[1]["id"], 1);
+        assert_eq!(responses[1]["error"]["code"], SERVER_NOT_INITIALIZED);
+        assert_eq!(responses[2]["id"], 2);
+        assert_eq!(responses[2]["result"]["tools"][0]["name"], "test-tool");
+    }
+
+    #[tokio::test]
+    async fn test_ping_before_initialized_notification_is_answered() {
+        let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }).to_string();
+
+        let mut outgoing = run_session_with_requests(
+            Arc::new(Server::new("test")),
+            vec![make_init_request(), ping],
+        )
+        .await;
+        outgoing.pop_front(); // initialize
+        let response: Value = serde_json::from_str(&outgoing.pop_front().unwrap()).unwrap();
+
+        assert_eq!(response, json!({ "jsonrpc": "2.0", "id": 1, "result": {} }));
+    }
+
+    #[tokio::test]
+    async fn test_initialize_advertises_only_registered_capabilities() {
+        let server = Arc::new(Server::new("test").on_list_prompts(|_| async {
+            Ok(crate::types::ListPromptsResult {
+                prompts: vec![],
+                meta: None,
+            })
+        }));
+        let mut outgoing = run_session_with_requests(server, vec![make_init_request()]).await;
+        let raw = outgoing.pop_front().unwrap();
+        let response: Response<InitializeResult> = serde_json::from_str(&raw).unwrap();
+
+        let capabilities = response.result.capabilities;
+        assert!(capabilities.prompts.is_some());
+        assert!(capabilities.tools.is_none());
+        assert!(capabilities.resources.is_none());
+        assert!(capabilities.logging.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_changed_helpers_use_spec_method_names() {
+        let (notification_sender, mut notification_rx) = mpsc::channel(8);
+        let handle = ConnectionHandle::new(notification_sender);
+        handle.notify_tools_list_changed().await.unwrap();
+        handle.notify_resources_list_changed().await.unwrap();
+        handle.notify_prompts_list_changed().await.unwrap();
+
+        for method in [
+            "notifications/tools/list_changed",
+            "notifications/resources/list_changed",
+            "notifications/prompts/list_changed",
+        ] {
+            let raw = notification_rx.recv().await.unwrap();
+            let notification: Notification<Value> = serde_json::from_str(&raw).unwrap();
+            assert_eq!(notification.method, method);
+            assert_eq!(notification.params, Some(json!({})));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_progress_pct_reports_out_of_100() {
+        let (notification_sender, mut notification_rx) = mpsc::channel(4);
+        let handle = ConnectionHandle::new(notification_sender);
+        handle
+            .send_progress_pct(RequestId::Str("job-1".to_string()), 42.5)
+            .await
+            .unwrap();
+        handle
+            .send_progress_pct(RequestId::Num(7), 150.0)
+            .await
+            .unwrap();
+
+        let raw = notification_rx.recv().await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&raw).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": { "progressToken": "job-1", "progress": 42.5, "total": 100.0 }
+            })
+        );
+        let raw = notification_rx.recv().await.unwrap();
+        let notification: Notification<Value> = serde_json::from_str(&raw).unwrap();
+        assert_eq!(
+            notification.params,
+            Some(json!({ "progressToken": 7, "progress": 100.0, "total": 100.0 }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_progress_indeterminate_omits_total() {
+        let (notification_sender, mut notification_rx) = mpsc::channel(4);
+        let handle = ConnectionHandle::new(notification_sender);
+        handle
+            .send_progress_indeterminate(RequestId::Num(3), "Indexing files")
+            .await
+            .unwrap();
+
+        let raw = notification_rx.recv().await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&raw).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": { "progressToken": 3, "progress": 0.0, "message": "Indexing files" }
+            })
+        );
+    }
+
+    /// Records the name and fields of every span created while it is installed.
+    #[derive(Clone, Default)]
+    struct SpanRecorder(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanRecorder {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            struct FieldWriter<'a>(&'a mut String);
+            impl tracing::field::Visit for FieldWriter<'_> {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn std::fmt::Debug,
+                ) {
+                    self.0.push_str(&format!("{}={:?} ", field.name(), value));
+                }
+            }
+            let mut fields = String::new();
+            attrs.record(&mut FieldWriter(&mut fields));
+            self.0
+                .lock()
+                .unwrap()
+                .push((attrs.metadata().name().to_string(), fields));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_handled_in_a_span_with_method_and_id() {
+        use tracing_subscriber::layer::SubscriberExt;
+        let recorder = SpanRecorder::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+
+        let list_tools = json!({ "jsonrpc": "2.0", "id": 42, "method": "tools/list" });
+        run_session_with_requests(
+            Arc::new(Server::new("span-test")),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                list_tools.to_string(),
+            ],
+        )
+        .await;
+
+        let spans = recorder.0.lock().unwrap().clone();
+        assert!(
+            spans.iter().any(|(name, fields)| name == "mcp_request"
+                && fields.contains("method=tools/list")
+                && fields.contains("id=Num(42)")),
+            "no matching span in {spans:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_requests_and_errors() {
+        let metrics = Arc::new(crate::server::AtomicMetrics::new());
+        let server = Server::new("metrics-test")
+            .register_tool(
+                Tool {
+                    name: "ok".to_string(),
+                    ..Default::default()
+                },
+                |_handle, _args| async { Ok(CallToolResult::text("done")) },
+            )
+            .with_metrics(Arc::clone(&metrics));
+
+        let call = |id: i64, name: &str| {
+            json!({
+                "jsonrpc": "2.0", "id": id, "method": "tools/call",
+                "params": { "name": name, "arguments": {} }
+            })
+            .to_string()
+        };
+        run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call(1, "ok"),
+                call(2, "missing"),
+            ],
+        )
+        .await;
+
+        assert_eq!(metrics.requests(), 2);
+        assert_eq!(metrics.errors(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_session_stats_reported_when_session_ends() {
+        #[derive(Default)]
+        struct LastSession(Mutex<Option<SessionStats>>);
+        impl crate::server::MetricsRecorder for LastSession {
+            fn on_session_end(&self, stats: &SessionStats) {
+                *self.0.lock().unwrap() = Some(*stats);
+            }
+        }
+
+        let recorder = Arc::new(LastSession::default());
+        let server = Server::new("stats-test")
+            .register_tool(
+                Tool {
+                    name: "ok".to_string(),
+                    ..Default::default()
+                },
+                |_handle, _args| async { Ok(CallToolResult::text("done")) },
+            )
+            .register_tool(
+                Tool {
+                    name: "announce".to_string(),
+                    ..Default::default()
+                },
+                |handle: ConnectionHandle, _args| async move {
+                    handle.notify_tools_list_changed().await?;
+                    Ok(CallToolResult::text("announced"))
+                },
+            )
+            .with_metrics(Arc::clone(&recorder));
+
+        let call = |id: i64, name: &str| {
+            json!({
+                "jsonrpc": "2.0", "id": id, "method": "tools/call",
+                "params": { "name": name, "arguments": {} }
+            })
+            .to_string()
+        };
+        run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call(1, "ok"),
+                call(2, "missing"),
+                call(3, "announce"),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            Some(SessionStats {
+                requests: 3,
+                errors: 1,
+                notifications_sent: 1,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_stats_leave_out_server_requests() {
+        let server = Arc::new(Server::new("stats-test").register_tool(
+            Tool {
+                name: "ask".to_string(),
+                ..Default::default()
+            },
+            |handle: ConnectionHandle, _args| async move {
+                handle.notify_tools_list_changed().await?;
+                let roots = handle.list_roots().await?;
+                Ok(CallToolResult::text(format!("{} roots", roots.len())))
+            },
+        ));
+        let (mut client, server_end) = duplex_pair();
+        let mut session = ServerSession::new(ProtocolConnection::new(server_end), server);
+        let session = tokio::spawn(async move {
+            session.serve().await.unwrap();
+            session.stats()
+        });
+
+        let call = json!({
+            "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+            "params": { "name": "ask", "arguments": {} }
+        });
+        for msg in [
+            make_init_request(),
+            make_initialized_notification(),
+            call.to_string(),
+        ] {
+            client.send(&msg).await.unwrap();
+        }
+        async fn next_sent(client: &mut DuplexAdapter) -> Value {
+            let raw = tokio::time::timeout(Duration::from_secs(1), client.recv())
+                .await
+                .expect("Session sent nothing")
+                .unwrap()
+                .unwrap();
+            serde_json::from_str(&raw).unwrap()
+        }
+        assert_eq!(next_sent(&mut client).await["id"], 0); // initialize
+        assert_eq!(
+            next_sent(&mut client).await["method"],
+            "notifications/tools/list_changed"
+        );
+        let roots_request = next_sent(&mut client).await;
+        assert_eq!(roots_request["method"], "roots/list");
+        let roots_response = json!({
+            "jsonrpc": "2.0", "id": roots_request["id"], "result": { "roots": [] }
+        });
+        client.send(&roots_response.to_string()).await.unwrap();
+        assert_eq!(
+            next_sent(&mut client).await["result"]["content"][0]["text"],
+            "0 roots"
+        );
+        drop(client);
+
+        assert_eq!(
+            session.await.unwrap(),
+            SessionStats {
+                requests: 1,
+                errors: 0,
+                notifications_sent: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_notifications_reach_handler_with_null_params() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = Arc::clone(&received);
+        let server = Server::new("notification-test").on_notification(
+            "custom/progress",
+            move |_handle, params| {
+                received_clone.lock().unwrap().push(params);
+                async { Ok(()) }
+            },
+        );
+
+        let outgoing = run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                json!({ "jsonrpc": "2.0", "method": "custom/progress" }).to_string(),
+                json!({ "jsonrpc": "2.0", "method": "custom/progress", "params": null })
+                    .to_string(),
+                json!({ "jsonrpc": "2.0", "method": "custom/progress", "params": { "n": 1 } })
+                    .to_string(),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![Value::Null, Value::Null, json!({ "n": 1 })]
+        );
+        // Only the initialize response was sent; notifications get no reply.
+        assert_eq!(outgoing.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_resource_read_times_out() {
+        let server = Server::new("timeout-test")
+            .on_read_resource(|_handle, _uri| async {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                Ok(ReadResourceResult {
+                    contents: vec![],
+                    meta: None,
+                })
+            })
+            .set_request_timeout(std::time::Duration::from_millis(50));
+
+        let outgoing = run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                json!({
+                    "jsonrpc": "2.0", "id": 1, "method": "resources/read",
+                    "params": { "uri": "file:///slow.txt" }
+                })
+                .to_string(),
+            ],
+        )
+        .await;
+
+        let response: Value = serde_json::from_str(&outgoing.back().unwrap().clone()).unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["error"]["code"], REQUEST_TIMED_OUT);
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("resources/read"));
+    }
+
+    #[tokio::test]
+    async fn test_slow_tool_times_out_and_is_cancelled() {
+        struct SetOnDrop(Arc<std::sync::atomic::AtomicBool>);
+        impl Drop for SetOnDrop {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+        let cancelled = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let cancelled_clone = Arc::clone(&cancelled);
+        let server = Server::new("tool-timeout-test")
+            .register_tool(
+                Tool {
+                    name: "sleepy".to_string(),
+                    ..Default::default()
+                },
+                move |_handle, args| {
+                    let guard = SetOnDrop(Arc::clone(&cancelled_clone));
+                    async move {
+                        if args["slow"] == true {
+                            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                        }
+                        drop(guard);
+                        Ok(CallToolResult::text("done"))
+                    }
+                },
+            )
+            .tool_timeout(std::time::Duration::from_millis(50));
+
+        let call = |id: i64, slow: bool| {
+            json!({
+                "jsonrpc": "2.0", "id": id, "method": "tools/call",
+                "params": { "name": "sleepy", "arguments": { "slow": slow } }
+            })
+            .to_string()
+        };
+        let outgoing = run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call(1, true),
+                call(2, false),
+            ],
+        )
+        .await;
+
+        let responses: Vec<Value> = outgoing
+            .iter()
+            .map(|s| serde_json::from_str(s).unwrap())
+            .collect();
+        assert_eq!(responses[1]["id"], 1);
+        assert_eq!(responses[1]["error"]["code"], REQUEST_TIMED_OUT);
+        assert!(cancelled.load(Ordering::SeqCst));
+        // The session is still healthy for the next call.
+        assert_eq!(responses[2]["id"], 2);
+        assert_eq!(responses[2]["result"]["content"][0]["text"], "done");
+    }
+
+    #[tokio::test]
+    async fn test_result_log_appends_a_line_per_tool_call() {
+        let path =
+            std::env::temp_dir().join(format!("mcp-result-log-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = Server::new("result-log-test")
+            .register_tool(
+                Tool {
+                    name: "echo".to_string(),
+                    ..Default::default()
+                },
+                |_handle, args| async move { Ok(CallToolResult::text(args["msg"].to_string())) },
+            )
+            .with_result_log(&path);
+
+        let call = |id: i64, msg: &str| {
+            json!({
+                "jsonrpc": "2.0", "id": id, "method": "tools/call",
+                "params": { "name": "echo", "arguments": { "msg": msg } }
+            })
+            .to_string()
+        };
+        run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call(1, "first"),
+                call(2, "second"),
+            ],
+        )
+        .await;
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let records: Vec<Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["tool"], "echo");
+        assert_eq!(records[0]["arguments"], json!({ "msg": "first" }));
+        assert_eq!(records[1]["arguments"], json!({ "msg": "second" }));
+        assert_eq!(records[1]["result"]["content"][0]["text"], "\"second\"");
+        assert!(records[0]["timestampMs"].is_u64());
+    }
+
+    /// Passes messages through until the peer hangs up, then fails instead of
+    /// reporting a clean close, which an in-memory transport never does.
+    struct FailingAdapter(DuplexAdapter);
+
+    #[async_trait]
+    impl NetworkAdapter for FailingAdapter {
+        async fn send(&mut self, msg: &str) -> Result<()> {
+            self.0.send(msg).await
+        }
+        async fn recv(&mut self) -> Result<Option<String>> {
+            match self.0.recv().await? {
+                Some(msg) => Ok(Some(msg)),
+                None => Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset).into()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transport_error_still_flushes_queued_notifications() {
+        let server = Server::new("transport-error-test").register_tool(
+            Tool {
+                name: "notify".to_string(),
+                ..Default::default()
+            },
+            |handle, _args| async move {
+                handle.notify_resources_list_changed().await?;
+                Ok(CallToolResult::text("done"))
+            },
+        );
+        let (mut client, server_end) = client_sending(vec![
+            make_init_request(),
+            make_initialized_notification(),
+            json!({
+                "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                "params": { "name": "notify", "arguments": {} }
+            })
+            .to_string(),
+        ])
+        .await;
+
+        let session = ServerSession::new(
+            ProtocolConnection::new(FailingAdapter(server_end)),
+            Arc::new(server),
+        );
+        tokio::time::timeout(std::time::Duration::from_secs(1), session.run())
+            .await
+            .expect("Session run timed out")
+            .expect("A transport error should end the session cleanly");
+
+        let outgoing = sent_to(&mut client).await;
+        assert!(outgoing
+            .iter()
+            .any(|msg| msg.contains("notifications/resources/list_changed")));
+    }
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_their_rpc_codes() {
+        let server = Server::new("error-code-test")
+            .register_tool(
+                Tool {
+                    name: "fail".to_string(),
+                    ..Default::default()
+                },
+                |_handle, args| async move {
+                    Err(match args["kind"].as_str() {
+                        Some("invalid") => Error::InvalidParams("count must be positive".into()),
+                        Some("missing") => Error::NotFound("record 7".into()),
+                        _ => Error::Other("disk on fire".into()),
+                    })
+                },
+            )
+            .on_read_resource(|_handle, uri| async move {
+                Err(Error::NotFound(format!("resource {}", uri)))
+            });
+
+        let call = |id: i64, kind: &str| {
+            json!({
+                "jsonrpc": "2.0", "id": id, "method": "tools/call",
+                "params": { "name": "fail", "arguments": { "kind": kind } }
+            })
+            .to_string()
+        };
+        let outgoing = run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call(1, "invalid"),
+                call(2, "missing"),
+                call(3, "other"),
+                json!({
+                    "jsonrpc": "2.0", "id": 4, "method": "resources/read",
+                    "params": { "uri": "file:///gone.txt" }
+                })
+                .to_string(),
+            ],
+        )
+        .await;
+
+        let responses: Vec<Value> = outgoing
+            .iter()
+            .skip(1)
+            .map(|raw| serde_json::from_str(raw).unwrap())
+            .collect();
+        let codes: Vec<(i64, i64)> = responses
+            .iter()
+            .map(|r| {
+                (
+                    r["id"].as_i64().unwrap(),
+                    r["error"]["code"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                (1, INVALID_PARAMS as i64),
+                (2, crate::types::NOT_FOUND as i64),
+                (3, INTERNAL_ERROR as i64),
+                (4, crate::types::NOT_FOUND as i64),
+            ]
+        );
+        assert_eq!(
+            responses[0]["error"]["message"],
+            "Invalid params: count must be positive"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_closes_silent_session() {
+        let server = Server::new("idle-test").idle_timeout(std::time::Duration::from_millis(50));
+        let (_client_end, server_end) = crate::network_adapter::duplex_pair();
+        let session = ServerSession::new(ProtocolConnection::new(server_end), Arc::new(server));
+
+        // The client end stays open but never sends anything.
+        tokio::time::timeout(std::time::Duration::from_secs(1), session.run())
+            .await
+            .expect("Idle session was not closed")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_resets_on_each_message() {
+        let server = Server::new("idle-test").idle_timeout(std::time::Duration::from_millis(80));
+        let (mut client_end, server_end) = crate::network_adapter::duplex_pair();
+        let session = ServerSession::new(ProtocolConnection::new(server_end), Arc::new(server));
+        let session_task = tokio::spawn(session.run());
+
+        client_end.send(&make_init_request()).await.unwrap();
+        client_end
+            .send(&make_initialized_notification())
+            .await
+            .unwrap();
+        // Active for well over the idle timeout in total.
+        for id in 1..=6 {
+            tokio::time::sleep(std::time::Duration::from_millis(40)).await;
+            let list = json!({ "jsonrpc": "2.0", "id": id, "method": "tools/list" });
+            client_end.send(&list.to_string()).await.unwrap();
+        }
+        assert!(!session_task.is_finished());
+
+        // Once the client goes quiet, the session ends.
+        tokio::time::timeout(std::time::Duration::from_secs(1), session_task)
+            .await
+            .expect("Idle session was not closed")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[test]
+    fn test_tool_input_validator_compiles_schema_on_first_check_only() {
+        let validator = ToolInputValidator::new(json!({ "type": "object" }));
+        assert!(validator.compiled.get().is_none());
+
+        validator.check("t", &json!({})).unwrap();
+        let compiled = validator.compiled.get().unwrap() as *const _;
+        assert!(validator.check("t", &json!(1)).is_err());
+        assert!(std::ptr::eq(compiled, validator.compiled.get().unwrap()));
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[tokio::test]
+    async fn test_validate_tool_input_rejects_arguments_not_matching_schema() {
+        let server = Server::new("validate-input-test")
+            .validate_tool_input(true)
+            .register_tool(
+                Tool::new(
+                    "fetch",
+                    Some("Fetches a URL"),
+                    json!({
+                        "type": "object",
+                        "properties": { "url": { "type": "string" } },
+                        "required": ["url"]
+                    }),
+                ),
+                |_handle, args| async move {
+                    Ok(crate::types::CallToolResult::text(format!(
+                        "fetched {}",
+                        args["url"].as_str().unwrap()
+                    )))
+                },
+            );
+
+        let call = |id: i64, arguments: Value| {
+            json!({
+                "jsonrpc": "2.0", "id": id, "method": "tools/call",
+                "params": { "name": "fetch", "arguments": arguments }
+            })
+            .to_string()
+        };
+        let outgoing = run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                call(1, json!({})),
+                call(2, json!({ "url": 42 })),
+                call(3, json!({ "url": "https://example.com" })),
+            ],
+        )
+        .await;
+
+        let responses: Vec<Value> = outgoing
+            .iter()
+            .skip(1)
+            .map(|raw| serde_json::from_str(raw).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 3);
+        for rejected in &responses[..2] {
+            assert_eq!(rejected["error"]["code"], INVALID_PARAMS);
+            assert!(rejected["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("Invalid arguments for tool 'fetch'"));
+        }
+        assert_eq!(
+            responses[2]["result"]["content"][0]["text"],
+            "fetched https://example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handlers_see_client_capabilities() {
+        let server = Server::new("client-capabilities-test").register_tool(
+            Tool {
+                name: "caps".to_string(),
+                ..Default::default()
+            },
+            |handle: ConnectionHandle, _args| async move {
+                let capabilities = handle.client_capabilities();
+                Ok(crate::types::CallToolResult::text(format!(
+                    "tools: {}, roots: {}",
+                    capabilities.tools.is_some(),
+                    capabilities.roots.is_some()
+                )))
+            },
+        );
+        let init_with_roots_only = json!({
+            "jsonrpc": "2.0", "id": 0, "method": "initialize",
+            "params": {
+                "protocolVersion": "test",
+                "clientInfo": { "name": "test", "version": "0" },
+                "capabilities": { "roots": { "listChanged": true } }
+            }
+        })
+        .to_string();
+        let outgoing = run_session_with_requests(
+            Arc::new(server),
+            vec![
+                init_with_roots_only,
+                make_initialized_notification(),
+                json!({
+                    "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                    "params": { "name": "caps", "arguments": {} }
+                })
+                .to_string(),
+            ],
+        )
+        .await;
+
+        let raw = outgoing[1].clone();
+        let response: Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(
+            response["result"]["content"][0]["text"],
+            "tools: false, roots: true"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notification_burst_larger_than_default_buffer_is_delivered() {
+        let server = Server::new("burst-test")
+            .notification_buffer(128)
+            .register_tool(
+                Tool {
+                    name: "burst".to_string(),
+                    ..Default::default()
+                },
+                |handle: ConnectionHandle, _args| async move {
+                    for i in 0..100 {
+                        handle
+                            .send_notification(Notification {
+                                jsonrpc: "2.0".to_string(),
+                                method: "test/burst".to_string(),
+                                params: Some(json!({ "i": i })),
+                            })
+                            .await?;
+                    }
+                    Ok(crate::types::CallToolResult::default())
+                },
+            );
+        let outgoing = run_session_with_requests(
+            Arc::new(server),
+            vec![
+                make_init_request(),
+                make_initialized_notification(),
+                json!({
+                    "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                    "params": { "name": "burst", "arguments": {} }
+                })
+                .to_string(),
+            ],
+        )
+        .await;
+
+        let responses = outgoing;
+        let burst: Vec<Value> = responses
+            .iter()
+            .map(|s| serde_json::from_str::<Value>(s).unwrap())
+            .filter(|msg| msg["method"] == "test/burst")
+            .collect();
+        assert_eq!(burst.len(), 100);
+        for (i, msg) in burst.iter().enumerate() {
+            assert_eq!(msg["params"]["i"], i);
+        }
+        assert!(responses.iter().any(|s| s.contains("\"id\":1")));
+    }
+
+    #[tokio::test]
+    async fn test_send_notification_times_out_on_a_full_queue() {
+        let (tx, _rx) = mpsc::channel::<String>(1);
+        let mut handle = ConnectionHandle::new(tx);
+        handle.send_timeout = Some(Duration::from_millis(20));
+        let notification = || Notification {
+            jsonrpc: "2.0".to_string(),
+            method: "test/full".to_string(),
+            params: Some(ListToolsChangedParams {}),
+        };
+
+        handle.send_notification(notification()).await.unwrap();
+        let err = handle.send_notification(notification()).await.unwrap_err();
+        assert!(matches!(err, Error::Timeout));
+    }
+
+    #[tokio::test]
+    async fn test_skip_initialize_answers_first_request() {
+        let server = Server::new("skip-init-test")
+            .register_tool(
+                Tool {
+                    name: "noop".to_string(),
+                    ..Default::default()
+                },
+                |_handle, _args| async { Ok(crate::types::CallToolResult::default()) },
+            )
+            .skip_initialize(true);
+        let outgoing = run_session_with_requests(
+            Arc::new(server),
+            vec![json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }).to_string()],
+        )
+        .await;
+
+        let responses = outgoing;
+        assert_eq!(responses.len(), 1);
+        let response: Value = serde_json::from_str(&responses[0]).unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["tools"][0]["name"], "noop");
+    }
 }
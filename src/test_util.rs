@@ -0,0 +1,62 @@
+//! Helpers for running a `Client` against a `Server` in the same process.
+
+use crate::{error::Result, network_adapter::duplex_pair, Client, Server};
+use tokio::task::JoinHandle;
+
+/// Starts a session for `server` on an in-memory transport and connects a
+/// `Client` to it.
+///
+/// No sockets are involved, which makes this a quick way to demonstrate or test a
+/// server. The returned handle resolves once the server session ends, which
+/// happens when the client is dropped.
+///
+/// # Errors
+///
+/// Returns an error if the initialization handshake fails.
+///
+/// # Example
+///
+/// ```
+/// use mcp_sdk::test_util::connected_pair;
+/// use mcp_sdk::types::{CallToolResult, Content, Tool};
+/// use mcp_sdk::Server;
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> mcp_sdk::Result<()> {
+/// let server = Server::new("greeter").register_tool(
+///     Tool {
+///         name: "greet".to_string(),
+///         ..Default::default()
+///     },
+///     |_handle, args| async move {
+///         Ok(CallToolResult {
+///             content: vec![Content::Text {
+///                 text: format!("Hello, {}!", args["name"].as_str().unwrap_or("world")),
+///                 annotations: None,
+///             }],
+///             ..Default::default()
+///         })
+///     },
+/// );
+///
+/// let (client, _server_handle) = connected_pair(server).await?;
+/// let result = client
+///     .call_tool("greet".to_string(), json!({ "name": "Ferris" }))
+///     .await?;
+/// assert_eq!(
+///     result.content,
+///     vec![Content::Text {
+///         text: "Hello, Ferris!".to_string(),
+///         annotations: None,
+///     }]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub async fn connected_pair(server: Server) -> Result<(Client, JoinHandle<Result<()>>)> {
+    let (client_adapter, server_adapter) = duplex_pair();
+    let server_handle = tokio::spawn(async move { server.handle_connection(server_adapter).await });
+    let client = Client::new(client_adapter).await?;
+    Ok((client, server_handle))
+}
@@ -0,0 +1,78 @@
+//! Builds a rustls server configuration from PEM-encoded certificates and keys.
+//!
+//! Only available with the `tls` feature. The resulting config can be handed to an
+//! acceptor such as `tokio_rustls::TlsAcceptor`, whose streams are then served with
+//! [`StdioAdapter::from_child`](crate::StdioAdapter::from_child) and
+//! [`Server::handle_connection`](crate::Server::handle_connection).
+
+use crate::error::{Error, Result};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The environment variable [`TlsConfig::from_env`] reads the certificate path from.
+pub const TLS_CERT_ENV: &str = "MCP_TLS_CERT";
+/// The environment variable [`TlsConfig::from_env`] reads the private key path from.
+pub const TLS_KEY_ENV: &str = "MCP_TLS_KEY";
+
+/// Helpers for building the `rustls::ServerConfig` of a TLS-secured server.
+pub struct TlsConfig;
+
+impl TlsConfig {
+    /// Loads a certificate chain and private key from PEM files.
+    ///
+    /// `cert` may hold the full chain, leaf first. `key` must hold a single PKCS#8,
+    /// PKCS#1 or SEC1 private key. Client authentication is not requested.
+    pub fn from_pem_files(
+        cert: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> Result<Arc<ServerConfig>> {
+        let cert_path = cert.as_ref();
+        let key_path = key.as_ref();
+
+        let cert_pem = std::fs::read(cert_path)?;
+        let certs = CertificateDer::pem_slice_iter(&cert_pem)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| {
+                Error::Other(format!(
+                    "invalid certificate PEM in {}: {}",
+                    cert_path.display(),
+                    e
+                ))
+            })?;
+        if certs.is_empty() {
+            return Err(Error::Other(format!(
+                "no certificates found in {}",
+                cert_path.display()
+            )));
+        }
+
+        let key_pem = std::fs::read(key_path)?;
+        let key = PrivateKeyDer::from_pem_slice(&key_pem).map_err(|e| {
+            Error::Other(format!(
+                "invalid private key PEM in {}: {}",
+                key_path.display(),
+                e
+            ))
+        })?;
+
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+                .map_err(|e| Error::Other(format!("invalid TLS configuration: {}", e)))?;
+        Ok(Arc::new(config))
+    }
+
+    /// Like [`from_pem_files`](Self::from_pem_files), with the paths taken from the
+    /// `MCP_TLS_CERT` and `MCP_TLS_KEY` environment variables.
+    pub fn from_env() -> Result<Arc<ServerConfig>> {
+        let var = |name: &str| {
+            std::env::var_os(name)
+                .ok_or_else(|| Error::Other(format!("environment variable {} is not set", name)))
+        };
+        Self::from_pem_files(var(TLS_CERT_ENV)?, var(TLS_KEY_ENV)?)
+    }
+}
@@ -5,8 +5,10 @@
 //! We use the `serde` library for robust and efficient JSON handling.
 
 use crate::ToolArgumentsDescriptor;
+use base64::prelude::{Engine, BASE64_STANDARD};
 use serde::{Deserialize, Serialize};
 use serde_json::Value; // Removed json here, as it's not used in this file anymore
+use std::path::Path;
 
 // --- Base MCP Message Trait ---
 /// A trait for all MCP messages that have a `method` field.
@@ -24,6 +26,9 @@ pub const LATEST_PROTOCOL_VERSION: &str = "2024-11-05";
 #[serde(rename_all = "camelCase")]
 pub struct Tool {
     pub name: String,
+    /// A human-readable name for display, distinct from the programmatic `name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub input_schema: Value,
@@ -35,6 +40,7 @@ impl Default for Tool {
     fn default() -> Self {
         Self {
             name: String::new(),
+            title: None,
             description: None,
             input_schema: Value::Null, // Changed from Value::Object(Default::default())
             annotations: None,
@@ -50,6 +56,7 @@ impl Tool {
     ) -> Self {
         Self {
             name: name.into(),
+            title: None,
             description: description.map(|s| s.into()),
             input_schema,
             annotations: None, // Defaulting annotations to None
@@ -109,15 +116,94 @@ impl Tool {
     ) -> Self {
         Self {
             name: name.into(),
+            title: None,
             description: description.map(|s| s.into()),
             input_schema: T::mcp_input_schema(),
             annotations: None, // Defaulting annotations to None
         }
     }
+    /// Sets the human-readable title shown to users instead of `name`.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Attaches `annotations`, replacing any already set.
+    pub fn with_annotations(mut self, annotations: ToolAnnotations) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+}
+
+/// Helpers for working with tool input schemas.
+pub struct ToolSchema;
+
+impl ToolSchema {
+    /// Combines two object schemas, e.g. a set of arguments shared by many tools
+    /// with the ones specific to a single tool.
+    ///
+    /// `properties` are merged recursively, with `extra` winning where both define
+    /// the same non-object property. `required` lists are concatenated without
+    /// duplicates. Any other keyword is taken from `base` when present there.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mcp_sdk::types::ToolSchema;
+    /// use serde_json::json;
+    ///
+    /// let common = json!({ "type": "object", "properties": { "verbose": { "type": "boolean" } } });
+    /// let extra = json!({ "type": "object", "properties": { "path": { "type": "string" } }, "required": ["path"] });
+    /// let merged = ToolSchema::merge(&common, &extra);
+    /// assert!(merged["properties"].get("verbose").is_some());
+    /// assert_eq!(merged["required"], json!(["path"]));
+    /// ```
+    pub fn merge(base: &Value, extra: &Value) -> Value {
+        let (Some(base_map), Some(extra_map)) = (base.as_object(), extra.as_object()) else {
+            return extra.clone();
+        };
+        let mut merged = base_map.clone();
+        for (key, extra_value) in extra_map {
+            let combined = match (key.as_str(), merged.get(key)) {
+                ("properties", Some(Value::Object(base_props))) => {
+                    let mut props = base_props.clone();
+                    if let Some(extra_props) = extra_value.as_object() {
+                        for (name, schema) in extra_props {
+                            let value = match props.get(name) {
+                                Some(existing) if is_object_schema(existing) => {
+                                    Self::merge(existing, schema)
+                                }
+                                _ => schema.clone(),
+                            };
+                            props.insert(name.clone(), value);
+                        }
+                    }
+                    Value::Object(props)
+                }
+                ("required", Some(Value::Array(base_required))) => {
+                    let mut required = base_required.clone();
+                    for name in extra_value.as_array().into_iter().flatten() {
+                        if !required.contains(name) {
+                            required.push(name.clone());
+                        }
+                    }
+                    Value::Array(required)
+                }
+                (_, Some(existing)) => existing.clone(),
+                (_, None) => extra_value.clone(),
+            };
+            merged.insert(key.clone(), combined);
+        }
+        Value::Object(merged)
+    }
+}
+
+fn is_object_schema(schema: &Value) -> bool {
+    schema.get("properties").is_some_and(Value::is_object)
 }
 
 /// A known resource that the server is capable of reading.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Resource {
     pub uri: String,
@@ -126,20 +212,138 @@ pub struct Resource {
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+}
+
+/// A template describing a family of resources via an RFC 6570 URI template,
+/// e.g. `file:///logs/{date}.log`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplate {
+    pub uri_template: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// The intended consumer of a piece of content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// Hints for clients about how to use or display a resource or content block.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Annotations {
+    /// Who the data is meant for; it may be meant for several audiences.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Vec<Role>>,
+    /// How important the data is, from 0.0 (least) to 1.0 (most).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f64>,
 }
 
 // --- NEW: Prompt-related types ---
 
 /// A prompt or prompt template that the server offers.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct Prompt {
     pub name: String,
+    /// A human-readable name for display, distinct from the programmatic `name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<Vec<PromptArgument>>,
 }
 
+impl Prompt {
+    /// Creates a prompt with no title, description or arguments.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the human-readable title shown to users instead of `name`.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Sets the prompt's description.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Creates a prompt whose `arguments` are derived from `T`, mirroring
+    /// [`Tool::from_args`].
+    ///
+    /// Each property of `T::mcp_input_schema()` becomes a `PromptArgument`, in
+    /// declaration order when `T` provides one, carrying the property's
+    /// `description` and whether it is listed as `required`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mcp_sdk::types::Prompt;
+    /// use mcp_sdk::ToolArguments;
+    ///
+    /// #[derive(ToolArguments)]
+    /// struct ReviewArgs {
+    ///     #[tool_arg(desc = "The code to review.")]
+    ///     code: String,
+    ///     focus: Option<String>,
+    /// }
+    ///
+    /// let prompt = Prompt::from_args::<ReviewArgs>("review", Some("Reviews code."));
+    /// let arguments = prompt.arguments.unwrap();
+    /// assert_eq!(arguments[0].name, "code");
+    /// assert_eq!(arguments[1].required, Some(false));
+    /// ```
+    pub fn from_args<T: ToolArgumentsDescriptor>(
+        name: impl Into<String>,
+        description: Option<impl Into<String>>,
+    ) -> Self {
+        let schema = T::mcp_input_schema();
+        let empty = serde_json::Map::new();
+        let properties = schema["properties"].as_object().unwrap_or(&empty);
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let order: Vec<&str> = match T::mcp_arg_order() {
+            [] => properties.keys().map(String::as_str).collect(),
+            order => order.to_vec(),
+        };
+        let arguments = order
+            .into_iter()
+            .map(|arg| PromptArgument {
+                name: arg.to_string(),
+                description: properties
+                    .get(arg)
+                    .and_then(|p| p["description"].as_str())
+                    .map(str::to_string),
+                required: Some(required.contains(&arg)),
+            })
+            .collect();
+        Self {
+            name: name.into(),
+            title: None,
+            description: description.map(|s| s.into()),
+            arguments: Some(arguments),
+        }
+    }
+}
+
 /// An argument for a prompt template.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PromptArgument {
@@ -161,6 +365,9 @@ pub struct PromptMessage {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ListToolsResult {
     pub tools: Vec<Tool>,
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
 }
 
 /// The server's response to a `tools/call` request.
@@ -170,6 +377,197 @@ pub struct CallToolResult {
     pub content: Vec<Content>,
     #[serde(default)]
     pub is_error: bool,
+    /// Machine-readable output, expected to conform to the tool's `outputSchema`.
+    #[serde(
+        rename = "structuredContent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub structured_content: Option<Value>,
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
+
+impl CallToolResult {
+    /// A successful result carrying a single text block.
+    pub fn text(msg: impl Into<String>) -> Self {
+        Self {
+            content: vec![Content::Text {
+                text: msg.into(),
+                annotations: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// A tool-level failure carrying a single text block explaining it. Unlike a
+    /// JSON-RPC error, this lets the model see what went wrong and try again.
+    pub fn error(msg: impl Into<String>) -> Self {
+        Self {
+            is_error: true,
+            ..Self::text(msg)
+        }
+    }
+
+    /// The first text block of an error result, or `None` when the call succeeded or
+    /// carried no text.
+    pub fn error_message(&self) -> Option<String> {
+        if !self.is_error {
+            return None;
+        }
+        self.content.iter().find_map(|content| match content {
+            Content::Text { text, .. } => Some(text.clone()),
+            _ => None,
+        })
+    }
+
+    /// Starts a [`CallToolResultBuilder`], for results with several content blocks.
+    pub fn builder() -> CallToolResultBuilder {
+        CallToolResultBuilder::default()
+    }
+
+    /// Attaches structured output to this result.
+    ///
+    /// The spec recommends also returning a text rendering in `content` for clients
+    /// that don't understand structured output.
+    pub fn with_structured(mut self, value: Value) -> Self {
+        self.structured_content = Some(value);
+        self
+    }
+}
+
+/// Builds a [`CallToolResult`] one content block at a time, keeping the blocks in
+/// the order they were added.
+///
+/// # Example
+///
+/// ```rust
+/// use mcp_sdk::types::CallToolResult;
+///
+/// # fn main() -> mcp_sdk::Result<()> {
+/// let result = CallToolResult::builder()
+///     .text("Here is the chart:")
+///     .image("iVBORw0KGgo=", "image/png")?
+///     .build();
+/// assert_eq!(result.content.len(), 2);
+/// assert!(!result.is_error);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CallToolResultBuilder {
+    result: CallToolResult,
+}
+
+impl CallToolResultBuilder {
+    /// Appends a text block.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.result.content.push(Content::Text {
+            text: text.into(),
+            annotations: None,
+        });
+        self
+    }
+
+    /// Appends an image block from base64-encoded `data`, failing like
+    /// [`Content::image`] when the data isn't valid base64.
+    pub fn image(
+        self,
+        data: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) -> crate::Result<Self> {
+        Ok(self.content(Content::image(data, mime_type)?))
+    }
+
+    /// Appends an already-built content block.
+    pub fn content(mut self, content: Content) -> Self {
+        self.result.content.push(content);
+        self
+    }
+
+    /// Appends an embedded resource block.
+    pub fn resource(mut self, resource: ResourceContents) -> Self {
+        self.result.content.push(Content::Resource {
+            resource,
+            annotations: None,
+        });
+        self
+    }
+
+    /// Marks the result as a tool-level error. Defaults to `false`.
+    pub fn error(mut self, is_error: bool) -> Self {
+        self.result.is_error = is_error;
+        self
+    }
+
+    /// Attaches structured output, see [`CallToolResult::with_structured`].
+    pub fn structured(mut self, value: Value) -> Self {
+        self.result.structured_content = Some(value);
+        self
+    }
+
+    /// Finishes the builder, returning the assembled result.
+    pub fn build(self) -> CallToolResult {
+        self.result
+    }
+}
+
+/// A filesystem location the client exposes to the server, e.g. a project directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    /// A `file://` URI identifying the root.
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// The client's response to a `roots/list` request.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ListRootsResult {
+    pub roots: Vec<Root>,
+}
+
+/// Parameters of an `elicitation/create` request, in which the server asks the
+/// user for structured input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElicitParams {
+    /// Shown to the user to explain what is being asked for.
+    pub message: String,
+    /// A JSON Schema describing the expected `content`.
+    pub requested_schema: Value,
+}
+
+/// How the user responded to an elicitation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElicitAction {
+    /// The user submitted the requested input.
+    Accept,
+    /// The user explicitly refused to provide it.
+    Decline,
+    /// The user dismissed the request without choosing.
+    Cancel,
+}
+
+/// The client's response to an `elicitation/create` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElicitResult {
+    pub action: ElicitAction,
+    /// The submitted input; only present when `action` is `Accept`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Value>,
+}
+
+/// The server's response to a `resources/templates/list` request.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListResourceTemplatesResult {
+    pub resource_templates: Vec<ResourceTemplate>,
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
 }
 
 /// The server's response to a `resources/read` request.
@@ -177,12 +575,111 @@ pub struct CallToolResult {
 #[serde(rename_all = "camelCase")]
 pub struct ReadResourceResult {
     pub contents: Vec<ResourceContents>,
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
+
+impl ReadResourceResult {
+    /// Reads the file at `path` and wraps it as the contents of `uri`.
+    ///
+    /// The mime type is inferred from the file extension. Textual files become
+    /// [`TextResourceContents`]; anything else is base64-encoded into
+    /// [`BlobResourceContents`]. Files with an unknown extension are treated as
+    /// `text/plain` if they are valid UTF-8 and `application/octet-stream` otherwise.
+    pub async fn from_path(
+        uri: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> crate::error::Result<ReadResourceResult> {
+        let path = path.as_ref();
+        let bytes = tokio::fs::read(path).await?;
+        let uri = uri.into();
+        let mime_type = mime_type_for_path(path);
+
+        let contents = match (mime_type, String::from_utf8(bytes)) {
+            (Some(mime), Ok(text)) if is_text_mime(mime) => {
+                ResourceContents::Text(TextResourceContents {
+                    uri,
+                    mime_type: Some(mime.to_string()),
+                    text,
+                })
+            }
+            (None, Ok(text)) => ResourceContents::Text(TextResourceContents {
+                uri,
+                mime_type: Some("text/plain".to_string()),
+                text,
+            }),
+            (mime, decoded) => {
+                let bytes = match decoded {
+                    Ok(text) => text.into_bytes(),
+                    Err(e) => e.into_bytes(),
+                };
+                ResourceContents::Blob(BlobResourceContents {
+                    uri,
+                    mime_type: Some(mime.unwrap_or("application/octet-stream").to_string()),
+                    blob: BASE64_STANDARD.encode(bytes),
+                })
+            }
+        };
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+            meta: None,
+        })
+    }
+}
+
+/// Maps common file extensions to their mime type.
+fn mime_type_for_path(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "rs" => "text/x-rust",
+        "py" => "text/x-python",
+        "sql" => "application/sql",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime,
+            "application/json"
+                | "application/xml"
+                | "application/yaml"
+                | "application/toml"
+                | "application/sql"
+        )
 }
 
 /// The server's response to a `prompts/list` request.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ListPromptsResult {
     pub prompts: Vec<Prompt>,
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
 }
 
 /// The server's response to a `prompts/get` request.
@@ -193,6 +690,21 @@ pub struct GetPromptResult {
     pub messages: Vec<PromptMessage>,
 }
 
+impl GetPromptResult {
+    /// Renders the text messages as a transcript, one `role: text` line per
+    /// message, in order. Images, audio and embedded resources are skipped.
+    pub fn render_text(&self) -> String {
+        self.messages
+            .iter()
+            .filter_map(|message| match &message.content {
+                Content::Text { text, .. } => Some(format!("{}: {}", message.role, text)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 // --- Content and Resource Types ---
 
 // serialization and deserialization, removing the need for separate structs.
@@ -202,17 +714,97 @@ pub struct GetPromptResult {
 pub enum Content {
     Text {
         text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
     },
     Image {
         data: String,
         #[serde(rename = "mimeType")]
         mime_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    /// Base64-encoded audio data, e.g. `audio/wav`.
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
     },
     Resource {
         resource: ResourceContents,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
     },
 }
 
+impl Content {
+    /// An image block from base64-encoded `data`, rejecting data that isn't valid
+    /// base64 so encoding bugs surface on the server rather than in the client.
+    pub fn image(data: impl Into<String>, mime_type: impl Into<String>) -> crate::Result<Self> {
+        let data = data.into();
+        decode_base64("image", &data)?;
+        Ok(Content::Image {
+            data,
+            mime_type: mime_type.into(),
+            annotations: None,
+        })
+    }
+
+    /// An embedded text resource, e.g. a file the tool read or generated.
+    pub fn text_resource(
+        uri: impl Into<String>,
+        mime_type: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        Content::Resource {
+            resource: ResourceContents::Text(TextResourceContents {
+                uri: uri.into(),
+                mime_type: Some(mime_type.into()),
+                text: text.into(),
+            }),
+            annotations: None,
+        }
+    }
+
+    /// An embedded binary resource from base64-encoded `blob`.
+    pub fn blob_resource(
+        uri: impl Into<String>,
+        mime_type: impl Into<String>,
+        blob: impl Into<String>,
+    ) -> Self {
+        Content::Resource {
+            resource: ResourceContents::Blob(BlobResourceContents {
+                uri: uri.into(),
+                mime_type: Some(mime_type.into()),
+                blob: blob.into(),
+            }),
+            annotations: None,
+        }
+    }
+
+    /// The raw bytes of an image or audio block.
+    ///
+    /// Fails with a message naming the block type when the data isn't valid base64,
+    /// and for text and resource blocks, which carry no base64 payload.
+    pub fn decoded_bytes(&self) -> crate::Result<Vec<u8>> {
+        match self {
+            Content::Image { data, .. } => decode_base64("image", data),
+            Content::Audio { data, .. } => decode_base64("audio", data),
+            Content::Text { .. } | Content::Resource { .. } => Err(crate::Error::Other(
+                "only image and audio content carries base64 data".to_string(),
+            )),
+        }
+    }
+}
+
+fn decode_base64(kind: &str, data: &str) -> crate::Result<Vec<u8>> {
+    BASE64_STANDARD
+        .decode(data)
+        .map_err(|e| crate::Error::Other(format!("invalid base64 in {} content: {}", kind, e)))
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ResourceContents {
@@ -251,6 +843,26 @@ pub struct ToolAnnotations {
     pub destructive_hint: Option<bool>,
 }
 
+impl ToolAnnotations {
+    /// Marks the tool as not modifying its environment.
+    pub fn read_only(mut self) -> Self {
+        self.read_only_hint = Some(true);
+        self
+    }
+
+    /// Marks the tool as possibly performing destructive updates.
+    pub fn destructive(mut self) -> Self {
+        self.destructive_hint = Some(true);
+        self
+    }
+
+    /// Sets a human-readable title for the tool.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
 // --- Foundational JSON-RPC Types ---
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -307,6 +919,19 @@ pub enum JSONRPCResponse<T> {
 pub const METHOD_NOT_FOUND: i32 = -32601;
 pub const INVALID_PARAMS: i32 = -32602;
 pub const INTERNAL_ERROR: i32 = -32603;
+/// Server-defined error code used when a tool guard rejects a `tools/call`.
+pub const TOOL_CALL_DENIED: i32 = -32001;
+/// Returned for requests sent after `initialize` but before `notifications/initialized`.
+pub const SERVER_NOT_INITIALIZED: i32 = -32002;
+/// Sent, with a null id, to a connection refused because the server is at its
+/// connection limit.
+pub const CONNECTION_LIMIT_REACHED: i32 = -32003;
+/// Returned for requests received after the server began shutting down.
+pub const SERVER_SHUTTING_DOWN: i32 = -32004;
+/// Returned when a request outlives the server's request timeout.
+pub const REQUEST_TIMED_OUT: i32 = -32005;
+/// Returned when a handler reports that what the request refers to doesn't exist.
+pub const NOT_FOUND: i32 = -32006;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ErrorResponse {
@@ -319,6 +944,9 @@ pub struct ErrorResponse {
 pub struct ErrorData {
     pub code: i32,
     pub message: String,
+    /// Structured detail about the error, e.g. which argument failed validation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
 }
 
 // --- Initialization Handshake Types ---
@@ -338,6 +966,9 @@ pub struct InitializeResult {
     pub capabilities: ServerCapabilities,
     #[serde(rename = "serverInfo")]
     pub server_info: Implementation,
+    /// Hints for the client (or its model) on how to use this server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
@@ -345,6 +976,8 @@ pub struct InitializeResult {
 pub struct ClientCapabilities {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<ToolsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roots: Option<RootsCapability>,
 }
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
@@ -352,8 +985,37 @@ pub struct ClientCapabilities {
 pub struct ServerCapabilities {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<ToolsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourcesCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompts: Option<PromptsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging: Option<LoggingCapability>,
+}
+
+/// Advertises that the server offers resources.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesCapability {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscribe: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
+}
+
+/// Advertises that the server offers prompt templates.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptsCapability {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
 }
 
+/// Advertises that the server can send log messages. It carries no options and
+/// serializes to `{}`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LoggingCapability {}
+
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolsCapability {
@@ -361,38 +1023,115 @@ pub struct ToolsCapability {
     pub list_changed: Option<bool>,
 }
 
+/// Advertises that the client can answer `roots/list` requests.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootsCapability {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Implementation {
     pub name: String,
     pub version: String,
 }
 
+#[cfg(feature = "semver")]
+impl Implementation {
+    /// Parses `version` as a semantic version.
+    ///
+    /// Parsing is lenient about the forms peers commonly report: a leading `v` is
+    /// ignored and missing minor/patch components are treated as zero, so `"v1.2"`
+    /// parses as `1.2.0`.
+    pub fn semver(&self) -> crate::Result<semver::Version> {
+        parse_lenient_version(&self.version)
+    }
+
+    /// Returns `true` if the peer's version is greater than or equal to `minimum`.
+    ///
+    /// Returns `false` if either version cannot be parsed.
+    pub fn version_at_least(&self, minimum: &str) -> bool {
+        match (self.semver(), parse_lenient_version(minimum)) {
+            (Ok(version), Ok(minimum)) => version >= minimum,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "semver")]
+fn parse_lenient_version(raw: &str) -> crate::Result<semver::Version> {
+    let trimmed = raw.trim();
+    let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+    if let Ok(version) = semver::Version::parse(trimmed) {
+        return Ok(version);
+    }
+    // Pad "1" or "1.2" out to a full "major.minor.patch" triple.
+    let (core, rest) = match trimmed.find(['-', '+']) {
+        Some(idx) => trimmed.split_at(idx),
+        None => (trimmed, ""),
+    };
+    let mut padded = core.to_string();
+    for _ in core.split('.').count()..3 {
+        padded.push_str(".0");
+    }
+    padded.push_str(rest);
+    semver::Version::parse(&padded)
+        .map_err(|e| crate::Error::Other(format!("Invalid version '{}': {}", raw, e)))
+}
+
 // --- Method-Specific Parameter Types ---
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ListToolsParams {}
+pub struct ListToolsParams {
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CallToolParams {
     pub name: String,
     pub arguments: Value,
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ListResourcesParams {}
+pub struct ListResourcesParams {
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListResourceTemplatesParams {
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReadResourceParams {
     pub uri: String,
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ListPromptsParams {}
+pub struct ListPromptsParams {
+    /// Implementation-specific metadata, sent as `_meta`.
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -402,11 +1141,104 @@ pub struct GetPromptParams {
     pub arguments: Option<Value>,
 }
 
+/// Parameters for the `completion/complete` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompleteParams {
+    #[serde(rename = "ref")]
+    pub ref_: CompletionReference,
+    pub argument: CompletionArgument,
+}
+
+/// Identifies what a `completion/complete` request is completing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CompletionReference {
+    /// An argument of the prompt with the given name.
+    #[serde(rename = "ref/prompt")]
+    Prompt { name: String },
+    /// A variable of a resource URI template.
+    #[serde(rename = "ref/resource")]
+    Resource { uri: String },
+}
+
+/// The argument being completed and its current, partial value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletionArgument {
+    pub name: String,
+    pub value: String,
+}
+
+/// The server's response to a `completion/complete` request.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CompleteResult {
+    pub completion: Completion,
+}
+
+/// Candidate values for a completion request.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Completion {
+    /// At most 100 candidate values.
+    pub values: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_more: Option<bool>,
+}
+
 /// Parameters for the `tools/listChanged` notification. Currently empty.
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListToolsChangedParams {}
 
+/// Parameters for the `resources/listChanged` notification. Currently empty.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListResourcesChangedParams {}
+
+/// Parameters for the `prompts/listChanged` notification. Currently empty.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPromptsChangedParams {}
+
+/// Parameters for the `notifications/progress` notification, reporting how far a
+/// long-running request has come.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressNotificationParams {
+    /// The token the requester supplied in its request's `_meta.progressToken`.
+    pub progress_token: RequestId,
+    /// Progress so far. Should increase with every notification.
+    pub progress: f64,
+    /// The value `progress` reaches when done, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Parameters for the `notifications/tools/partial_result` notification, carrying
+/// one chunk of a tool's output ahead of its final `CallToolResult`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialResultParams {
+    /// The id of the `tools/call` request the chunk belongs to.
+    pub request_id: RequestId,
+    /// The chunk itself.
+    pub content: Content,
+}
+
+/// Parameters for the `notifications/close` notification, telling the peer why
+/// the connection is about to be closed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloseParams {
+    /// A machine-readable reason, e.g. one of the JSON-RPC error codes.
+    pub code: i32,
+    /// A human-readable explanation.
+    pub reason: String,
+}
+
 // --- Unit Tests ---
 #[cfg(test)]
 mod tests {
@@ -425,6 +1257,7 @@ mod tests {
             params: Some(CallToolParams {
                 name: "test_tool".to_string(),
                 arguments: json!({}),
+                meta: None,
             }),
         };
         assert_eq!(request.method(), "test/method");
@@ -508,6 +1341,7 @@ mod tests {
     fn test_tool_roundtrip() {
         let tool = Tool {
             name: "fetch".to_string(),
+            title: None,
             description: Some("Fetches a website".to_string()),
             input_schema: json!({
                 "type": "object",
@@ -523,10 +1357,114 @@ mod tests {
         assert_eq!(tool, deserialized);
     }
 
+    #[test]
+    fn test_tool_with_annotations() {
+        let tool = Tool::new("list_files", Some("Lists files"), json!({"type": "object"}))
+            .with_annotations(ToolAnnotations::default().read_only().title("List files"));
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(
+            json["annotations"],
+            json!({ "title": "List files", "readOnlyHint": true })
+        );
+
+        let annotations = ToolAnnotations::default().destructive();
+        assert_eq!(annotations.destructive_hint, Some(true));
+        assert_eq!(annotations.read_only_hint, None);
+    }
+
+    #[test]
+    fn test_call_tool_result_structured_content_roundtrip() {
+        let result = CallToolResult {
+            content: vec![Content::Text {
+                text: "22.5".to_string(),
+                annotations: None,
+            }],
+            ..Default::default()
+        }
+        .with_structured(json!({ "temperature": 22.5 }));
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(value["structuredContent"], json!({ "temperature": 22.5 }));
+        assert!(value.get("structured_content").is_none());
+        let deserialized: CallToolResult = serde_json::from_value(value).unwrap();
+        assert_eq!(result, deserialized);
+
+        let plain = serde_json::to_value(CallToolResult::default()).unwrap();
+        assert!(plain.get("structuredContent").is_none());
+        let deserialized: CallToolResult = serde_json::from_value(plain).unwrap();
+        assert_eq!(deserialized.structured_content, None);
+    }
+
+    #[test]
+    fn test_meta_roundtrip() {
+        let params = CallToolParams {
+            name: "search".to_string(),
+            arguments: json!({}),
+            meta: Some(json!({ "progressToken": 7 })),
+        };
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value["_meta"], json!({ "progressToken": 7 }));
+        assert!(value.get("meta").is_none());
+        assert_eq!(
+            serde_json::from_value::<CallToolParams>(value).unwrap(),
+            params
+        );
+
+        let result = CallToolResult {
+            meta: Some(json!({ "traceId": "abc" })),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(value["_meta"], json!({ "traceId": "abc" }));
+        assert_eq!(
+            serde_json::from_value::<CallToolResult>(value).unwrap(),
+            result
+        );
+
+        let list: ListToolsParams = serde_json::from_value(json!({ "_meta": {} })).unwrap();
+        assert_eq!(list.meta, Some(json!({})));
+        let read = ReadResourceResult {
+            contents: vec![],
+            meta: None,
+        };
+        let value = serde_json::to_value(&read).unwrap();
+        assert!(value.get("_meta").is_none());
+        assert_eq!(
+            serde_json::from_value::<ReadResourceResult>(value).unwrap(),
+            read
+        );
+    }
+
+    #[test]
+    fn test_initialize_result_instructions() {
+        let result = InitializeResult {
+            protocol_version: LATEST_PROTOCOL_VERSION.to_string(),
+            capabilities: ServerCapabilities::default(),
+            server_info: Implementation {
+                name: "server".to_string(),
+                version: "1.2.3".to_string(),
+            },
+            instructions: Some("Call `search` before `fetch`.".to_string()),
+        };
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(value["instructions"], "Call `search` before `fetch`.");
+        let deserialized: InitializeResult = serde_json::from_value(value).unwrap();
+        assert_eq!(result, deserialized);
+
+        let without = InitializeResult {
+            instructions: None,
+            ..result
+        };
+        let value = serde_json::to_value(&without).unwrap();
+        assert!(value.get("instructions").is_none());
+        let deserialized: InitializeResult = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.instructions, None);
+    }
+
     #[test]
     fn test_prompt_roundtrip() {
         let prompt = Prompt {
             name: "test-prompt".to_string(),
+            title: None,
             description: Some("A test prompt".to_string()),
             arguments: Some(vec![PromptArgument {
                 name: "arg1".to_string(),
@@ -539,6 +1477,66 @@ mod tests {
         assert_eq!(prompt, deserialized);
     }
 
+    #[test]
+    fn test_title_serializes_only_when_set() {
+        let tool = Tool::new("get_weather", None::<String>, json!({"type": "object"}));
+        let json = serde_json::to_value(&tool).unwrap();
+        assert!(json.get("title").is_none());
+        let titled = tool.title("Get weather");
+        let json = serde_json::to_value(&titled).unwrap();
+        assert_eq!(json["title"], "Get weather");
+        assert_eq!(serde_json::from_value::<Tool>(json).unwrap(), titled);
+
+        let prompt = Prompt::new("summarize");
+        let json = serde_json::to_value(&prompt).unwrap();
+        assert_eq!(json, json!({ "name": "summarize" }));
+        let titled = prompt
+            .title("Summarize text")
+            .description("Summarizes text");
+        let json = serde_json::to_value(&titled).unwrap();
+        assert_eq!(json["title"], "Summarize text");
+        assert_eq!(serde_json::from_value::<Prompt>(json).unwrap(), titled);
+    }
+
+    #[test]
+    fn test_prompt_from_args() {
+        struct SummarizeArgs;
+        impl ToolArgumentsDescriptor for SummarizeArgs {
+            fn mcp_input_schema() -> Value {
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "text": { "type": "string", "description": "The text to summarize" },
+                        "length": { "type": "integer" }
+                    },
+                    "required": ["text"]
+                })
+            }
+            fn mcp_arg_order() -> &'static [&'static str] {
+                &["text", "length"]
+            }
+        }
+
+        let prompt = Prompt::from_args::<SummarizeArgs>("summarize", Some("Summarizes text"));
+        assert_eq!(prompt.name, "summarize");
+        assert_eq!(prompt.description.as_deref(), Some("Summarizes text"));
+        assert_eq!(
+            prompt.arguments.unwrap(),
+            vec![
+                PromptArgument {
+                    name: "text".to_string(),
+                    description: Some("The text to summarize".to_string()),
+                    required: Some(true),
+                },
+                PromptArgument {
+                    name: "length".to_string(),
+                    description: None,
+                    required: Some(false),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_get_prompt_result_roundtrip() {
         let result = GetPromptResult {
@@ -548,6 +1546,7 @@ mod tests {
                     role: "user".to_string(),
                     content: Content::Text {
                         text: "Hello".to_string(),
+                        annotations: None,
                     },
                 },
                 PromptMessage {
@@ -555,6 +1554,7 @@ mod tests {
                     content: Content::Image {
                         data: "base64data".to_string(),
                         mime_type: "image/png".to_string(),
+                        annotations: None,
                     },
                 },
             ],
@@ -571,6 +1571,77 @@ mod tests {
         assert_eq!(value["messages"][1]["content"]["mimeType"], "image/png");
     }
 
+    #[test]
+    fn test_audio_content_roundtrip() {
+        let audio = Content::Audio {
+            data: "UklGRg==".to_string(),
+            mime_type: "audio/wav".to_string(),
+            annotations: None,
+        };
+        let value = serde_json::to_value(&audio).unwrap();
+        assert_eq!(
+            value,
+            json!({ "type": "audio", "data": "UklGRg==", "mimeType": "audio/wav" })
+        );
+        let deserialized: Content = serde_json::from_value(value).unwrap();
+        assert_eq!(audio, deserialized);
+
+        // The other variants keep their existing tags.
+        let text: Content =
+            serde_json::from_value(json!({ "type": "text", "text": "hi" })).unwrap();
+        assert_eq!(
+            text,
+            Content::Text {
+                text: "hi".to_string(),
+                annotations: None,
+            }
+        );
+        let image: Content = serde_json::from_value(
+            json!({ "type": "image", "data": "x", "mimeType": "image/png" }),
+        )
+        .unwrap();
+        assert!(matches!(image, Content::Image { .. }));
+    }
+
+    #[test]
+    fn test_resource_annotations_roundtrip() {
+        let resource = Resource {
+            uri: "file:///notes.md".to_string(),
+            name: "notes".to_string(),
+            annotations: Some(Annotations {
+                audience: None,
+                priority: Some(0.5),
+            }),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&resource).unwrap();
+        assert_eq!(value["annotations"], json!({ "priority": 0.5 }));
+        let deserialized: Resource = serde_json::from_value(value).unwrap();
+        assert_eq!(resource, deserialized);
+
+        // Resources without annotations serialize without the key.
+        let plain = serde_json::to_value(Resource::default()).unwrap();
+        assert!(plain.get("annotations").is_none());
+    }
+
+    #[test]
+    fn test_content_annotations_roundtrip() {
+        let content = Content::Text {
+            text: "for the model".to_string(),
+            annotations: Some(Annotations {
+                audience: Some(vec![Role::Assistant, Role::User]),
+                priority: Some(1.0),
+            }),
+        };
+        let value = serde_json::to_value(&content).unwrap();
+        assert_eq!(
+            value["annotations"],
+            json!({ "audience": ["assistant", "user"], "priority": 1.0 })
+        );
+        let deserialized: Content = serde_json::from_value(value).unwrap();
+        assert_eq!(content, deserialized);
+    }
+
     #[test]
     fn test_resource_roundtrip() {
         let resource = Resource {
@@ -578,6 +1649,7 @@ mod tests {
             uri: "file:///path/to/file.txt".to_string(),
             description: Some("A test file".to_string()),
             mime_type: Some("text/plain".to_string()),
+            annotations: None,
         };
         let json_string = serde_json::to_string(&resource).unwrap();
         let deserialized: Resource = serde_json::from_str(&json_string).unwrap();
@@ -599,6 +1671,7 @@ mod tests {
                     blob: "base64data".to_string(),
                 }),
             ],
+            meta: None,
         };
         let json_string = serde_json::to_string(&result).unwrap();
         let deserialized: ReadResourceResult = serde_json::from_str(&json_string).unwrap();
@@ -618,6 +1691,45 @@ mod tests {
         assert_eq!(notif.method, "notifications/tools/list_changed");
     }
 
+    #[cfg(feature = "semver")]
+    #[test]
+    fn test_implementation_version_at_least() {
+        let peer = |version: &str| Implementation {
+            name: "peer".to_string(),
+            version: version.to_string(),
+        };
+
+        assert!(peer("1.2.0").version_at_least("1.2.0"));
+        assert!(peer("1.10.0").version_at_least("1.9.3"));
+        assert!(peer("v2.0").version_at_least("1.99.99"));
+        assert!(peer("1.2").version_at_least("1.2.0"));
+        assert!(!peer("1.2.0-beta.1").version_at_least("1.2.0"));
+        assert!(!peer("0.9.9").version_at_least("1.0.0"));
+        assert!(!peer("not-a-version").version_at_least("0.0.1"));
+
+        assert_eq!(
+            peer("v3.1").semver().unwrap(),
+            semver::Version::new(3, 1, 0)
+        );
+    }
+
+    #[test]
+    fn test_complete_params_prompt_ref() {
+        let params_json = json!({
+            "ref": { "type": "ref/prompt", "name": "greet" },
+            "argument": { "name": "language", "value": "En" }
+        });
+        let params: CompleteParams = serde_json::from_value(params_json.clone()).unwrap();
+        assert_eq!(
+            params.ref_,
+            CompletionReference::Prompt {
+                name: "greet".to_string()
+            }
+        );
+        assert_eq!(params.argument.value, "En");
+        assert_eq!(serde_json::to_value(&params).unwrap(), params_json);
+    }
+
     #[test]
     fn test_jsonrpc_response_success() {
         let success_json = r#"
@@ -656,7 +1768,318 @@ mod tests {
                 assert_eq!(e.id, RequestId::Num(2));
                 assert_eq!(e.error.code, -32601);
                 assert_eq!(e.error.message, "Method not found");
+                assert_eq!(e.error.data, None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_error_data_round_trips_optional_data() {
+        let without = ErrorData {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        };
+        let json = serde_json::to_value(&without).unwrap();
+        assert_eq!(json, json!({ "code": -32602, "message": "Invalid params" }));
+        assert_eq!(serde_json::from_value::<ErrorData>(json).unwrap(), without);
+
+        let with = ErrorData {
+            data: Some(json!({ "field": "count", "expected": "integer" })),
+            ..without
+        };
+        let json = serde_json::to_value(&with).unwrap();
+        assert_eq!(json["data"]["field"], "count");
+        assert_eq!(serde_json::from_value::<ErrorData>(json).unwrap(), with);
+    }
+
+    #[test]
+    fn test_tool_schema_merge_combines_properties_and_required() {
+        let base = json!({
+            "type": "object",
+            "properties": {
+                "verbose": { "type": "boolean" },
+                "target": { "type": "object", "properties": { "host": { "type": "string" } } }
+            },
+            "required": ["verbose", "target"]
+        });
+        let extra = json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "target": { "type": "object", "properties": { "port": { "type": "integer" } } }
+            },
+            "required": ["path", "verbose"]
+        });
+
+        let merged = ToolSchema::merge(&base, &extra);
+
+        assert_eq!(merged["type"], "object");
+        let properties = merged["properties"].as_object().unwrap();
+        assert_eq!(properties.len(), 3);
+        assert_eq!(properties["path"], json!({ "type": "string" }));
+        assert!(properties["target"]["properties"].get("host").is_some());
+        assert!(properties["target"]["properties"].get("port").is_some());
+        assert_eq!(merged["required"], json!(["verbose", "target", "path"]));
+    }
+
+    #[test]
+    fn test_call_tool_result_text_and_error_constructors() {
+        let ok = CallToolResult::text("done");
+        assert!(!ok.is_error);
+        assert_eq!(
+            ok.content,
+            vec![Content::Text {
+                text: "done".to_string(),
+                annotations: None
+            }]
+        );
+        assert_eq!(ok.structured_content, None);
+
+        let err = CallToolResult::error(String::from("bad input"));
+        assert!(err.is_error);
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({ "content": [{ "type": "text", "text": "bad input" }], "isError": true })
+        );
+    }
+
+    #[test]
+    fn test_call_tool_result_builder_keeps_content_order() {
+        let result = CallToolResult::builder()
+            .text("first")
+            .image("aGVsbG8=", "image/png")
+            .unwrap()
+            .text("second")
+            .build();
+
+        assert!(!result.is_error);
+        assert_eq!(result.structured_content, None);
+        assert_eq!(
+            result.content,
+            vec![
+                Content::Text {
+                    text: "first".to_string(),
+                    annotations: None
+                },
+                Content::Image {
+                    data: "aGVsbG8=".to_string(),
+                    mime_type: "image/png".to_string(),
+                    annotations: None
+                },
+                Content::Text {
+                    text: "second".to_string(),
+                    annotations: None
+                },
+            ]
+        );
+
+        let failed = CallToolResult::builder()
+            .resource(ResourceContents::Text(TextResourceContents {
+                uri: "file:///log.txt".to_string(),
+                mime_type: None,
+                text: "trace".to_string(),
+            }))
+            .structured(json!({ "code": 3 }))
+            .error(true)
+            .build();
+        assert!(failed.is_error);
+        assert!(matches!(failed.content[0], Content::Resource { .. }));
+        assert_eq!(failed.structured_content, Some(json!({ "code": 3 })));
+
+        assert!(CallToolResult::builder()
+            .image("not base64!", "image/png")
+            .is_err());
+    }
+
+    #[test]
+    fn test_error_message_takes_first_text_of_error_results() {
+        let failed = CallToolResult::builder()
+            .resource(ResourceContents::Text(TextResourceContents {
+                uri: "file:///query.sql".to_string(),
+                mime_type: Some("application/sql".to_string()),
+                text: "SELECT 1".to_string(),
+            }))
+            .text("syntax error near 'SELEC'")
+            .text("hint: check the query")
+            .error(true)
+            .build();
+        assert_eq!(
+            failed.error_message().as_deref(),
+            Some("syntax error near 'SELEC'")
+        );
+
+        assert_eq!(CallToolResult::text("fine").error_message(), None);
+        let no_text = CallToolResult::builder()
+            .image("aGVsbG8=", "image/png")
+            .unwrap()
+            .error(true)
+            .build();
+        assert_eq!(no_text.error_message(), None);
+    }
+
+    #[test]
+    fn test_render_text_keeps_text_messages_in_order() {
+        let message = |role: &str, content: Content| PromptMessage {
+            role: role.to_string(),
+            content,
+        };
+        let text = |text: &str| Content::Text {
+            text: text.to_string(),
+            annotations: None,
+        };
+        let result = GetPromptResult {
+            description: None,
+            messages: vec![
+                message("user", text("Review this diff")),
+                message(
+                    "user",
+                    Content::Image {
+                        data: "aGVsbG8=".to_string(),
+                        mime_type: "image/png".to_string(),
+                        annotations: None,
+                    },
+                ),
+                message("assistant", text("Looks good")),
+                message(
+                    "assistant",
+                    Content::Resource {
+                        resource: ResourceContents::Text(TextResourceContents {
+                            uri: "file:///diff.patch".to_string(),
+                            mime_type: None,
+                            text: "+ added".to_string(),
+                        }),
+                        annotations: None,
+                    },
+                ),
+                message("user", text("Thanks")),
+            ],
+        };
+        assert_eq!(
+            result.render_text(),
+            "user: Review this diff\nassistant: Looks good\nuser: Thanks"
+        );
+
+        let empty = GetPromptResult {
+            description: None,
+            messages: vec![],
+        };
+        assert_eq!(empty.render_text(), "");
+    }
+
+    #[tokio::test]
+    async fn test_read_resource_result_from_text_and_binary_files() {
+        let dir = std::env::temp_dir().join(format!("mcp-from-path-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let text_path = dir.join("notes.md");
+        let binary_path = dir.join("pixel.png");
+        std::fs::write(&text_path, "# Notes\n").unwrap();
+        std::fs::write(&binary_path, [0x89, b'P', b'N', b'G', 0xff, 0x00]).unwrap();
+
+        let text = ReadResourceResult::from_path("file:///notes.md", &text_path)
+            .await
+            .unwrap();
+        let binary = ReadResourceResult::from_path("file:///pixel.png", &binary_path)
+            .await
+            .unwrap();
+        let missing = ReadResourceResult::from_path("file:///missing", dir.join("missing")).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            text.contents,
+            vec![ResourceContents::Text(TextResourceContents {
+                uri: "file:///notes.md".to_string(),
+                mime_type: Some("text/markdown".to_string()),
+                text: "# Notes\n".to_string(),
+            })]
+        );
+        assert_eq!(
+            binary.contents,
+            vec![ResourceContents::Blob(BlobResourceContents {
+                uri: "file:///pixel.png".to_string(),
+                mime_type: Some("image/png".to_string()),
+                blob: "iVBOR/8A".to_string(),
+            })]
+        );
+        assert!(missing.is_err());
+    }
+    #[test]
+    fn test_image_content_base64_validation() {
+        let image = Content::image("aGVsbG8=", "image/png").unwrap();
+        assert_eq!(image.decoded_bytes().unwrap(), b"hello");
+        let result = CallToolResult::builder().content(image.clone()).build();
+        assert_eq!(result.content, vec![image]);
+
+        let err = Content::image("not base64!", "image/png").unwrap_err();
+        assert!(
+            err.to_string().contains("invalid base64 in image content"),
+            "got {}",
+            err
+        );
+
+        // Blocks built by hand or received off the wire are checked on access.
+        let malformed = Content::Image {
+            data: "base64data".to_string(),
+            mime_type: "image/png".to_string(),
+            annotations: None,
+        };
+        let err = malformed.decoded_bytes().unwrap_err();
+        assert!(err.to_string().contains("invalid base64 in image content"));
+
+        let text = Content::Text {
+            text: "hi".to_string(),
+            annotations: None,
+        };
+        assert!(text.decoded_bytes().is_err());
+    }
+    #[test]
+    fn test_embedded_resource_helpers_roundtrip() {
+        let text = Content::text_resource("file:///notes.md", "text/markdown", "# Notes");
+        let value = serde_json::to_value(&text).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "type": "resource",
+                "resource": { "uri": "file:///notes.md", "mimeType": "text/markdown", "text": "# Notes" }
+            })
+        );
+        assert_eq!(serde_json::from_value::<Content>(value).unwrap(), text);
+
+        let blob = Content::blob_resource("file:///logo.png", "image/png", "aGVsbG8=");
+        let value = serde_json::to_value(&blob).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "type": "resource",
+                "resource": { "uri": "file:///logo.png", "mimeType": "image/png", "blob": "aGVsbG8=" }
+            })
+        );
+        assert_eq!(serde_json::from_value::<Content>(value).unwrap(), blob);
+    }
+
+    #[test]
+    fn test_call_tool_result_with_embedded_text_resource() {
+        let result = CallToolResult::builder()
+            .text("Generated the report:")
+            .content(Content::text_resource(
+                "file:///report.csv",
+                "text/csv",
+                "a,b\n1,2",
+            ))
+            .build();
+
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(value["content"][1]["type"], "resource");
+        assert_eq!(value["content"][1]["resource"]["text"], "a,b\n1,2");
+        match &result.content[1] {
+            Content::Resource {
+                resource: ResourceContents::Text(contents),
+                ..
+            } => {
+                assert_eq!(contents.uri, "file:///report.csv");
+                assert_eq!(contents.mime_type.as_deref(), Some("text/csv"));
             }
+            other => panic!("expected an embedded text resource, got {:?}", other),
         }
     }
 }
@@ -4,10 +4,17 @@
 //! public API to run a client and server to ensure they can communicate correctly.
 
 // UPDATED: Use our custom Result type and Error enum.
+use mcp_sdk::types::{
+    ErrorData, PartialResultParams, CONNECTION_LIMIT_REACHED, INVALID_PARAMS, SERVER_SHUTTING_DOWN,
+};
 use mcp_sdk::{
-    error::Result, CallToolResult, Client, ConnectionHandle, Content, GetPromptResult,
-    ListPromptsResult, NdjsonAdapter, Prompt, PromptMessage, ReadResourceResult, Resource,
-    ResourceContents, Server, TextResourceContents, Tool,
+    error::{Error, Result},
+    network_adapter::{duplex_pair, DuplexAdapter, NetworkAdapter},
+    test_util::connected_pair,
+    CallToolResult, Client, CloseParams, ConnectionHandle, Content, ElicitAction, ElicitParams,
+    ElicitResult, GetPromptResult, ListPromptsResult, ListToolsChangedParams, NdjsonAdapter,
+    Prompt, PromptMessage, ReadResourceResult, Resource, ResourceContents, ResourceTemplate, Root,
+    Server, TextResourceContents, Tool,
 };
 use serde_json::{json, Value};
 use std::time::Duration;
@@ -19,6 +26,7 @@ async fn mock_list_resources_handler(_handle: ConnectionHandle) -> Result<Vec<Re
         name: "file.txt".to_string(),
         description: Some("An end-to-end test resource".to_string()),
         mime_type: Some("text/plain".to_string()),
+        annotations: None,
     }])
 }
 
@@ -38,6 +46,7 @@ async fn mock_read_resource_handler(
             mime_type: Some("text/plain".to_string()),
             text: "Hello, Resource!".to_string(),
         })],
+        meta: None,
     })
 }
 
@@ -45,9 +54,11 @@ async fn mock_list_prompts_handler(_handle: ConnectionHandle) -> Result<ListProm
     Ok(ListPromptsResult {
         prompts: vec![Prompt {
             name: "e2e-prompt".to_string(),
+            title: None,
             description: Some("An end-to-end test prompt.".to_string()),
             arguments: None,
         }],
+        meta: None,
     })
 }
 
@@ -68,6 +79,7 @@ async fn mock_get_prompt_handler(
             role: "user".to_string(),
             content: Content::Text {
                 text: "This is the prompt content.".to_string(),
+                annotations: None,
             },
         }],
     })
@@ -99,8 +111,58 @@ async fn setup_test_server(server: Server) -> (String, JoinHandle<()>) {
     (server_addr, server_handle)
 }
 
+/// Wires a client and a server together in-process through a duplex adapter pair.
+async fn setup_duplex_client(server: Server) -> (Client, JoinHandle<Result<()>>) {
+    connected_pair(server).await.unwrap()
+}
+
 // --- The Tests ---
 
+#[tokio::test]
+async fn test_client_server_over_duplex() {
+    let test_body = async {
+        let server = Server::new("mcp-duplex-test").register_tool(
+            Tool {
+                name: "duplex-tool".to_string(),
+                ..Default::default()
+            },
+            |_handle, args| async move {
+                Ok(CallToolResult {
+                    content: vec![Content::Text {
+                        text: format!("got {}", args["value"]),
+                        annotations: None,
+                    }],
+                    is_error: false,
+                    ..Default::default()
+                })
+            },
+        );
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "duplex-tool");
+
+        let result = client
+            .call_tool("duplex-tool".to_string(), json!({ "value": 42 }))
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(
+            result.content,
+            vec![Content::Text {
+                text: "got 42".to_string(),
+                annotations: None,
+            }]
+        );
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
 #[tokio::test]
 async fn test_full_client_server_interaction() {
     let test_body = async {
@@ -150,25 +212,181 @@ async fn test_full_resource_interaction() {
 }
 
 #[tokio::test]
-async fn test_full_prompt_interaction() {
+async fn test_list_resources_by_mime() {
     let test_body = async {
-        let server = Server::new("mcp-prompt-test")
-            .on_list_prompts(mock_list_prompts_handler)
-            .on_get_prompt(mock_get_prompt_handler);
+        let server = Server::new("mcp-mime-test").on_list_resources(|_handle| async {
+            let resource = |uri: &str, mime: Option<&str>| Resource {
+                uri: uri.to_string(),
+                name: uri.to_string(),
+                description: None,
+                mime_type: mime.map(str::to_string),
+                annotations: None,
+            };
+            Ok(vec![
+                resource("mcp://e2e/logo.png", Some("image/png")),
+                resource("mcp://e2e/notes.txt", Some("text/plain")),
+                resource("mcp://e2e/photo.jpg", Some("image/jpeg")),
+                resource("mcp://e2e/unknown", None),
+            ])
+        });
 
         let (server_addr, _server_handle) = setup_test_server(server).await;
         let adapter1 = NdjsonAdapter::connect(&server_addr).await.unwrap();
         let client = Client::new(adapter1).await.unwrap();
 
-        let list_result = client.list_prompts().await.unwrap();
-        assert_eq!(list_result.prompts.len(), 1);
-        assert_eq!(list_result.prompts[0].name, "e2e-prompt");
+        let images = client.list_resources_by_mime("image/").await.unwrap();
+        let uris: Vec<&str> = images.iter().map(|r| r.uri.as_str()).collect();
+        assert_eq!(uris, vec!["mcp://e2e/logo.png", "mcp://e2e/photo.jpg"]);
+    };
 
-        let get_result = client
-            .get_prompt("e2e-prompt".to_string(), None)
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_client_exposes_negotiated_capabilities() {
+    let test_body = async {
+        let server = Server::new("mcp-capabilities-test").register_tool(
+            Tool {
+                name: "only-tool".to_string(),
+                ..Default::default()
+            },
+            |_handle, _args| async { Ok(CallToolResult::default()) },
+        );
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        assert_eq!(client.server_info().name, "mcp-capabilities-test");
+        let capabilities = client.server_capabilities();
+        assert!(capabilities.tools.is_some());
+        assert!(capabilities.prompts.is_none());
+        assert!(capabilities.resources.is_none());
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_supported_methods_follow_capabilities() {
+    let test_body = async {
+        let server = Server::new("mcp-supported-methods-test").register_tool(
+            Tool {
+                name: "only-tool".to_string(),
+                ..Default::default()
+            },
+            |_handle, _args| async { Ok(CallToolResult::default()) },
+        );
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        assert_eq!(client.supported_methods(), vec!["tools/list", "tools/call"]);
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_dropping_client_with_request_in_flight() {
+    let test_body = async {
+        let server = Server::new("mcp-drop-race-test").register_tool(
+            Tool {
+                name: "slow".to_string(),
+                ..Default::default()
+            },
+            |_handle, _args| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Ok(CallToolResult::default())
+            },
+        );
+
+        let (client_adapter, server_adapter) = duplex_pair();
+        let server_handle =
+            tokio::spawn(async move { server.handle_connection(server_adapter).await });
+        let client = Client::new(client_adapter).await.unwrap();
+
+        // Issue the request, give up on it, and drop the client while the server is
+        // still working on the response.
+        let caller = tokio::spawn(async move {
+            let call = client.call_tool("slow".to_string(), json!({}));
+            let outcome = tokio::time::timeout(Duration::from_millis(20), call).await;
+            assert!(
+                outcome.is_err(),
+                "the slow tool should not have answered yet"
+            );
+            drop(client);
+        });
+        caller.await.expect("client task panicked");
+
+        // The server's late response has nowhere to go; its session must still wind
+        // down cleanly once it notices the client is gone.
+        server_handle
+            .await
+            .expect("server task panicked")
+            .expect("server session failed");
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_client_reads_server_version_and_instructions() {
+    let test_body = async {
+        let server = Server::new("mcp-instructions-test")
+            .version("3.1.4")
+            .instructions("Always list tools before calling them.");
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        let init = client.initialize_result();
+        // Shared rather than copied on every call.
+        assert!(std::sync::Arc::ptr_eq(&init, &client.initialize_result()));
+        assert_eq!(init.server_info.version, "3.1.4");
+        assert_eq!(
+            init.instructions.as_deref(),
+            Some("Always list tools before calling them.")
+        );
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_tools_list_changed_reaches_client_handler() {
+    let test_body = async {
+        let server = Server::new("mcp-list-changed-test").register_tool(
+            Tool {
+                name: "change-tools".to_string(),
+                ..Default::default()
+            },
+            |handle: ConnectionHandle, _args| async move {
+                handle.notify_tools_list_changed().await?;
+                Ok(CallToolResult::default())
+            },
+        );
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let tx = std::sync::Mutex::new(Some(tx));
+        client.on_tools_list_changed(move |_: ListToolsChangedParams| {
+            if let Some(tx) = tx.lock().unwrap().take() {
+                let _ = tx.send(());
+            }
+        });
+
+        client
+            .call_tool("change-tools".to_string(), json!({}))
             .await
             .unwrap();
-        assert_eq!(get_result.messages.len(), 1);
+        rx.await.expect("tools/list_changed handler was not called");
     };
 
     tokio::time::timeout(Duration::from_secs(6), test_body)
@@ -177,26 +395,220 @@ async fn test_full_prompt_interaction() {
 }
 
 #[tokio::test]
-async fn test_multiple_interactions_on_one_connection() {
+async fn test_tool_added_mid_session_is_listed() {
     let test_body = async {
-        let server = Server::new("mcp-e2e-test-server").register_tool(
+        let server = Server::new("mcp-dynamic-tools-test").register_tool(
             Tool {
-                name: "e2e-test-tool".to_string(),
+                name: "initial".to_string(),
                 ..Default::default()
             },
             |_handle, _args| async { Ok(CallToolResult::default()) },
         );
+        let (client, _server_handle) = setup_duplex_client(server.clone()).await;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        client.on_tools_list_changed(move |_: ListToolsChangedParams| {
+            let _ = tx.send(());
+        });
+
+        server
+            .add_tool(
+                Tool {
+                    name: "late".to_string(),
+                    ..Default::default()
+                },
+                |_handle, _args| async { Ok(CallToolResult::default()) },
+            )
+            .await;
+        rx.recv().await.expect("tools/list_changed was not sent");
+        let names: Vec<String> = client
+            .list_tools()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert!(names.contains(&"initial".to_string()));
+        assert!(names.contains(&"late".to_string()));
+        client
+            .call_tool("late".to_string(), json!({}))
+            .await
+            .unwrap();
+
+        assert!(server.remove_tool("late").await);
+        assert!(!server.remove_tool("late").await);
+        rx.recv().await.expect("tools/list_changed was not sent");
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "initial");
+    };
 
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_connections_beyond_the_cap_are_refused() {
+    let test_body = async {
+        let server = Server::new("mcp-max-connections-test")
+            .register_tool(
+                Tool {
+                    name: "noop".to_string(),
+                    ..Default::default()
+                },
+                |_handle, _args| async { Ok(CallToolResult::default()) },
+            )
+            .max_connections(1);
         let (server_addr, _server_handle) = setup_test_server(server).await;
-        let adapter1 = NdjsonAdapter::connect(&server_addr).await.unwrap();
-        let client = Client::new(adapter1).await.unwrap();
+        let first = Client::new(NdjsonAdapter::connect(&server_addr).await.unwrap())
+            .await
+            .unwrap();
+
+        let mut second = tokio::net::TcpStream::connect(&server_addr).await.unwrap();
+        let mut refusal = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut second, &mut refusal)
+            .await
+            .unwrap();
+        let refusal: Value = serde_json::from_str(refusal.trim()).unwrap();
+        assert_eq!(refusal["id"], Value::Null);
+        assert_eq!(refusal["error"]["code"], CONNECTION_LIMIT_REACHED);
+
+        // The connection that got in is unaffected.
+        assert_eq!(first.list_tools().await.unwrap().len(), 1);
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_connected_clients_lists_open_sessions() {
+    let test_body = async {
+        let server = Server::new("mcp-connected-clients-test");
+        let (server_addr, _server_handle) = setup_test_server(server.clone()).await;
+        assert!(server.connected_clients().is_empty());
+
+        let first = Client::new(NdjsonAdapter::connect(&server_addr).await.unwrap())
+            .await
+            .unwrap();
+        let second = Client::new(NdjsonAdapter::connect(&server_addr).await.unwrap())
+            .await
+            .unwrap();
+
+        let clients = server.connected_clients();
+        assert_eq!(clients.len(), 2);
+        for client in &clients {
+            assert!(client
+                .peer_addr
+                .as_deref()
+                .unwrap()
+                .starts_with("127.0.0.1:"));
+        }
+        assert_ne!(clients[0].peer_addr, clients[1].peer_addr);
+        assert!(clients[0].connected_at <= clients[1].connected_at);
+
+        // A session leaves the list once its client disconnects.
+        drop(first);
+        while server.connected_clients().len() > 1 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            server.connected_clients()[0].peer_addr,
+            clients[1].peer_addr
+        );
+        drop(second);
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_serve_stream_on_a_manually_accepted_connection() {
+    let test_body = async {
+        let server = Server::new("serve-stream-test").register_tool(
+            Tool {
+                name: "noop".to_string(),
+                ..Default::default()
+            },
+            |_handle, _args| async { Ok(CallToolResult::default()) },
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap().to_string();
+        let server_task = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            server.serve_stream::<NdjsonAdapter>(stream).await
+        });
+
+        let client = Client::new(NdjsonAdapter::connect(&server_addr).await.unwrap())
+            .await
+            .unwrap();
         let tools = client.list_tools().await.unwrap();
-        assert_eq!(tools[0].name, "e2e-test-tool");
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "noop");
+
+        drop(client);
+        server_task.await.unwrap().unwrap();
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_tool_reads_progress_token_from_request_meta() {
+    let test_body = async {
+        let server = Server::new("meta-test").register_tool(
+            Tool {
+                name: "index".to_string(),
+                ..Default::default()
+            },
+            |handle: ConnectionHandle, _args| async move {
+                let token = handle.progress_token().expect("no progress token");
+                handle.send_progress(token.clone(), 1.0, Some(1.0)).await?;
+                let tag = handle.meta().unwrap()["tag"].clone();
+                Ok(CallToolResult::text(format!(
+                    "{} {}",
+                    serde_json::to_value(&token)?,
+                    tag
+                )))
+            },
+        );
+        let (client, _server_handle) = connected_pair(server).await.unwrap();
+
         let result = client
-            .call_tool("e2e-test-tool".to_string(), json!({}))
+            .call_tool_with_meta(
+                "index".to_string(),
+                json!({}),
+                json!({ "progressToken": "job-1", "tag": "nightly" }),
+            )
             .await
             .unwrap();
-        assert!(!result.is_error);
+        assert_eq!(result, CallToolResult::text(r#""job-1" "nightly""#));
+
+        // Requests without `_meta` leave it unset.
+        let (client, _server_handle) = connected_pair(Server::new("meta-test").register_tool(
+            Tool {
+                name: "peek".to_string(),
+                ..Default::default()
+            },
+            |handle: ConnectionHandle, _args| async move {
+                assert!(handle.meta().is_none());
+                assert!(handle.progress_token().is_none());
+                Ok(CallToolResult::text("ok"))
+            },
+        ))
+        .await
+        .unwrap();
+        let result = client
+            .call_tool("peek".to_string(), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result, CallToolResult::text("ok"));
     };
 
     tokio::time::timeout(Duration::from_secs(6), test_body)
@@ -204,22 +616,1380 @@ async fn test_multiple_interactions_on_one_connection() {
         .expect("Test timed out after 6 seconds");
 }
 
-#[tokio::test]
-async fn test_call_unregistered_tool_returns_error() {
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_server_close_with_reason_reaches_client_close_handler() {
     let test_body = async {
-        let server = Server::new("mcp-error-test");
+        let server = Server::new("close-test").register_tool(
+            Tool {
+                name: "logout".to_string(),
+                ..Default::default()
+            },
+            |handle: ConnectionHandle, _args| async move {
+                handle.close_with_reason(401, "session expired").await?;
+                Ok(CallToolResult::text("bye"))
+            },
+        );
+        let (client, server_handle) = connected_pair(server).await.unwrap();
+        let (close_tx, mut close_rx) = tokio::sync::mpsc::unbounded_channel();
+        client.on_close(move |params: CloseParams| {
+            close_tx.send(params).unwrap();
+        });
 
-        let (server_addr, _server_handle) = setup_test_server(server).await;
-        let adapter1 = NdjsonAdapter::connect(&server_addr).await.unwrap();
-        let client = Client::new(adapter1).await.unwrap();
+        // The response to the closing request is still delivered, after the
+        // close notification, whose handler has therefore already run.
         let result = client
-            .call_tool("this-tool-does-not-exist".to_string(), json!({}))
-            .await;
-        assert!(result.is_err());
-        let error_message = result.unwrap_err().to_string();
-        // UPDATED: Check for the new error message format.
-        assert!(error_message.contains("JSON-RPC error"));
-        assert!(error_message.contains("not found"));
+            .call_tool("logout".to_string(), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result, CallToolResult::text("bye"));
+
+        let params = close_rx.try_recv().unwrap();
+        assert_eq!(
+            params,
+            CloseParams {
+                code: 401,
+                reason: "session expired".to_string(),
+            }
+        );
+        server_handle.await.unwrap().unwrap();
+        assert!(client.list_tools().await.is_err());
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_tool_streams_partial_results_before_final_result() {
+    let test_body = async {
+        let server = Server::new("streaming-test").register_tool(
+            Tool {
+                name: "query".to_string(),
+                ..Default::default()
+            },
+            |handle: ConnectionHandle, _args| async move {
+                let request_id = handle.request_id().unwrap();
+                for row in ["row 1", "row 2", "row 3"] {
+                    handle
+                        .stream_content(
+                            request_id.clone(),
+                            Content::Text {
+                                text: row.to_string(),
+                                annotations: None,
+                            },
+                        )
+                        .await?;
+                }
+                Ok(CallToolResult::text("3 rows"))
+            },
+        );
+        let (client, _server_handle) = connected_pair(server).await.unwrap();
+        let (chunks_tx, mut chunks_rx) = tokio::sync::mpsc::unbounded_channel();
+        client.on_partial_result(move |params: PartialResultParams| {
+            chunks_tx.send(params).unwrap();
+        });
+
+        let result = client
+            .call_tool("query".to_string(), json!({}))
+            .await
+            .unwrap();
+
+        // Every chunk was delivered by the time the final result arrived.
+        let mut chunks = Vec::new();
+        while let Ok(params) = chunks_rx.try_recv() {
+            chunks.push(params);
+        }
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|p| p.request_id == chunks[0].request_id));
+        let texts: Vec<String> = chunks
+            .into_iter()
+            .map(|p| match p.content {
+                Content::Text { text, .. } => text,
+                other => panic!("Expected a text chunk, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(texts, vec!["row 1", "row 2", "row 3"]);
+        assert_eq!(result, CallToolResult::text("3 rows"));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_requests_after_shutdown_get_shutting_down_error() {
+    let test_body = async {
+        let server = Server::new("mcp-shutdown-test").register_tool(
+            Tool {
+                name: "slow".to_string(),
+                ..Default::default()
+            },
+            |_handle, _args| async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                Ok(CallToolResult::text("finished"))
+            },
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let addr_clone = server_addr.clone();
+        let listen_handle = tokio::spawn(async move {
+            server
+                .tcp_listen_with_shutdown::<NdjsonAdapter>(&addr_clone, async {
+                    let _ = shutdown_rx.await;
+                })
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let client = std::sync::Arc::new(
+            Client::new(NdjsonAdapter::connect(&server_addr).await.unwrap())
+                .await
+                .unwrap(),
+        );
+        let in_flight = {
+            let client = std::sync::Arc::clone(&client);
+            tokio::spawn(async move { client.call_tool("slow".to_string(), json!({})).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        shutdown_tx.send(()).unwrap();
+        listen_handle.await.unwrap().unwrap();
+
+        // The request that was already running completes normally...
+        let result = in_flight.await.unwrap().unwrap();
+        assert_eq!(result, CallToolResult::text("finished"));
+        // ...but new ones are turned away.
+        match client.list_tools().await {
+            Err(Error::JsonRpc(e)) => assert_eq!(e.code, SERVER_SHUTTING_DOWN),
+            other => panic!("Expected a shutting down error, got {:?}", other),
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_typed_tool_added_mid_session_gets_typed_arguments() {
+    let test_body = async {
+        let server = Server::new("mcp-dynamic-typed-tools-test");
+        let (client, _server_handle) = setup_duplex_client(server.clone()).await;
+
+        server
+            .add_tool_typed(
+                Tool {
+                    name: "greet".to_string(),
+                    ..Default::default()
+                },
+                |_handle, args: GreetingArgs| async move {
+                    Ok(CallToolResult::text(format!("Hello, {}", args.name)))
+                },
+            )
+            .await;
+
+        let result = client
+            .call_tool("greet".to_string(), json!({ "name": "Ada" }))
+            .await
+            .unwrap();
+        assert_eq!(result, CallToolResult::text("Hello, Ada"));
+
+        let result = client
+            .call_tool("greet".to_string(), json!({ "name": 7 }))
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert!(result
+            .error_message()
+            .unwrap()
+            .starts_with("Invalid arguments for tool 'greet'"));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[derive(serde::Deserialize)]
+struct GreetingArgs {
+    name: String,
+    excited: Option<bool>,
+}
+
+#[tokio::test]
+async fn test_typed_prompt_arguments_are_validated() {
+    let test_body = async {
+        let server = Server::new("mcp-typed-prompt-test").on_get_prompt_typed(
+            |_handle, prompt_name: String, args: GreetingArgs| async move {
+                let punctuation = if args.excited == Some(true) { "!" } else { "." };
+                Ok(GetPromptResult {
+                    description: Some(prompt_name),
+                    messages: vec![PromptMessage {
+                        role: "user".to_string(),
+                        content: Content::Text {
+                            text: format!("Hello, {}{}", args.name, punctuation),
+                            annotations: None,
+                        },
+                    }],
+                })
+            },
+        );
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        let result = client
+            .get_prompt(
+                "greet".to_string(),
+                Some(json!({ "name": "Ada", "excited": true })),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.description.as_deref(), Some("greet"));
+        assert!(matches!(
+            &result.messages[0].content,
+            Content::Text { text, .. } if text == "Hello, Ada!"
+        ));
+
+        for malformed in [Some(json!({ "name": 42 })), Some(json!({})), None] {
+            match client.get_prompt("greet".to_string(), malformed).await {
+                Err(Error::JsonRpc(e)) => {
+                    assert_eq!(e.code, mcp_sdk::types::INVALID_PARAMS);
+                    assert!(e.message.contains("greet"), "Got: {}", e.message);
+                }
+                other => panic!("Expected INVALID_PARAMS, got {:?}", other),
+            }
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_roots_capability_only_advertised_with_roots() {
+    let test_body = async {
+        let server = Server::new("mcp-roots-capability-test").register_tool(
+            Tool {
+                name: "roots".to_string(),
+                ..Default::default()
+            },
+            |handle, _args| async move {
+                if handle.client_capabilities().roots.is_none() {
+                    return Ok(CallToolResult::text("no roots"));
+                }
+                let roots = handle.list_roots().await?;
+                let uris: Vec<String> = roots.into_iter().map(|root| root.uri).collect();
+                Ok(CallToolResult::text(uris.join(",")))
+            },
+        );
+        let ask = |client: Client| async move {
+            client
+                .call_tool("roots".to_string(), json!({}))
+                .await
+                .unwrap()
+        };
+
+        let (client, _server_handle) = setup_duplex_client(server.clone()).await;
+        assert_eq!(ask(client).await, CallToolResult::text("no roots"));
+
+        let (client_adapter, server_adapter) = duplex_pair();
+        tokio::spawn(async move { server.handle_connection(server_adapter).await });
+        let roots = vec![Root {
+            uri: "file:///workspace".to_string(),
+            name: None,
+        }];
+        let client = Client::new_with_roots(client_adapter, roots).await.unwrap();
+        assert_eq!(ask(client).await, CallToolResult::text("file:///workspace"));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_server_lists_roots_configured_on_client() {
+    let test_body = async {
+        let server = Server::new("mcp-roots-test").register_tool(
+            Tool {
+                name: "show-roots".to_string(),
+                ..Default::default()
+            },
+            |handle, _args| async move {
+                let roots = handle.list_roots().await?;
+                let names: Vec<String> = roots
+                    .iter()
+                    .map(|root| format!("{}={}", root.name.as_deref().unwrap_or("?"), root.uri))
+                    .collect();
+                Ok(CallToolResult::text(names.join(",")))
+            },
+        );
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+        client
+            .set_roots(vec![
+                Root {
+                    uri: "file:///home/user/project".to_string(),
+                    name: Some("project".to_string()),
+                },
+                Root {
+                    uri: "file:///tmp".to_string(),
+                    name: None,
+                },
+            ])
+            .await
+            .unwrap();
+
+        let result = client
+            .call_tool("show-roots".to_string(), json!({}))
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(
+            result.content,
+            vec![Content::Text {
+                text: "project=file:///home/user/project,?=file:///tmp".to_string(),
+                annotations: None,
+            }]
+        );
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_tool_echoes_input_elicited_from_client() {
+    let test_body = async {
+        let server = Server::new("mcp-elicit-test").register_tool(
+            Tool {
+                name: "greet".to_string(),
+                ..Default::default()
+            },
+            |handle, _args| async move {
+                let answer = handle
+                    .elicit(ElicitParams {
+                        message: "What is your name?".to_string(),
+                        requested_schema: json!({
+                            "type": "object",
+                            "properties": { "name": { "type": "string" } },
+                            "required": ["name"]
+                        }),
+                    })
+                    .await?;
+                match (answer.action, answer.content) {
+                    (ElicitAction::Accept, Some(content)) => {
+                        let name = content["name"].as_str().unwrap_or("stranger");
+                        Ok(CallToolResult::text(format!("Hello, {}!", name)))
+                    }
+                    (action, _) => Ok(CallToolResult::error(format!("{:?}", action))),
+                }
+            },
+        );
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+        client.on_elicit(|params| async move {
+            assert_eq!(params.message, "What is your name?");
+            assert_eq!(params.requested_schema["required"], json!(["name"]));
+            Ok(ElicitResult {
+                action: ElicitAction::Accept,
+                content: Some(json!({ "name": "Ada" })),
+            })
+        });
+
+        let result = client
+            .call_tool("greet".to_string(), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result, CallToolResult::text("Hello, Ada!"));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_client_surfaces_structured_error_data() {
+    let test_body = async {
+        let server =
+            Server::new("mcp-error-data-test").on_read_resource(|_handle, uri| async move {
+                Err(Error::JsonRpc(ErrorData {
+                    code: INVALID_PARAMS,
+                    message: "Unknown resource".to_string(),
+                    data: Some(json!({ "uri": uri, "known": ["mcp://e2e/file.txt"] })),
+                }))
+            });
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+        let err = client
+            .read_resource("mcp://e2e/missing.txt".to_string())
+            .await
+            .unwrap_err();
+
+        match &err {
+            Error::JsonRpc(error) => {
+                assert_eq!(error.code, INVALID_PARAMS);
+                assert_eq!(
+                    error.data,
+                    Some(
+                        json!({ "uri": "mcp://e2e/missing.txt", "known": ["mcp://e2e/file.txt"] })
+                    )
+                );
+            }
+            other => panic!("Expected a JSON-RPC error, got {:?}", other),
+        }
+        assert!(err.to_string().contains("\"known\""));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_result_transform_appends_footer() {
+    let test_body = async {
+        let server = Server::new("mcp-transform-test")
+            .register_tool(
+                Tool {
+                    name: "secret".to_string(),
+                    ..Default::default()
+                },
+                |_handle, _args| async { Ok(CallToolResult::text("token=abc123")) },
+            )
+            .with_result_transform(|result| {
+                for content in &mut result.content {
+                    if let Content::Text { text, .. } = content {
+                        *text = text.replace("abc123", "[redacted]");
+                    }
+                }
+                result.content.push(Content::Text {
+                    text: "-- served by mcp-transform-test".to_string(),
+                    annotations: None,
+                });
+            });
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+        let result = client
+            .call_tool("secret".to_string(), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            CallToolResult::builder()
+                .text("token=[redacted]")
+                .text("-- served by mcp-transform-test")
+                .build()
+        );
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_client_tool_map_and_find_tool() {
+    let test_body = async {
+        let noop = |_handle, _args| async { Ok(CallToolResult::text("ok")) };
+        let server = Server::new("tool-map-test")
+            .register_tool(
+                Tool {
+                    name: "search".to_string(),
+                    description: Some("Searches the index".to_string()),
+                    ..Default::default()
+                },
+                noop,
+            )
+            .register_tool(
+                Tool {
+                    name: "reindex".to_string(),
+                    ..Default::default()
+                },
+                noop,
+            );
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        let map = client.tool_map().await.unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["search"].as_deref(), Some("Searches the index"));
+        assert_eq!(map["reindex"], None);
+
+        let tool = client.find_tool("search").await.unwrap().unwrap();
+        assert_eq!(tool.name, "search");
+        assert!(client.find_tool("unknown").await.unwrap().is_none());
+    };
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("test timed out");
+}
+
+#[tokio::test]
+async fn test_client_close_drains_before_disconnecting() {
+    let test_body = async {
+        let (roots_tx, mut roots_rx) = tokio::sync::mpsc::unbounded_channel();
+        let server = Server::new("close-test")
+            .register_tool(
+                Tool {
+                    name: "noop".to_string(),
+                    ..Default::default()
+                },
+                |_handle, _args| async { Ok(CallToolResult::text("done")) },
+            )
+            .on_notification(
+                "notifications/roots/list_changed",
+                move |_handle, _params| {
+                    let roots_tx = roots_tx.clone();
+                    async move {
+                        let _ = roots_tx.send(());
+                        Ok(())
+                    }
+                },
+            );
+        let (client, server_handle) = setup_duplex_client(server).await;
+
+        let result = client
+            .call_tool("noop".to_string(), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result, CallToolResult::text("done"));
+
+        // Queued right before closing; `close` must still deliver it.
+        client
+            .set_roots(vec![Root {
+                uri: "file:///workspace".to_string(),
+                name: None,
+            }])
+            .await
+            .unwrap();
+        client.close().await.unwrap();
+
+        // The server sees a clean end of stream, not a reset mid-message.
+        server_handle.await.unwrap().unwrap();
+        assert!(roots_rx.try_recv().is_ok());
+    };
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("test timed out");
+}
+
+#[tokio::test]
+async fn test_connection_state_is_separate_per_connection() {
+    struct SessionId(u64);
+
+    let test_body = async {
+        let next_id = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(1));
+        let server = Server::new("connection-state-test")
+            .with_connection_state_init(move || {
+                SessionId(next_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+            })
+            .register_tool(
+                Tool {
+                    name: "whoami".to_string(),
+                    ..Default::default()
+                },
+                |handle: ConnectionHandle, _args| async move {
+                    Ok(CallToolResult::text(
+                        handle.state::<SessionId>().0.to_string(),
+                    ))
+                },
+            );
+        let (first, _first_server) = setup_duplex_client(server.clone()).await;
+        let (second, _second_server) = setup_duplex_client(server).await;
+
+        async fn whoami(client: &Client) -> CallToolResult {
+            client
+                .call_tool("whoami".to_string(), json!({}))
+                .await
+                .unwrap()
+        }
+        let first_id = whoami(&first).await;
+        let second_id = whoami(&second).await;
+        assert_ne!(first_id, second_id);
+        // The state lives as long as the connection, not a single call.
+        assert_eq!(whoami(&first).await, first_id);
+        assert_eq!(whoami(&second).await, second_id);
+    };
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("test timed out");
+}
+
+#[tokio::test]
+async fn test_large_tool_argument_arrives_intact() {
+    let test_body = async {
+        let server = Server::new("mcp-large-body-test").register_tool(
+            Tool {
+                name: "measure".to_string(),
+                ..Default::default()
+            },
+            |_handle, args| async move {
+                let document = args["document"].as_str().unwrap_or_default();
+                let intact = document.len() == 5 * 1024 * 1024
+                    && document
+                        .bytes()
+                        .enumerate()
+                        .all(|(i, b)| b == b'a' + (i % 26) as u8);
+                Ok(CallToolResult {
+                    content: vec![Content::Text {
+                        text: format!("{} {}", document.len(), intact),
+                        annotations: None,
+                    }],
+                    ..Default::default()
+                })
+            },
+        );
+
+        let (server_addr, _server_handle) = setup_test_server(server).await;
+        let client = Client::new(NdjsonAdapter::connect(&server_addr).await.unwrap())
+            .await
+            .unwrap();
+
+        let document: String = (0..5 * 1024 * 1024)
+            .map(|i| (b'a' + (i % 26) as u8) as char)
+            .collect();
+        for _ in 0..2 {
+            let result = client
+                .call_tool("measure".to_string(), json!({ "document": document }))
+                .await
+                .unwrap();
+            assert_eq!(
+                result.content,
+                vec![Content::Text {
+                    text: format!("{} true", 5 * 1024 * 1024),
+                    annotations: None,
+                }]
+            );
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(30), test_body)
+        .await
+        .expect("Test timed out after 30 seconds");
+}
+
+#[tokio::test]
+async fn test_static_resources() {
+    let test_body = async {
+        let static_resource = |uri: &str, text: &str| {
+            (
+                Resource {
+                    uri: uri.to_string(),
+                    name: uri.to_string(),
+                    mime_type: Some("text/plain".to_string()),
+                    ..Default::default()
+                },
+                ResourceContents::Text(TextResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some("text/plain".to_string()),
+                    text: text.to_string(),
+                }),
+            )
+        };
+        let (readme, readme_contents) = static_resource("mcp://e2e/README", "read me");
+        let (license, license_contents) = static_resource("mcp://e2e/LICENSE", "MIT");
+        let server = Server::new("mcp-static-resource-test")
+            .register_resource(readme, readme_contents)
+            .register_resource(license, license_contents.clone());
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        let resources = client.list_resources().await.unwrap();
+        let uris: Vec<&str> = resources.iter().map(|r| r.uri.as_str()).collect();
+        assert_eq!(uris, vec!["mcp://e2e/README", "mcp://e2e/LICENSE"]);
+
+        let read = client
+            .read_resource("mcp://e2e/LICENSE".to_string())
+            .await
+            .unwrap();
+        assert_eq!(read.contents, vec![license_contents]);
+
+        let missing = client.read_resource("mcp://e2e/missing".to_string()).await;
+        assert!(missing.is_err());
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_list_resource_templates() {
+    let test_body = async {
+        let server = Server::new("mcp-template-test").on_list_resource_templates(|_handle| async {
+            Ok(vec![ResourceTemplate {
+                uri_template: "mcp://e2e/logs/{date}.log".to_string(),
+                name: "daily-log".to_string(),
+                description: Some("The log for a given day".to_string()),
+                mime_type: Some("text/plain".to_string()),
+            }])
+        });
+
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        let templates = client.list_resource_templates().await.unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].uri_template, "mcp://e2e/logs/{date}.log");
+        assert_eq!(templates[0].name, "daily-log");
+        assert_eq!(
+            templates[0].description.as_deref(),
+            Some("The log for a given day")
+        );
+        assert_eq!(templates[0].mime_type.as_deref(), Some("text/plain"));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_discover_resources() {
+    let test_body = async {
+        let template = ResourceTemplate {
+            uri_template: "mcp://e2e/logs/{date}.log".to_string(),
+            name: "daily-log".to_string(),
+            ..Default::default()
+        };
+        let template_clone = template.clone();
+        let server = Server::new("mcp-discover-test")
+            .on_list_resources(mock_list_resources_handler)
+            .on_list_resource_templates(move |_handle| {
+                let template = template_clone.clone();
+                async move { Ok(vec![template]) }
+            });
+        let (client, _server_handle) = setup_duplex_client(server).await;
+
+        let catalog = client.discover_resources().await.unwrap();
+        assert_eq!(catalog.resources.len(), 1);
+        assert_eq!(catalog.resources[0].uri, "mcp://e2e/file.txt");
+        assert_eq!(catalog.templates, vec![template]);
+
+        // A server without templates still yields its concrete resources.
+        let server =
+            Server::new("mcp-discover-test").on_list_resources(mock_list_resources_handler);
+        let (client, _server_handle) = setup_duplex_client(server).await;
+        let catalog = client.discover_resources().await.unwrap();
+        assert_eq!(catalog.resources.len(), 1);
+        assert!(catalog.templates.is_empty());
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_full_prompt_interaction() {
+    let test_body = async {
+        let server = Server::new("mcp-prompt-test")
+            .on_list_prompts(mock_list_prompts_handler)
+            .on_get_prompt(mock_get_prompt_handler);
+
+        let (server_addr, _server_handle) = setup_test_server(server).await;
+        let adapter1 = NdjsonAdapter::connect(&server_addr).await.unwrap();
+        let client = Client::new(adapter1).await.unwrap();
+
+        let list_result = client.list_prompts().await.unwrap();
+        assert_eq!(list_result.prompts.len(), 1);
+        assert_eq!(list_result.prompts[0].name, "e2e-prompt");
+
+        let get_result = client
+            .get_prompt("e2e-prompt".to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(get_result.messages.len(), 1);
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_multiple_interactions_on_one_connection() {
+    let test_body = async {
+        let server = Server::new("mcp-e2e-test-server").register_tool(
+            Tool {
+                name: "e2e-test-tool".to_string(),
+                ..Default::default()
+            },
+            |_handle, _args| async { Ok(CallToolResult::default()) },
+        );
+
+        let (server_addr, _server_handle) = setup_test_server(server).await;
+        let adapter1 = NdjsonAdapter::connect(&server_addr).await.unwrap();
+        let client = Client::new(adapter1).await.unwrap();
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools[0].name, "e2e-test-tool");
+        let result = client
+            .call_tool("e2e-test-tool".to_string(), json!({}))
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_call_unregistered_tool_returns_error() {
+    let test_body = async {
+        let server = Server::new("mcp-error-test");
+
+        let (server_addr, _server_handle) = setup_test_server(server).await;
+        let adapter1 = NdjsonAdapter::connect(&server_addr).await.unwrap();
+        let client = Client::new(adapter1).await.unwrap();
+        let result = client
+            .call_tool("this-tool-does-not-exist".to_string(), json!({}))
+            .await;
+        assert!(result.is_err());
+        let error_message = result.unwrap_err().to_string();
+        // UPDATED: Check for the new error message format.
+        assert!(error_message.contains("JSON-RPC error"));
+        assert!(error_message.contains("not found"));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_connect_stdio_lists_tools_from_subprocess() {
+    // A tiny scripted MCP server: it answers `initialize` and `tools/list` by echoing
+    // back the request id, and ignores anything else (e.g. notifications).
+    const SCRIPT: &str = r#"
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"method":"initialize"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"sh-server","version":"0.0.1"}}}\n' "$id" ;;
+    *'"method":"tools/list"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":[{"name":"stdio-tool","inputSchema":{"type":"object"}}]}}\n' "$id" ;;
+  esac
+done
+"#;
+
+    let test_body = async {
+        let client = Client::connect_stdio("sh", ["-c", SCRIPT]).await.unwrap();
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "stdio-tool");
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_client_with_string_ids() {
+    let test_body = async {
+        let (client_adapter, mut server_adapter) = duplex_pair();
+        // A fake server that checks the id of each request and echoes it back.
+        let fake_server = tokio::spawn(async move {
+            let mut seen_ids = Vec::new();
+            while let Some(raw) = server_adapter.recv().await.unwrap() {
+                let message: Value = serde_json::from_str(&raw).unwrap();
+                let Some(id) = message.get("id") else {
+                    continue; // notifications/initialized
+                };
+                seen_ids.push(id.clone());
+                let result = match message["method"].as_str() {
+                    Some("initialize") => json!({
+                        "protocolVersion": "2024-11-05",
+                        "capabilities": {},
+                        "serverInfo": { "name": "fake", "version": "0" }
+                    }),
+                    _ => json!({ "tools": [] }),
+                };
+                let response = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                server_adapter.send(&response.to_string()).await.unwrap();
+            }
+            seen_ids
+        });
+
+        let client = Client::new(client_adapter)
+            .await
+            .unwrap()
+            .with_string_ids("req");
+        assert!(client.list_tools().await.unwrap().is_empty());
+        assert!(client.list_tools().await.unwrap().is_empty());
+        drop(client);
+
+        let seen_ids = fake_server.await.unwrap();
+        assert_eq!(seen_ids, vec![json!(0), json!("req-1"), json!("req-2")]);
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_client_with_string_ids_uses_them_for_reconnect_handshake() {
+    let test_body = async {
+        // A fake server that reports the id of each request and answers it.
+        fn fake_server(
+            mut server_adapter: DuplexAdapter,
+            ids: tokio::sync::mpsc::UnboundedSender<Value>,
+        ) {
+            tokio::spawn(async move {
+                while let Ok(Some(raw)) = server_adapter.recv().await {
+                    let message: Value = serde_json::from_str(&raw).unwrap();
+                    let Some(id) = message.get("id") else {
+                        continue; // notifications/initialized
+                    };
+                    let _ = ids.send(id.clone());
+                    let result = match message["method"].as_str() {
+                        Some("initialize") => json!({
+                            "protocolVersion": "2024-11-05",
+                            "capabilities": {},
+                            "serverInfo": { "name": "fake", "version": "0" }
+                        }),
+                        _ => json!({ "tools": [] }),
+                    };
+                    let response = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                    if server_adapter.send(&response.to_string()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let (ids_tx, mut ids_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (client_adapter, server_adapter) = duplex_pair();
+        // The first connection accepts only the handshake, then drops.
+        let first_server = {
+            let ids_tx = ids_tx.clone();
+            tokio::spawn(async move {
+                let mut server_adapter = server_adapter;
+                let raw = server_adapter.recv().await.unwrap().unwrap();
+                let request: Value = serde_json::from_str(&raw).unwrap();
+                ids_tx.send(request["id"].clone()).unwrap();
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": {
+                        "protocolVersion": "2024-11-05",
+                        "capabilities": {},
+                        "serverInfo": { "name": "fake", "version": "0" }
+                    }
+                });
+                server_adapter.send(&response.to_string()).await.unwrap();
+                server_adapter.recv().await.unwrap(); // notifications/initialized
+            })
+        };
+        let client = Client::new(client_adapter)
+            .await
+            .unwrap()
+            .with_string_ids("req")
+            .with_reconnect(move || {
+                let (client_adapter, server_adapter) = duplex_pair();
+                fake_server(server_adapter, ids_tx.clone());
+                async move { Ok(client_adapter) }
+            });
+        first_server.await.unwrap();
+
+        let _: Value = client
+            .send_request_retrying("tools/list", json!({}), 1)
+            .await
+            .unwrap();
+        drop(client);
+
+        let mut seen_ids = Vec::new();
+        while let Some(id) = ids_rx.recv().await {
+            seen_ids.push(id);
+        }
+        // `req-1` was lost with the first connection; the reconnect handshake
+        // takes the next id from the same counter.
+        assert_eq!(seen_ids, vec![json!(0), json!("req-2"), json!("req-3")]);
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_client_without_handshake_sends_requests_directly() {
+    let test_body = async {
+        let (client_adapter, mut server_adapter) = duplex_pair();
+        // A fake server that expects no handshake and answers the first request.
+        let fake_server = tokio::spawn(async move {
+            let raw = server_adapter.recv().await.unwrap().unwrap();
+            let request: Value = serde_json::from_str(&raw).unwrap();
+            let response = json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": { "tools": [{ "name": "noop", "inputSchema": { "type": "object" } }] }
+            });
+            server_adapter.send(&response.to_string()).await.unwrap();
+            request
+        });
+
+        let client = Client::new_without_handshake(client_adapter);
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "noop");
+
+        let request = fake_server.await.unwrap();
+        assert_eq!(request["method"], "tools/list");
+        assert!(client.supported_methods().is_empty());
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_max_pending_rejects_requests_beyond_the_cap() {
+    let test_body = async {
+        // The server never answers, so every request stays pending.
+        let (client_adapter, _silent_server) = duplex_pair();
+        let client = Client::new_without_handshake(client_adapter).max_pending(2);
+
+        let stuck = futures::future::join(client.list_tools(), client.list_prompts());
+        tokio::select! {
+            _ = stuck => panic!("The silent server answered"),
+            result = async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                client.list_tools().await
+            } => {
+                assert!(matches!(result, Err(Error::TooManyPendingRequests(2))));
+            }
+        }
+
+        // Abandoned requests free their slots.
+        let result = tokio::time::timeout(Duration::from_millis(50), client.list_tools()).await;
+        assert!(
+            result.is_err(),
+            "Expected the request to be sent and stay pending"
+        );
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_call_tool_retrying_reconnects_after_connection_loss() {
+    let test_body = async {
+        let server = Server::new("mcp-reconnect-test").register_tool(
+            Tool {
+                name: "echo".to_string(),
+                ..Default::default()
+            },
+            |_handle, args| async move {
+                Ok(CallToolResult {
+                    content: vec![Content::Text {
+                        text: args.to_string(),
+                        annotations: None,
+                    }],
+                    ..Default::default()
+                })
+            },
+        );
+        let serve = {
+            let server = server.clone();
+            move |adapter| {
+                let server = server.clone();
+                tokio::spawn(async move { server.handle_connection(adapter).await })
+            }
+        };
+
+        let (client_adapter, server_adapter) = duplex_pair();
+        let first_server = serve(server_adapter);
+        let client = Client::new(client_adapter)
+            .await
+            .unwrap()
+            .with_reconnect(move || {
+                let (client_adapter, server_adapter) = duplex_pair();
+                serve(server_adapter);
+                async move { Ok(client_adapter) }
+            });
+
+        // Drop the first connection from the server side.
+        first_server.abort();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(client.list_tools().await.is_err());
+
+        let result = client
+            .call_tool_retrying("echo".to_string(), json!({"n": 1}), 1)
+            .await
+            .unwrap();
+        match &result.content[0] {
+            Content::Text { text, .. } => assert_eq!(text, r#"{"n":1}"#),
+            other => panic!("Unexpected content: {:?}", other),
+        }
+
+        // The new connection is used from then on.
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools[0].name, "echo");
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_send_request_retrying_only_retries_idempotent_methods() {
+    let test_body = async {
+        // Every connection is dropped right away, so each attempt fails.
+        let connects = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (client_adapter, server_adapter) = duplex_pair();
+        drop(server_adapter);
+        let client = Client::new_without_handshake(client_adapter).with_reconnect({
+            let connects = connects.clone();
+            move || {
+                connects.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let (client_adapter, _) = duplex_pair();
+                async move { Ok(client_adapter) }
+            }
+        });
+
+        let result: Result<Value> = client
+            .send_request_retrying("tools/call", json!({"name": "x"}), 3)
+            .await;
+        assert!(result.is_err());
+        assert_eq!(connects.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        let result: Result<Value> = client
+            .send_request_retrying("tools/list", json!({}), 3)
+            .await;
+        assert!(result.is_err());
+        assert_eq!(connects.load(std::sync::atomic::Ordering::SeqCst), 1);
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_minimal_client_advertises_no_capabilities() {
+    let test_body = async {
+        let (client_adapter, mut server_adapter) = duplex_pair();
+        // A fake server that answers the handshake and hands back the request.
+        let fake_server = tokio::spawn(async move {
+            let raw = server_adapter.recv().await.unwrap().unwrap();
+            let request: Value = serde_json::from_str(&raw).unwrap();
+            let response = json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": {
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": {},
+                    "serverInfo": { "name": "fake", "version": "0" }
+                }
+            });
+            server_adapter.send(&response.to_string()).await.unwrap();
+            // Kept open so the client can finish the handshake.
+            (request, server_adapter)
+        });
+
+        let _client = Client::new_minimal(client_adapter).await.unwrap();
+        let (request, _server_adapter) = fake_server.await.unwrap();
+        assert_eq!(request["method"], "initialize");
+        assert_eq!(request["params"]["capabilities"], json!({}));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_keepalive_pings_keep_connection_usable() {
+    let test_body = async {
+        let server = Server::new("mcp-keepalive-test").register_tool(
+            Tool {
+                name: "still-here".to_string(),
+                ..Default::default()
+            },
+            |_handle, _args| async { Ok(CallToolResult::default()) },
+        );
+        let (client, _server_handle) = setup_duplex_client(server).await;
+        let client = client.with_keepalive(Duration::from_millis(20));
+
+        // Several pings go out and are answered by the server.
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(client.list_tools().await.unwrap()[0].name, "still-here");
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_keepalive_closes_connection_when_pings_go_unanswered() {
+    let test_body = async {
+        let (client_adapter, mut server_adapter) = duplex_pair();
+        // A fake server that answers the first three pings, then goes silent.
+        let (pings_tx, mut pings_rx) = tokio::sync::mpsc::unbounded_channel();
+        let _fake_server = tokio::spawn(async move {
+            let mut answered = 0;
+            while let Some(raw) = server_adapter.recv().await.unwrap() {
+                let message: Value = serde_json::from_str(&raw).unwrap();
+                let result = match message["method"].as_str() {
+                    Some("initialize") => json!({
+                        "protocolVersion": "2024-11-05",
+                        "capabilities": {},
+                        "serverInfo": { "name": "fake", "version": "0" }
+                    }),
+                    Some("ping") => {
+                        pings_tx.send(message["id"].clone()).unwrap();
+                        if answered == 3 {
+                            continue;
+                        }
+                        answered += 1;
+                        json!({})
+                    }
+                    _ => continue,
+                };
+                let response = json!({ "jsonrpc": "2.0", "id": message["id"], "result": result });
+                server_adapter.send(&response.to_string()).await.unwrap();
+            }
+        });
+
+        let client = Client::new(client_adapter)
+            .await
+            .unwrap()
+            .with_keepalive(Duration::from_millis(30));
+
+        let mut ids = Vec::new();
+        for _ in 0..4 {
+            ids.push(pings_rx.recv().await.unwrap());
+        }
+        assert_eq!(
+            ids,
+            vec![
+                json!("keepalive-1"),
+                json!("keepalive-2"),
+                json!("keepalive-3"),
+                json!("keepalive-4")
+            ]
+        );
+
+        // The fourth ping is never answered, so the client closes the connection.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            client.list_tools().await,
+            Err(Error::ChannelClosed)
+        ));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_keepalive_survives_reconnect() {
+    let test_body = async {
+        let (client_adapter, server_adapter) = duplex_pair();
+        let server = Server::new("mcp-keepalive-first");
+        let first_server =
+            tokio::spawn(async move { server.handle_connection(server_adapter).await });
+
+        // The reconnect goes to a fake server that answers everything but pings.
+        let (pings_tx, mut pings_rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = Client::new(client_adapter)
+            .await
+            .unwrap()
+            .with_keepalive(Duration::from_millis(30))
+            .with_reconnect(move || {
+                let (client_adapter, mut server_adapter) = duplex_pair();
+                let pings_tx = pings_tx.clone();
+                tokio::spawn(async move {
+                    while let Ok(Some(raw)) = server_adapter.recv().await {
+                        let message: Value = serde_json::from_str(&raw).unwrap();
+                        let result = match message["method"].as_str() {
+                            Some("initialize") => json!({
+                                "protocolVersion": "2024-11-05",
+                                "capabilities": {},
+                                "serverInfo": { "name": "mcp-keepalive-second", "version": "0" }
+                            }),
+                            Some("tools/list") => json!({ "tools": [] }),
+                            Some("ping") => {
+                                let _ = pings_tx.send(message["id"].clone());
+                                continue;
+                            }
+                            _ => continue,
+                        };
+                        let response =
+                            json!({ "jsonrpc": "2.0", "id": message["id"], "result": result });
+                        if server_adapter.send(&response.to_string()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+                async move { Ok(client_adapter) }
+            });
+        assert_eq!(client.server_info().name, "mcp-keepalive-first");
+
+        first_server.abort();
+        let _: Value = client
+            .send_request_retrying("tools/list", json!({}), 1)
+            .await
+            .unwrap();
+        assert_eq!(client.server_info().name, "mcp-keepalive-second");
+
+        // Keepalive pings the new server, and closes the connection when it
+        // doesn't answer.
+        assert_eq!(pings_rx.recv().await.unwrap(), json!("keepalive-1"));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            client.list_tools().await,
+            Err(Error::ChannelClosed)
+        ));
+    };
+
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("Test timed out after 6 seconds");
+}
+
+#[tokio::test]
+async fn test_malformed_initialize_response_is_a_handshake_error() {
+    let test_body = async {
+        let (client_adapter, mut server_adapter) = duplex_pair();
+        // A fake server whose initialize response lacks `capabilities`.
+        let fake_server = tokio::spawn(async move {
+            let raw = server_adapter.recv().await.unwrap().unwrap();
+            let request: Value = serde_json::from_str(&raw).unwrap();
+            let response = json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": {
+                    "protocolVersion": "2024-11-05",
+                    "serverInfo": { "name": "broken", "version": "0" }
+                }
+            });
+            server_adapter.send(&response.to_string()).await.unwrap();
+            server_adapter
+        });
+
+        let err = Client::new(client_adapter).await.err().unwrap();
+        match err {
+            Error::Handshake(msg) => assert!(msg.contains("capabilities"), "got: {}", msg),
+            other => panic!("expected a handshake error, got {:?}", other),
+        }
+        fake_server.await.unwrap();
     };
 
     tokio::time::timeout(Duration::from_secs(6), test_body)
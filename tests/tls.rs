@@ -0,0 +1,111 @@
+//! Integration tests for loading a TLS server config from PEM files.
+#![cfg(feature = "tls")]
+
+use mcp_sdk::{CallToolResult, Client, Content, Server, StdioAdapter, TlsConfig, Tool};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/tls")
+        .join(name)
+}
+
+/// A client config trusting only the self-signed fixture certificate.
+fn client_config() -> Arc<rustls::ClientConfig> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots
+        .add(CertificateDer::from_pem_file(fixture("cert.pem")).unwrap())
+        .unwrap();
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Arc::new(config)
+}
+
+#[tokio::test]
+async fn test_mcp_session_over_tls_from_pem_files() {
+    let test_body = async {
+        let server_config =
+            TlsConfig::from_pem_files(fixture("cert.pem"), fixture("key.pem")).unwrap();
+        let acceptor = TlsAcceptor::from(server_config);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = Server::new("tls-test").register_tool(
+            Tool {
+                name: "echo".to_string(),
+                ..Default::default()
+            },
+            |_handle, args| async move {
+                Ok(CallToolResult {
+                    content: vec![Content::Text {
+                        text: args["text"].as_str().unwrap_or_default().to_string(),
+                        annotations: None,
+                    }],
+                    ..Default::default()
+                })
+            },
+        );
+        let server_task = tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let tls = acceptor.accept(tcp).await.unwrap();
+            let (reader, writer) = tokio::io::split(tls);
+            server
+                .handle_connection(StdioAdapter::from_child(writer, reader))
+                .await
+        });
+
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let tls = TlsConnector::from(client_config())
+            .connect(ServerName::try_from("localhost").unwrap(), tcp)
+            .await
+            .unwrap();
+        let (reader, writer) = tokio::io::split(tls);
+        let client = Client::new(StdioAdapter::from_child(writer, reader))
+            .await
+            .unwrap();
+
+        let result = client
+            .call_tool("echo".to_string(), json!({ "text": "over tls" }))
+            .await
+            .unwrap();
+        match &result.content[0] {
+            Content::Text { text, .. } => assert_eq!(text, "over tls"),
+            other => panic!("expected text content, got {:?}", other),
+        }
+
+        drop(client);
+        server_task.await.unwrap().unwrap();
+    };
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("test timed out");
+}
+
+#[test]
+fn test_from_pem_files_reports_missing_key() {
+    let err = TlsConfig::from_pem_files(fixture("cert.pem"), fixture("missing.pem")).unwrap_err();
+    assert!(matches!(err, mcp_sdk::Error::Io(_)), "got {:?}", err);
+}
+
+#[test]
+fn test_from_pem_files_rejects_a_cert_without_certificates() {
+    // The key file parses as PEM but holds no CERTIFICATE section.
+    let err = TlsConfig::from_pem_files(fixture("key.pem"), fixture("key.pem")).unwrap_err();
+    assert!(
+        err.to_string().contains("no certificates found"),
+        "got {}",
+        err
+    );
+}
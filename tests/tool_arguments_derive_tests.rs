@@ -276,4 +276,35 @@ mod tool_arguments_derive_tests {
             required_fields
         );
     }
+
+    // 9. Argument order follows declaration order, after rename and skip
+    #[derive(ToolArguments)]
+    struct OrderedArgs {
+        zeta: String,
+        #[tool_arg(rename = "alphaName")]
+        alpha: i32,
+        #[tool_arg(skip)]
+        hidden: bool,
+        middle: Option<String>,
+    }
+
+    #[test]
+    fn test_arg_order_follows_declaration_order() {
+        assert_eq!(
+            OrderedArgs::mcp_arg_order(),
+            &["zeta", "alphaName", "middle"]
+        );
+        assert_eq!(BasicArgs::mcp_arg_order(), &["name", "age"]);
+        assert_eq!(SkipArgs::mcp_arg_order(), &["visible"]);
+    }
+
+    #[test]
+    fn test_arg_order_via_descriptor_trait() {
+        use mcp_sdk::ToolArgumentsDescriptor;
+        fn order_of<T: ToolArgumentsDescriptor>() -> &'static [&'static str] {
+            T::mcp_arg_order()
+        }
+        assert_eq!(order_of::<RenameArgs>(), &["userIdentifier"]);
+        assert_eq!(order_of::<OrderedArgs>(), OrderedArgs::mcp_arg_order());
+    }
 }
@@ -0,0 +1,111 @@
+//! End-to-end tests for typed tools: a real `Client` talks to a real `Server`
+//! through the full stack, so the shapes the client expects for `tools/list` and
+//! `tools/call` are checked against what the server actually sends.
+
+use mcp_sdk::{
+    test_util::connected_pair, CallToolResult, Client, ConnectionHandle, Content, NdjsonAdapter,
+    Server, Tool, ToolArguments,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+#[derive(ToolArguments, Deserialize)]
+struct RepeatArgs {
+    #[tool_arg(desc = "The text to repeat.")]
+    text: String,
+    times: u32,
+    #[tool_arg(required = false)]
+    separator: Option<String>,
+}
+
+fn repeat_server() -> Server {
+    Server::new("typed-e2e").register_tool_typed(
+        Tool::from_args::<RepeatArgs>("repeat", Some("Repeats text.")),
+        |_handle: ConnectionHandle, args: RepeatArgs| async move {
+            let separator = args.separator.unwrap_or_else(|| " ".to_string());
+            let repeated = vec![args.text; args.times as usize].join(&separator);
+            Ok(CallToolResult::text(repeated).with_structured(json!({ "times": args.times })))
+        },
+    )
+}
+
+async fn exercise_repeat_tool(client: &Client) {
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.len(), 1);
+    let tool = &tools[0];
+    assert_eq!(tool.name, "repeat");
+    assert_eq!(tool.description.as_deref(), Some("Repeats text."));
+    assert_eq!(tool.input_schema["type"], "object");
+    assert_eq!(
+        tool.input_schema["properties"]["text"]["description"],
+        "The text to repeat."
+    );
+    let required = tool.input_schema["required"].as_array().unwrap();
+    assert!(required.contains(&json!("text")) && required.contains(&json!("times")));
+    assert!(!required.contains(&json!("separator")));
+
+    let result = client
+        .call_tool(
+            "repeat".to_string(),
+            json!({ "text": "ab", "times": 3, "separator": "-" }),
+        )
+        .await
+        .unwrap();
+    assert!(!result.is_error);
+    assert_eq!(
+        result.content,
+        vec![Content::Text {
+            text: "ab-ab-ab".to_string(),
+            annotations: None,
+        }]
+    );
+    assert_eq!(result.structured_content, Some(json!({ "times": 3 })));
+
+    // Arguments that don't deserialize come back as a tool error, not a protocol one.
+    let result = client
+        .call_tool("repeat".to_string(), json!({ "text": "ab" }))
+        .await
+        .unwrap();
+    let message = result.error_message().expect("expected a tool error");
+    assert!(message.contains("missing field `times`"), "got {}", message);
+}
+
+#[tokio::test]
+async fn test_typed_tool_over_duplex() {
+    let test_body = async {
+        let (client, _server_task) = connected_pair(repeat_server()).await.unwrap();
+        exercise_repeat_tool(&client).await;
+    };
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("test timed out");
+}
+
+#[tokio::test]
+async fn test_typed_tool_over_tcp() {
+    let test_body = async {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let listen_addr = addr.clone();
+        tokio::spawn(async move {
+            repeat_server()
+                .tcp_listen::<NdjsonAdapter>(&listen_addr)
+                .await
+        });
+
+        // The listener starts asynchronously; retry until it accepts.
+        let adapter = loop {
+            match NdjsonAdapter::connect(&addr).await {
+                Ok(adapter) => break adapter,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        let client = Client::new(adapter).await.unwrap();
+        exercise_repeat_tool(&client).await;
+    };
+    tokio::time::timeout(Duration::from_secs(6), test_body)
+        .await
+        .expect("test timed out");
+}
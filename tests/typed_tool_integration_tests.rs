@@ -1,11 +1,9 @@
 #[cfg(test)]
 mod typed_tool_integration_tests {
-    use async_trait::async_trait;
     use mcp_sdk::{
         error::{Error as SdkError, Result as SdkResult},
-        network_adapter::NetworkAdapter,
-        protocol::ProtocolConnection,
-        server::{ConnectionHandle as ServerConnectionHandle, Server, ServerSession},
+        network_adapter::{duplex_pair, NetworkAdapter},
+        server::{ConnectionHandle as ServerConnectionHandle, Server},
         types::{
             CallToolParams, CallToolResult, Content, JSONRPCResponse, Request, RequestId, Tool,
         }, // Removed unused Notification, Response
@@ -13,11 +11,7 @@ mod typed_tool_integration_tests {
     };
     use serde::Deserialize; // Removed unused Serialize, de::DeserializeOwned
     use serde_json::{json, Value};
-    use std::{
-        collections::VecDeque,
-        // Removed unused Future, Pin
-        sync::{Arc, Mutex},
-    };
+    use std::sync::Arc;
 
     // --- Test Structs ---
     #[derive(ToolArguments, Deserialize, Debug, PartialEq, Clone)]
@@ -33,43 +27,6 @@ mod typed_tool_integration_tests {
         value: Option<i32>,
     }
 
-    // --- Mock Infrastructure (adapted from server/session.rs tests) ---
-    #[derive(Default, Clone)]
-    struct MockAdapter {
-        incoming: Arc<Mutex<VecDeque<String>>>,
-        outgoing: Arc<Mutex<VecDeque<String>>>,
-    }
-
-    impl MockAdapter {
-        fn new() -> Self {
-            Default::default()
-        }
-
-        fn push_incoming(&self, msg: String) {
-            self.incoming.lock().unwrap().push_back(msg);
-        }
-
-        fn pop_outgoing(&self) -> Option<String> {
-            self.outgoing.lock().unwrap().pop_front()
-        }
-
-        #[allow(dead_code)] // May be useful for debugging later
-        fn outgoing_len(&self) -> usize {
-            self.outgoing.lock().unwrap().len()
-        }
-    }
-
-    #[async_trait]
-    impl NetworkAdapter for MockAdapter {
-        async fn send(&mut self, msg: &str) -> SdkResult<()> {
-            self.outgoing.lock().unwrap().push_back(msg.to_string());
-            Ok(())
-        }
-        async fn recv(&mut self) -> SdkResult<Option<String>> {
-            Ok(self.incoming.lock().unwrap().pop_front())
-        }
-    }
-
     struct TestServerHarness {
         server: Arc<Server>,
     }
@@ -87,7 +44,9 @@ mod typed_tool_integration_tests {
         ) -> SdkResult<Option<String>> {
             // Returns raw JSON string of the first response after init
 
-            let adapter = MockAdapter::new();
+            let (mut client, server_end) = duplex_pair();
+            let server = Server::clone(&self.server);
+            let session = tokio::spawn(async move { server.handle_connection(server_end).await });
 
             // Standard Initialize Request
             let init_req_params = json!({
@@ -101,31 +60,28 @@ mod typed_tool_integration_tests {
                 method: "initialize".to_string(),
                 params: Some(init_req_params),
             };
-            let init_req_json = serde_json::to_string(&init_req)?;
-            adapter.push_incoming(init_req_json);
+            client.send(&serde_json::to_string(&init_req)?).await?;
+            client
+                .send(
+                    &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }).to_string(),
+                )
+                .await?;
 
             // Actual request from the test
-            adapter.push_incoming(request_json);
-
-            let session_runner = ServerSession::new(
-                ProtocolConnection::new(adapter.clone()), // Clone adapter for session
-                Arc::clone(&self.server),
-            );
+            client.send(&request_json).await?;
 
-            match tokio::time::timeout(std::time::Duration::from_secs(3), session_runner.run())
+            let responses = async {
+                let _init_response_json = client.recv().await?;
+                client.recv().await // The actual tool response
+            };
+            let response = tokio::time::timeout(std::time::Duration::from_secs(3), responses)
                 .await
-            {
-                Ok(Ok(_)) => {
-                    // Session completed
-                    let _init_response_json = adapter.pop_outgoing(); // Pop init response
-                                                                      // assert!(_init_response_json.is_some(), "Harness expected init response");
-                    Ok(adapter.pop_outgoing()) // Return the actual tool response
-                }
-                Ok(Err(e)) => Err(e),
-                Err(_) => Err(SdkError::Other(
-                    "TestServerHarness: session run timed out".to_string(),
-                )),
-            }
+                .map_err(|_| {
+                    SdkError::Other("TestServerHarness: session run timed out".to_string())
+                })??;
+            drop(client);
+            session.await.unwrap()?;
+            Ok(response)
         }
 
         async fn call_tool(
@@ -137,6 +93,7 @@ mod typed_tool_integration_tests {
             let call_params = CallToolParams {
                 name: tool_name.to_string(),
                 arguments: args,
+                meta: None,
             };
             let request = Request {
                 jsonrpc: "2.0".to_string(),
@@ -158,8 +115,10 @@ mod typed_tool_integration_tests {
                 Ok(CallToolResult {
                     content: vec![Content::Text {
                         text: format!("msg: {}, count: {}", args.message, args.count),
+                        annotations: None,
                     }],
                     is_error: false,
+                    ..Default::default()
                 })
             },
         );
@@ -186,7 +145,8 @@ mod typed_tool_integration_tests {
                 assert_eq!(
                     tool_result.content,
                     vec![Content::Text {
-                        text: "msg: hello, count: 42".into()
+                        text: "msg: hello, count: 42".into(),
+                        annotations: None,
                     }]
                 );
             }
@@ -226,7 +186,7 @@ mod typed_tool_integration_tests {
                     tool_result.is_error,
                     "Expected CallToolResult.is_error to be true for missing args"
                 );
-                if let Some(Content::Text { text }) = tool_result.content.get(0) {
+                if let Some(Content::Text { text, .. }) = tool_result.content.first() {
                     assert!(
                         text.contains("Invalid arguments for tool 'check_simple_missing'"),
                         "Error message prefix mismatch. Got: {}",
@@ -283,7 +243,7 @@ mod typed_tool_integration_tests {
                     tool_result.is_error,
                     "Expected CallToolResult.is_error to be true for wrong arg type"
                 );
-                if let Some(Content::Text { text }) = tool_result.content.get(0) {
+                if let Some(Content::Text { text, .. }) = tool_result.content.first() {
                     assert!(
                         text.contains("Invalid arguments for tool 'check_simple_type_wrong'"),
                         "Error message prefix mismatch. Got: {}",
@@ -307,6 +267,44 @@ mod typed_tool_integration_tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_typed_tool_errors_include_pretty_schema() {
+        let tool =
+            Tool::from_args::<SimpleTypedArgs>("schema_in_errors", Some("Reports bad args."));
+        let expected_schema = serde_json::to_string_pretty(&tool.input_schema).unwrap();
+        let server = Server::new("test-server-typed-schema").register_tool_typed(
+            tool,
+            |_handle: ServerConnectionHandle, _args: SimpleTypedArgs| async move {
+                Ok(CallToolResult::default())
+            },
+        );
+
+        // The schema is rendered once at registration; every failed call reuses it.
+        let harness = TestServerHarness::new(server);
+        for (id, args) in [(1, json!({})), (2, json!({ "message": 1, "count": 2 }))] {
+            let response_json_str = harness
+                .call_tool("schema_in_errors", args, id)
+                .await
+                .unwrap()
+                .expect("Expected a response for schema_in_errors tool call");
+            let response_value: JSONRPCResponse<CallToolResult> =
+                serde_json::from_str(&response_json_str).unwrap();
+            let JSONRPCResponse::Success(res) = response_value else {
+                panic!("Expected a CallToolResult, got {:?}", response_value);
+            };
+            assert!(res.result.is_error);
+            assert!(
+                matches!(
+                    res.result.content.first(),
+                    Some(Content::Text { text, .. })
+                        if text.ends_with(&format!("Expected schema: {}", expected_schema))
+                ),
+                "Unexpected error content: {:?}",
+                res.result.content
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_typed_tool_optional_args() {
         let server_config = Server::new("test-server-typed-optional") // Define server config once
@@ -320,8 +318,10 @@ mod typed_tool_integration_tests {
                     Ok(CallToolResult {
                         content: vec![Content::Text {
                             text: format!("id: {}, value: {}", args.id, val_str),
+                            annotations: None,
                         }],
                         is_error: false,
+                        ..Default::default()
                     })
                 },
             );
@@ -346,7 +346,8 @@ mod typed_tool_integration_tests {
                 assert_eq!(
                     res.result.content,
                     vec![Content::Text {
-                        text: "id: id1, value: 123".into()
+                        text: "id: id1, value: 123".into(),
+                        annotations: None,
                     }]
                 );
             }
@@ -375,7 +376,8 @@ mod typed_tool_integration_tests {
                 assert_eq!(
                     res.result.content,
                     vec![Content::Text {
-                        text: "id: id2, value: None".into()
+                        text: "id: id2, value: None".into(),
+                        annotations: None,
                     }]
                 );
             }
@@ -384,4 +386,115 @@ mod typed_tool_integration_tests {
             }
         }
     }
+
+    /// Accepts numbers given as strings, e.g. `"7"`, for `count`.
+    fn coerce_simple_args(args: Value) -> SdkResult<SimpleTypedArgs> {
+        let message = args["message"]
+            .as_str()
+            .ok_or_else(|| SdkError::Other("missing `message`".to_string()))?
+            .to_string();
+        let count = match &args["count"] {
+            Value::String(s) => s
+                .parse::<i32>()
+                .map_err(|e| SdkError::Other(format!("bad `count`: {}", e)))?,
+            other => serde_json::from_value(other.clone())?,
+        };
+        Ok(SimpleTypedArgs { message, count })
+    }
+
+    #[tokio::test]
+    async fn test_typed_tool_with_custom_deserializer_coerces_strings() {
+        let server = Server::new("test-server-typed-with").register_tool_typed_with(
+            Tool::from_args::<SimpleTypedArgs>("coerce", Some("Coerces its arguments.")),
+            coerce_simple_args,
+            |_handle: ServerConnectionHandle, args: SimpleTypedArgs| async move {
+                Ok(CallToolResult::text(format!(
+                    "{} x{}",
+                    args.message, args.count
+                )))
+            },
+        );
+        let harness = TestServerHarness::new(server);
+
+        let response_json_str = harness
+            .call_tool("coerce", json!({"message": "hi", "count": "7"}), 6)
+            .await
+            .unwrap()
+            .expect("Expected a response for coerce tool call");
+        let response: JSONRPCResponse<CallToolResult> =
+            serde_json::from_str(&response_json_str).unwrap();
+        match response {
+            JSONRPCResponse::Success(res) => {
+                assert_eq!(res.result, CallToolResult::text("hi x7"));
+            }
+            JSONRPCResponse::Error(err) => panic!("Expected success, got error: {:?}", err),
+        }
+
+        let response_json_str = harness
+            .call_tool("coerce", json!({"message": "hi", "count": "seven"}), 7)
+            .await
+            .unwrap()
+            .expect("Expected a response for coerce tool call");
+        let response: JSONRPCResponse<CallToolResult> =
+            serde_json::from_str(&response_json_str).unwrap();
+        match response {
+            JSONRPCResponse::Success(res) => {
+                let message = res
+                    .result
+                    .error_message()
+                    .expect("Expected an error result");
+                assert!(message.contains("bad `count`"), "Got: {}", message);
+            }
+            JSONRPCResponse::Error(err) => panic!("Expected a tool error, got: {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_tool_positional_args_match_named_args() {
+        let server = Server::new("test-server-typed-positional").register_tool_typed_positional(
+            Tool::from_args::<OptionalTypedArgs>("lookup", Some("Looks up a value.")),
+            |_handle: ServerConnectionHandle, args: OptionalTypedArgs| async move {
+                Ok(CallToolResult::text(format!("{:?}", args)))
+            },
+        );
+        let harness = TestServerHarness::new(server);
+
+        let mut results = Vec::new();
+        for (id, args) in [
+            (1, json!({"id": "abc", "value": 5})),
+            (2, json!(["abc", 5])),
+            (3, json!({"id": "abc"})),
+            (4, json!(["abc"])),
+            (5, json!(["abc", 5, "extra"])),
+        ] {
+            let response_json_str = harness
+                .call_tool("lookup", args, id)
+                .await
+                .unwrap()
+                .expect("Expected a response for lookup tool call");
+            match serde_json::from_str(&response_json_str).unwrap() {
+                JSONRPCResponse::Success(res) => results.push(res.result),
+                JSONRPCResponse::<CallToolResult>::Error(err) => {
+                    panic!("Expected success, got error: {:?}", err)
+                }
+            }
+        }
+
+        let expected = OptionalTypedArgs {
+            id: "abc".to_string(),
+            value: Some(5),
+        };
+        assert_eq!(results[0], CallToolResult::text(format!("{:?}", expected)));
+        assert_eq!(results[1], results[0]);
+        assert_eq!(results[3], results[2]);
+        assert!(!results[2].is_error);
+        let message = results[4]
+            .error_message()
+            .expect("Expected an error result");
+        assert!(
+            message.contains("expected at most 2 positional arguments"),
+            "Got: {}",
+            message
+        );
+    }
 }